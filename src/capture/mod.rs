@@ -0,0 +1,26 @@
+//! Reading and writing packet captures of GDL90 traffic
+
+pub mod pcap;
+
+pub use self::pcap::*;
+
+use std::io::Read;
+
+use crate::prelude::*;
+
+/// Anything bigger is a corrupt file, not a packet
+pub(crate) const MAX_RECORD_LEN: usize = 16 * 1024 * 1024;
+
+/// Read a record of `len` bytes, `invalid` makes the error for a length over `MAX_RECORD_LEN`
+pub(crate) fn read_record(
+    reader: &mut impl Read,
+    len: usize,
+    invalid: fn(String) -> GDL90Error,
+) -> GDL90Result<Vec<u8>> {
+    if len > MAX_RECORD_LEN {
+        bail!(invalid(format!("record of {len} bytes")));
+    }
+    let mut data = vec![0; len];
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
@@ -0,0 +1,758 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{capture::read_record, prelude::*};
+
+const PCAP_MICROS: u32 = 0xA1B2_C3D4;
+const PCAP_NANOS: u32 = 0xA1B2_3C4D;
+const PCAPNG_SECTION: u32 = 0x0A0D_0D0A;
+const PCAPNG_BYTE_ORDER: u32 = 0x1A2B_3C4D;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+const ETHERTYPE_VLAN: [u16; 2] = [0x8100, 0x88A8];
+const IP_PROTO_UDP: u8 = 17;
+
+/// A UDP datagram from a capture file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedPacket {
+    pub timestamp: DateTime<Utc>,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    /// UDP payload, the GDL90 frames
+    pub payload: Vec<u8>,
+}
+
+impl CapturedPacket {
+    #[must_use]
+    pub fn messages(&self) -> Vec<GDL90Result<Message>> {
+        Message::from_gdl90_bytes(&self.payload)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Interface {
+    linktype: u32,
+    /// Timestamp units per second
+    units_per_second: u64,
+}
+
+#[derive(Debug)]
+enum Format {
+    Pcap {
+        big_endian: bool,
+        interface: Interface,
+    },
+    PcapNg {
+        big_endian: bool,
+        interfaces: Vec<Interface>,
+    },
+}
+
+/// Reads the UDP packets of a pcap or pcapng capture, e.g. saved by Wireshark or tcpdump.
+///
+/// Ethernet, VLAN, Linux cooked (SLL, SLL2), loopback and raw IP link types are understood,
+/// other packets and non-UDP traffic are skipped. IP fragments are skipped as well.
+///
+/// ```ignore
+/// let reader = PcapReader::new(File::open("issue.pcapng")?)?.with_port(4000);
+/// for packet in reader {
+///     let packet = packet?;
+///     for message in packet.messages() { /* ... */ }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PcapReader<R> {
+    reader: R,
+    format: Format,
+    port: Option<u16>,
+    done: bool,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Read the file header, pcap and pcapng are told apart by their magic number.
+    ///
+    /// # Errors
+    ///
+    /// If the file is neither pcap nor pcapng, or can't be read.
+    pub fn new(mut reader: R) -> GDL90Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+
+        let format = if u32::from_le_bytes(magic) == PCAPNG_SECTION {
+            let big_endian = Self::section_header(&mut reader)?;
+            Format::PcapNg {
+                big_endian,
+                interfaces: Vec::new(),
+            }
+        } else {
+            let (big_endian, nanos) = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+                (PCAP_MICROS, _) => (false, false),
+                (PCAP_NANOS, _) => (false, true),
+                (_, PCAP_MICROS) => (true, false),
+                (_, PCAP_NANOS) => (true, true),
+                _ => return Err(invalid(format!("unknown file magic {magic:02X?}"))),
+            };
+
+            let mut header = [0; 20];
+            reader.read_exact(&mut header)?;
+            Format::Pcap {
+                big_endian,
+                interface: Interface {
+                    linktype: u32_at(&header, 16, big_endian) & 0x0FFF_FFFF,
+                    units_per_second: if nanos { 1_000_000_000 } else { 1_000_000 },
+                },
+            }
+        };
+
+        Ok(Self {
+            reader,
+            format,
+            port: None,
+            done: false,
+        })
+    }
+
+    /// Only packets from or to `port`
+    #[must_use]
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Rest of a pcapng section header block after the block type, returns the byte order
+    fn section_header(reader: &mut R) -> GDL90Result<bool> {
+        let mut head = [0; 8];
+        reader.read_exact(&mut head)?;
+        let big_endian = match u32::from_le_bytes([head[4], head[5], head[6], head[7]]) {
+            PCAPNG_BYTE_ORDER => false,
+            magic if magic.swap_bytes() == PCAPNG_BYTE_ORDER => true,
+            _ => return Err(invalid("invalid pcapng byte order magic".to_string())),
+        };
+
+        let len = u32_at(&head, 0, big_endian) as usize;
+        if len < 12 + 16 {
+            bail!(invalid(format!("section header block too short: {len}")));
+        }
+        // version, section length and options are of no interest
+        Self::skip(reader, len - 12)?;
+        Ok(big_endian)
+    }
+
+    fn skip(reader: &mut R, len: usize) -> GDL90Result<()> {
+        let skipped = std::io::copy(&mut reader.by_ref().take(len as u64), &mut std::io::sink())?;
+        if skipped < len as u64 {
+            bail!(invalid("truncated file".to_string()));
+        }
+        Ok(())
+    }
+
+    /// `Ok(false)` at the end of the file, an error if it ends within `buf`
+    fn fill(&mut self, buf: &mut [u8]) -> GDL90Result<bool> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.reader.read(&mut buf[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return Err(invalid("truncated file".to_string())),
+                Ok(n) => filled += n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(true)
+    }
+
+    fn read_vec(&mut self, len: usize) -> GDL90Result<Vec<u8>> {
+        read_record(&mut self.reader, len, invalid)
+    }
+
+    /// Next link layer frame with its timestamp and link type
+    fn next_frame(&mut self) -> GDL90Result<Option<(DateTime<Utc>, u32, Vec<u8>)>> {
+        match &self.format {
+            &Format::Pcap {
+                big_endian,
+                interface,
+            } => {
+                let mut header = [0; 16];
+                if !self.fill(&mut header)? {
+                    return Ok(None);
+                }
+                let seconds = u32_at(&header, 0, big_endian);
+                let fraction = u32_at(&header, 4, big_endian);
+                let captured = u32_at(&header, 8, big_endian) as usize;
+
+                let data = self.read_vec(captured)?;
+                let timestamp = timestamp(
+                    u64::from(seconds) * interface.units_per_second + u64::from(fraction),
+                    interface.units_per_second,
+                );
+                Ok(Some((timestamp, interface.linktype, data)))
+            }
+            Format::PcapNg { .. } => self.next_block(),
+        }
+    }
+
+    fn next_block(&mut self) -> GDL90Result<Option<(DateTime<Utc>, u32, Vec<u8>)>> {
+        loop {
+            let mut block_type = [0; 4];
+            if !self.fill(&mut block_type)? {
+                return Ok(None);
+            }
+
+            if u32::from_le_bytes(block_type) == PCAPNG_SECTION {
+                let big_endian = Self::section_header(&mut self.reader)?;
+                self.format = Format::PcapNg {
+                    big_endian,
+                    interfaces: Vec::new(),
+                };
+                continue;
+            }
+
+            let Format::PcapNg { big_endian, .. } = self.format else {
+                return Ok(None);
+            };
+            let mut len = [0; 4];
+            self.reader.read_exact(&mut len)?;
+            let len = u32_at(&len, 0, big_endian) as usize;
+            if len < 12 || len % 4 != 0 {
+                bail!(invalid(format!("invalid pcapng block length {len}")));
+            }
+            // body and the trailing copy of the length
+            let body = self.read_vec(len - 8)?;
+            let body = &body[..len - 12];
+
+            let Format::PcapNg { interfaces, .. } = &mut self.format else {
+                return Ok(None);
+            };
+            match u32_at(&block_type, 0, big_endian) {
+                // interface description
+                1 if body.len() >= 8 => interfaces.push(Interface {
+                    linktype: u32::from(u16_at(body, 0, big_endian)),
+                    units_per_second: tsresol(&body[8..], big_endian),
+                }),
+                // enhanced packet
+                6 if body.len() >= 20 => {
+                    let interface = u32_at(body, 0, big_endian) as usize;
+                    let Some(&interface) = interfaces.get(interface) else {
+                        bail!(invalid(format!("packet on unknown interface {interface}")));
+                    };
+                    let units = (u64::from(u32_at(body, 4, big_endian)) << 32)
+                        | u64::from(u32_at(body, 8, big_endian));
+                    let captured = u32_at(body, 12, big_endian) as usize;
+                    let data = body
+                        .get(20..20 + captured)
+                        .ok_or_else(|| invalid("enhanced packet block too short".to_string()))?;
+                    return Ok(Some((
+                        timestamp(units, interface.units_per_second),
+                        interface.linktype,
+                        data.to_vec(),
+                    )));
+                }
+                // simple packet, no timestamp
+                3 if body.len() >= 4 => {
+                    let Some(&interface) = interfaces.first() else {
+                        bail!(invalid("packet before interface description".to_string()));
+                    };
+                    let captured = (u32_at(body, 0, big_endian) as usize).min(body.len() - 4);
+                    return Ok(Some((
+                        DateTime::UNIX_EPOCH,
+                        interface.linktype,
+                        body[4..4 + captured].to_vec(),
+                    )));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = GDL90Result<CapturedPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.next_frame() {
+                Ok(Some((timestamp, linktype, frame))) => {
+                    let Some(packet) = udp_packet(timestamp, linktype, &frame) else {
+                        continue;
+                    };
+                    if self.port.is_none_or(|port| {
+                        packet.source.port() == port || packet.destination.port() == port
+                    }) {
+                        return Some(Ok(packet));
+                    }
+                }
+                Ok(None) => self.done = true,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Writes UDP packets as a pcap capture (nanosecond timestamps, Ethernet link type)
+/// that opens in Wireshark.
+///
+/// ```ignore
+/// let mut writer = PcapWriter::new(File::create("repro.pcap")?)?;
+/// writer.write_messages(Utc::now(), source, destination, [heartbeat.into(), ownship.into()])?;
+/// ```
+#[derive(Debug)]
+pub struct PcapWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Write the file header
+    ///
+    /// # Errors
+    ///
+    /// If writing fails.
+    pub fn new(mut writer: W) -> GDL90Result<Self> {
+        let mut header = Vec::with_capacity(24);
+        header.extend(PCAP_NANOS.to_le_bytes());
+        header.extend(2u16.to_le_bytes());
+        header.extend(4u16.to_le_bytes());
+        // time zone and timestamp accuracy
+        header.extend([0; 8]);
+        header.extend(65_535u32.to_le_bytes());
+        header.extend(LINKTYPE_ETHERNET.to_le_bytes());
+        writer.write_all(&header)?;
+
+        Ok(Self { writer })
+    }
+
+    /// # Errors
+    ///
+    /// If the addresses are not both IPv4 or both IPv6, the payload doesn't fit into
+    /// a UDP datagram, or writing fails.
+    pub fn write_packet(&mut self, packet: &CapturedPacket) -> GDL90Result<()> {
+        let frame = ethernet_frame(packet)?;
+        let (Ok(seconds), Ok(len)) = (
+            u32::try_from(packet.timestamp.timestamp()),
+            u32::try_from(frame.len()),
+        ) else {
+            bail!(invalid(format!(
+                "timestamp {} can't be written",
+                packet.timestamp
+            )));
+        };
+
+        let mut record = Vec::with_capacity(16 + frame.len());
+        record.extend(seconds.to_le_bytes());
+        record.extend(packet.timestamp.timestamp_subsec_nanos().to_le_bytes());
+        record.extend(len.to_le_bytes());
+        record.extend(len.to_le_bytes());
+        record.extend(frame);
+        self.writer.write_all(&record)?;
+        Ok(())
+    }
+
+    /// Encode `messages` into one datagram and write it
+    ///
+    /// # Errors
+    ///
+    /// If a message can't be encoded, or see `write_packet()`.
+    pub fn write_messages(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        source: SocketAddr,
+        destination: SocketAddr,
+        messages: impl IntoIterator<Item = Message>,
+    ) -> GDL90Result<()> {
+        let mut payload = Vec::new();
+        for message in messages {
+            payload.extend(message.into_gdl90_bytes()?);
+        }
+        self.write_packet(&CapturedPacket {
+            timestamp,
+            source,
+            destination,
+            payload,
+        })
+    }
+
+    /// # Errors
+    ///
+    /// If flushing fails.
+    pub fn flush(&mut self) -> GDL90Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    #[must_use]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+fn invalid(reason: String) -> GDL90Error {
+    GDL90Error::InvalidCapture(reason)
+}
+
+fn u16_at(bytes: &[u8], offset: usize, big_endian: bool) -> u16 {
+    let b = [bytes[offset], bytes[offset + 1]];
+    if big_endian {
+        u16::from_be_bytes(b)
+    } else {
+        u16::from_le_bytes(b)
+    }
+}
+
+fn u32_at(bytes: &[u8], offset: usize, big_endian: bool) -> u32 {
+    let b = [
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ];
+    if big_endian {
+        u32::from_be_bytes(b)
+    } else {
+        u32::from_le_bytes(b)
+    }
+}
+
+fn timestamp(units: u64, units_per_second: u64) -> DateTime<Utc> {
+    let units_per_second = units_per_second.max(1);
+    let seconds = i64::try_from(units / units_per_second).unwrap_or(i64::MAX);
+    let nanos = u128::from(units % units_per_second) * 1_000_000_000 / u128::from(units_per_second);
+    DateTime::from_timestamp(seconds, u32::try_from(nanos).unwrap_or(0))
+        .unwrap_or(DateTime::UNIX_EPOCH)
+}
+
+/// `if_tsresol` from the interface description options, microseconds if absent
+fn tsresol(mut options: &[u8], big_endian: bool) -> u64 {
+    while options.len() >= 4 {
+        let code = u16_at(options, 0, big_endian);
+        let len = usize::from(u16_at(options, 2, big_endian));
+        if code == 0 {
+            break;
+        }
+        if code == 9
+            && len == 1
+            && let Some(&resolution) = options.get(4)
+        {
+            let exponent = u32::from(resolution & 0x7F);
+            let base: u64 = if resolution & 0x80 == 0 { 10 } else { 2 };
+            return base.checked_pow(exponent).unwrap_or(1_000_000);
+        }
+        options = options
+            .get(4 + len.next_multiple_of(4)..)
+            .unwrap_or_default();
+    }
+    1_000_000
+}
+
+/// IP packet in a link layer frame
+fn ip_packet(linktype: u32, frame: &[u8]) -> Option<&[u8]> {
+    let ethertype_at = move |offset: usize| {
+        let ethertype = u16::from_be_bytes([*frame.get(offset)?, *frame.get(offset + 1)?]);
+        matches!(ethertype, ETHERTYPE_IPV4 | ETHERTYPE_IPV6).then(|| frame.get(offset + 2..))?
+    };
+
+    match linktype {
+        LINKTYPE_NULL => frame.get(4..),
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            while frame
+                .get(offset..offset + 2)
+                .is_some_and(|t| ETHERTYPE_VLAN.contains(&u16::from_be_bytes([t[0], t[1]])))
+            {
+                offset += 4;
+            }
+            ethertype_at(offset)
+        }
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => Some(frame),
+        LINKTYPE_LINUX_SLL => ethertype_at(14),
+        LINKTYPE_LINUX_SLL2 => ethertype_at(0).and_then(|_| frame.get(20..)),
+        _ => None,
+    }
+}
+
+fn udp_packet(timestamp: DateTime<Utc>, linktype: u32, frame: &[u8]) -> Option<CapturedPacket> {
+    let ip = ip_packet(linktype, frame)?;
+    let (source, destination, udp) = match ip.first()? >> 4 {
+        4 => {
+            let header_len = usize::from(ip[0] & 0x0F) * 4;
+            let total_len = usize::from(u16::from_be_bytes([*ip.get(2)?, *ip.get(3)?]));
+            let fragment = u16::from_be_bytes([*ip.get(6)?, *ip.get(7)?]) & 0x3FFF;
+            if *ip.get(9)? != IP_PROTO_UDP || fragment != 0 {
+                return None;
+            }
+            let source: [u8; 4] = ip.get(12..16)?.try_into().ok()?;
+            let destination: [u8; 4] = ip.get(16..20)?.try_into().ok()?;
+            (
+                IpAddr::from(source),
+                IpAddr::from(destination),
+                ip.get(header_len..total_len.min(ip.len()))?,
+            )
+        }
+        6 => {
+            if *ip.get(6)? != IP_PROTO_UDP {
+                return None;
+            }
+            let payload_len = usize::from(u16::from_be_bytes([*ip.get(4)?, *ip.get(5)?]));
+            let source: [u8; 16] = ip.get(8..24)?.try_into().ok()?;
+            let destination: [u8; 16] = ip.get(24..40)?.try_into().ok()?;
+            (
+                IpAddr::from(source),
+                IpAddr::from(destination),
+                ip.get(40..(40 + payload_len).min(ip.len()))?,
+            )
+        }
+        _ => return None,
+    };
+
+    let source_port = u16::from_be_bytes([*udp.first()?, *udp.get(1)?]);
+    let destination_port = u16::from_be_bytes([*udp.get(2)?, *udp.get(3)?]);
+    let udp_len = usize::from(u16::from_be_bytes([*udp.get(4)?, *udp.get(5)?]));
+
+    Some(CapturedPacket {
+        timestamp,
+        source: SocketAddr::new(source, source_port),
+        destination: SocketAddr::new(destination, destination_port),
+        payload: udp.get(8..udp_len.clamp(8, udp.len()))?.to_vec(),
+    })
+}
+
+/// Internet checksum over the concatenated `parts`
+fn checksum(parts: &[&[u8]]) -> u16 {
+    let bytes = parts.concat();
+    let sum = bytes
+        .chunks(2)
+        .map(|c| u32::from(u16::from_be_bytes([c[0], c.get(1).copied().unwrap_or(0)])))
+        .fold(0u32, |acc, word| {
+            let acc = acc + word;
+            (acc & 0xFFFF) + (acc >> 16)
+        });
+    !(sum as u16)
+}
+
+fn ethernet_frame(packet: &CapturedPacket) -> GDL90Result<Vec<u8>> {
+    let udp_len = u16::try_from(packet.payload.len() + 8)
+        .map_err(|_| invalid(format!("{} byte payload", packet.payload.len())))?;
+    let mut udp = Vec::with_capacity(usize::from(udp_len));
+    udp.extend(packet.source.port().to_be_bytes());
+    udp.extend(packet.destination.port().to_be_bytes());
+    udp.extend(udp_len.to_be_bytes());
+    udp.extend([0, 0]);
+    udp.extend(&packet.payload);
+
+    let (ethertype, ip) = match (packet.source.ip(), packet.destination.ip()) {
+        (IpAddr::V4(source), IpAddr::V4(destination)) => {
+            (ETHERTYPE_IPV4, ipv4(source, destination, udp)?)
+        }
+        (IpAddr::V6(source), IpAddr::V6(destination)) => {
+            (ETHERTYPE_IPV6, ipv6(source, destination, udp))
+        }
+        _ => {
+            bail!(invalid(
+                "source and destination must both be IPv4 or IPv6".to_string()
+            ))
+        }
+    };
+
+    let mut frame = Vec::with_capacity(14 + ip.len());
+    frame.extend([0xFF; 6]);
+    frame.extend([0x02, 0, 0, 0, 0, 0x01]);
+    frame.extend(ethertype.to_be_bytes());
+    frame.extend(ip);
+    Ok(frame)
+}
+
+fn ipv4(source: Ipv4Addr, destination: Ipv4Addr, mut udp: Vec<u8>) -> GDL90Result<Vec<u8>> {
+    let total_len = u16::try_from(udp.len() + 20)
+        .map_err(|_| invalid(format!("{} byte datagram", udp.len())))?;
+
+    let pseudo = [
+        &source.octets()[..],
+        &destination.octets(),
+        &[0, IP_PROTO_UDP],
+        &udp[4..6],
+    ]
+    .concat();
+    let udp_checksum = match checksum(&[&pseudo, &udp]) {
+        // 0 means no checksum
+        0 => 0xFFFF,
+        sum => sum,
+    };
+    udp[6..8].copy_from_slice(&udp_checksum.to_be_bytes());
+
+    let mut ip = Vec::with_capacity(usize::from(total_len));
+    ip.extend([0x45, 0]);
+    ip.extend(total_len.to_be_bytes());
+    // identification, flags and fragment offset, TTL, protocol, checksum
+    ip.extend([0, 0, 0, 0, 64, IP_PROTO_UDP, 0, 0]);
+    ip.extend(source.octets());
+    ip.extend(destination.octets());
+    let ip_checksum = checksum(&[&ip]);
+    ip[10..12].copy_from_slice(&ip_checksum.to_be_bytes());
+    ip.extend(udp);
+    Ok(ip)
+}
+
+fn ipv6(source: Ipv6Addr, destination: Ipv6Addr, mut udp: Vec<u8>) -> Vec<u8> {
+    let pseudo = [
+        &source.octets()[..],
+        &destination.octets(),
+        &[0, 0, udp[4], udp[5], 0, 0, 0, IP_PROTO_UDP],
+    ]
+    .concat();
+    let udp_checksum = match checksum(&[&pseudo, &udp]) {
+        0 => 0xFFFF,
+        sum => sum,
+    };
+    udp[6..8].copy_from_slice(&udp_checksum.to_be_bytes());
+
+    let mut ip = Vec::with_capacity(40 + udp.len());
+    ip.extend([0x60, 0, 0, 0]);
+    ip.extend(&udp[4..6]);
+    // next header, hop limit
+    ip.extend([IP_PROTO_UDP, 64]);
+    ip.extend(source.octets());
+    ip.extend(destination.octets());
+    ip.extend(udp);
+    ip
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(nanos: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_nanos(1_760_000_000_000_000_000 + nanos)
+    }
+
+    fn heartbeat() -> Message {
+        Heartbeat::default()
+            .with_uat_initialized()
+            .with_timestamp(3600)
+            .into()
+    }
+
+    fn packet(source: &str, destination: &str, n: i64) -> CapturedPacket {
+        CapturedPacket {
+            timestamp: at(n * 1_000_000_123),
+            source: source.parse().unwrap(),
+            destination: destination.parse().unwrap(),
+            payload: heartbeat().into_gdl90_bytes().unwrap(),
+        }
+    }
+
+    #[test]
+    fn pcap_round_trip() {
+        let packets = [
+            packet("192.168.10.1:4001", "192.168.10.255:4000", 0),
+            packet("192.168.10.1:5000", "192.168.10.2:5001", 1),
+            packet("[fe80::1]:4001", "[ff02::1]:4000", 2),
+        ];
+
+        let mut writer = PcapWriter::new(Vec::new()).unwrap();
+        for packet in &packets {
+            writer.write_packet(packet).unwrap();
+        }
+        let file = writer.into_inner();
+
+        let read = PcapReader::new(file.as_slice())
+            .unwrap()
+            .collect::<GDL90Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read, packets);
+
+        let read = PcapReader::new(file.as_slice())
+            .unwrap()
+            .with_port(4000)
+            .collect::<GDL90Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[1].source, packets[2].source);
+        assert_eq!(read[0].messages()[0].as_ref().unwrap(), &heartbeat());
+    }
+
+    #[test]
+    fn checksums() {
+        let frame = ethernet_frame(&packet("10.0.0.1:4001", "10.0.0.2:4000", 0)).unwrap();
+        let ip = &frame[14..];
+        assert_eq!(checksum(&[&ip[..20]]), 0);
+
+        let udp = &ip[20..];
+        let pseudo = [&ip[12..20], &[0, IP_PROTO_UDP], &udp[4..6]].concat();
+        assert_eq!(checksum(&[&pseudo, udp]), 0);
+
+        let mixed = packet("10.0.0.1:4001", "[::1]:4000", 0);
+        assert!(matches!(
+            ethernet_frame(&mixed),
+            Err(GDL90Error::InvalidCapture(_))
+        ));
+    }
+
+    /// pcapng block with the given type and body, little endian
+    fn block(block_type: u32, body: &[u8]) -> Vec<u8> {
+        let len = u32::try_from(12 + body.len().next_multiple_of(4)).unwrap();
+        let mut block = block_type.to_le_bytes().to_vec();
+        block.extend(len.to_le_bytes());
+        block.extend(body);
+        block.resize(usize::try_from(len).unwrap() - 4, 0);
+        block.extend(len.to_le_bytes());
+        block
+    }
+
+    #[test]
+    fn pcapng() {
+        let packet = packet("192.168.10.1:4001", "192.168.10.255:4000", 1);
+        let frame = ethernet_frame(&packet).unwrap();
+
+        let mut shb = PCAPNG_BYTE_ORDER.to_le_bytes().to_vec();
+        shb.extend([1, 0, 0, 0]);
+        shb.extend((-1i64).to_le_bytes());
+
+        // Ethernet, if_tsresol = 10^-9
+        let mut idb = vec![1, 0, 0, 0, 0, 0, 0, 0];
+        idb.extend([9, 0, 1, 0, 9, 0, 0, 0]);
+        idb.extend([0, 0, 0, 0]);
+
+        let units = u64::try_from(packet.timestamp.timestamp_nanos_opt().unwrap()).unwrap();
+        let mut epb = 0u32.to_le_bytes().to_vec();
+        epb.extend(u32::try_from(units >> 32).unwrap().to_le_bytes());
+        epb.extend(u32::try_from(units & 0xFFFF_FFFF).unwrap().to_le_bytes());
+        let len = u32::try_from(frame.len()).unwrap();
+        epb.extend(len.to_le_bytes());
+        epb.extend(len.to_le_bytes());
+        epb.extend(&frame);
+
+        let file = [
+            block(PCAPNG_SECTION, &shb),
+            block(1, &idb),
+            // name resolution block, skipped
+            block(4, &[0, 0, 0, 0]),
+            block(6, &epb),
+        ]
+        .concat();
+
+        let read = PcapReader::new(file.as_slice())
+            .unwrap()
+            .collect::<GDL90Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read, [packet]);
+
+        let truncated = &file[..file.len() - 10];
+        let mut reader = PcapReader::new(truncated).unwrap();
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+}
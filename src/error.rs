@@ -8,11 +8,57 @@ pub enum GDL90Error {
     #[error("invalid escape sequence: [0x7D, {0:#02X}]")]
     InvalidEscapeSequence(u8),
 
+    #[error("unknown message id {0:#04X}")]
+    UnknownMessageId(u8),
+
     #[error("crc mismatch: expected {expected}, got {got}")]
     CrcMismatch { expected: u16, got: u16 },
 
     #[error("serialization/deserialization error: {0}")]
     DekuError(#[from] deku::DekuError),
+
+    #[error(
+        "unsupported version {version} of message {message_id:#04X}/{sub_id}, newest supported is {supported}"
+    )]
+    UnsupportedVersion {
+        message_id: u8,
+        sub_id: u8,
+        version: u8,
+        supported: u8,
+    },
+
+    #[error("extension error: {0}")]
+    Extension(String),
+
+    #[error("invalid ForeFlight broadcast: {0}")]
+    InvalidBroadcast(String),
+
+    #[error("unknown EFB profile: {0}")]
+    UnknownProfile(String),
+
+    #[error("invalid capture file: {0}")]
+    InvalidCapture(String),
+
+    #[error("invalid session recording: {0}")]
+    InvalidSession(String),
+
+    #[error("invalid playback speed {0}, must be finite and positive")]
+    InvalidPlaybackSpeed(f64),
+
+    #[error("export failed: {0}")]
+    Export(String),
+
+    #[error("invalid NMEA sentence: {0}")]
+    InvalidNmea(String),
+
+    #[error("io error: {0}")]
+    Io(std::sync::Arc<std::io::Error>),
+}
+
+impl From<std::io::Error> for GDL90Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(std::sync::Arc::new(err))
+    }
 }
 
 #[macro_export]
@@ -0,0 +1,302 @@
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+
+use crate::{export::escape_xml, prelude::*};
+
+/// `ce`, `le` and `hae` value for unknown
+const COT_UNKNOWN: f64 = 9_999_999.0;
+
+/// A Cursor-on-Target event, e.g. for ATAK and WinTAK
+#[derive(Debug, Clone, PartialEq, Builder)]
+pub struct CotEvent {
+    pub uid: String,
+
+    /// CoT type, e.g. `a-n-A-C-F`, see `cot_type()`
+    pub event_type: String,
+
+    /// How the position was obtained, `m-g` for GPS, `m-r` for radar (TIS-B)
+    pub how: String,
+
+    pub time: DateTime<Utc>,
+
+    pub stale: DateTime<Utc>,
+
+    pub latitude: Angle,
+
+    pub longitude: Angle,
+
+    /// `hae`, height above the WGS-84 ellipsoid
+    pub altitude: Option<Length>,
+
+    /// `ce`, horizontal error
+    pub circular_error: Option<Length>,
+
+    /// `le`, vertical error
+    pub linear_error: Option<Length>,
+
+    /// True track
+    pub course: Option<Angle>,
+
+    pub speed: Option<Velocity>,
+
+    pub callsign: String,
+}
+
+impl CotEvent {
+    /// Event for a traffic or ownship report, `None` if it has no valid position.
+    ///
+    /// `hae` is the pressure altitude of the report plus `altitude_offset`, the ownship geometric
+    /// altitude minus the ownship pressure altitude. Nearby traffic is off from its height above
+    /// the ellipsoid by about the same as ownship. `hae` is unknown without the offset.
+    /// `ce`/`le` are taken from the NACp, or the NIC containment limits if the NACp is unknown.
+    #[must_use]
+    pub fn from_report(
+        report: &TrafficReport,
+        ownship: bool,
+        altitude_offset: Option<Length>,
+        time: DateTime<Utc>,
+        stale_after: Duration,
+    ) -> Option<Self> {
+        if !report.has_position() {
+            return None;
+        }
+
+        let identity = report.target_identity;
+        let uid = match identity.address_type {
+            AddressType::AdsbIcao | AddressType::TisbIcao => {
+                format!("ICAO-{:06X}", identity.participant_address)
+            }
+            address_type => format!(
+                "GDL90-{address_type:?}-{:06X}",
+                identity.participant_address
+            ),
+        };
+        let how = match identity.address_type {
+            AddressType::TisbIcao | AddressType::TisbTrackFileId => "m-r",
+            _ => "m-g",
+        };
+        let callsign = match report.callsign.trim() {
+            "" => format!("{:06X}", identity.participant_address),
+            callsign => callsign.to_string(),
+        };
+        // CoT course is over ground, a heading is where the nose points
+        let course = (report.miscellaneous_indicators.track_heading_type
+            == TrackHeadingType::TrueTrackAngle)
+            .then_some(report.track_heading);
+
+        Some(Self {
+            uid,
+            event_type: cot_type(report.emitter_category, ownship),
+            how: how.to_string(),
+            time,
+            stale: TimeDelta::from_std(stale_after)
+                .ok()
+                .and_then(|stale_after| time.checked_add_signed(stale_after))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+            latitude: report.latitude,
+            longitude: report.longitude,
+            altitude: report
+                .altitude
+                .zip(altitude_offset)
+                .map(|(altitude, offset)| altitude + offset),
+            circular_error: report
+                .nacp
+                .horizontal_accuracy()
+                .or_else(|| report.nic.containment_radius()),
+            linear_error: report
+                .nacp
+                .vertical_accuracy()
+                .or_else(|| report.nic.vertical_protection_limit()),
+            course,
+            speed: report.horizontal_velocity,
+            callsign,
+        })
+    }
+
+    /// The `<event>` document
+    #[must_use]
+    pub fn to_xml(&self) -> String {
+        let time = |time: DateTime<Utc>| time.to_rfc3339_opts(SecondsFormat::Millis, true);
+        let meters = |length: Option<Length>| length.map_or(COT_UNKNOWN, |l| l.meters());
+
+        // unknown values are left out rather than written as 0
+        let course = self
+            .course
+            .map(|c| format!(r#" course="{:.1}""#, c.degrees().rem_euclid(360.0)));
+        let speed = self
+            .speed
+            .map(|s| format!(r#" speed="{:.2}""#, s.meters_per_second()));
+        let track = match (course, speed) {
+            (None, None) => String::new(),
+            (course, speed) => format!(
+                "<track{}{}/>",
+                course.unwrap_or_default(),
+                speed.unwrap_or_default()
+            ),
+        };
+
+        format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+                r#"<event version="2.0" uid="{uid}" type="{event_type}" how="{how}" time="{time}" start="{time}" stale="{stale}">"#,
+                r#"<point lat="{lat:.7}" lon="{lon:.7}" hae="{hae:.1}" ce="{ce:.1}" le="{le:.1}"/>"#,
+                r#"<detail><contact callsign="{callsign}"/>{track}</detail>"#,
+                "</event>"
+            ),
+            uid = escape_xml(&self.uid),
+            event_type = escape_xml(&self.event_type),
+            how = escape_xml(&self.how),
+            time = time(self.time),
+            stale = time(self.stale),
+            lat = self.latitude.degrees(),
+            lon = self.longitude.degrees(),
+            hae = meters(self.altitude),
+            ce = meters(self.circular_error),
+            le = meters(self.linear_error),
+            callsign = escape_xml(&self.callsign),
+        )
+    }
+}
+
+/// CoT type for an emitter category, friendly (`f`) for ownship, neutral (`n`) for traffic.
+///
+/// | Emitter category                      | Type          |
+/// |---------------------------------------|---------------|
+/// | Light to Heavy, glider, ultra light   | `a-?-A-C-F`   |
+/// | Highly maneuverable                   | `a-?-A-M-F`   |
+/// | Rotorcraft                            | `a-?-A-C-H`   |
+/// | Lighter than air                      | `a-?-A-C-L`   |
+/// | Unmanned aerial vehicle               | `a-?-A-C-F-q` |
+/// | Parachutist                           | `a-?-G-U-C-I` |
+/// | Space vehicle                         | `a-?-P`       |
+/// | Surface vehicles                      | `a-?-G-E-V`   |
+/// | Obstacles                             | `a-?-G-I`     |
+/// | No information                        | `a-?-A-C`     |
+#[must_use]
+pub fn cot_type(category: EmitterCategory, ownship: bool) -> String {
+    let affiliation = if ownship { 'f' } else { 'n' };
+    let function = match category {
+        EmitterCategory::Light
+        | EmitterCategory::Small
+        | EmitterCategory::Large
+        | EmitterCategory::HighVortexLarge
+        | EmitterCategory::Heavy
+        | EmitterCategory::GliderSailplane
+        | EmitterCategory::UltraLightHangGliderParaglider => "A-C-F",
+        EmitterCategory::HighlyManeuverable => "A-M-F",
+        EmitterCategory::Rotorcraft => "A-C-H",
+        EmitterCategory::LighterThanAir => "A-C-L",
+        EmitterCategory::UnmannedAerialVehicle => "A-C-F-q",
+        EmitterCategory::ParachutistSkyDiver => "G-U-C-I",
+        EmitterCategory::SpaceTransatmosphericVehicle => "P",
+        EmitterCategory::SurfaceVehicleEmergencyVehicle
+        | EmitterCategory::SurfaceVehicleServiceVehicle => "G-E-V",
+        EmitterCategory::PointObstacleIncludesTetheredBalloons
+        | EmitterCategory::ClusterObstacle
+        | EmitterCategory::LineObstacle => "G-I",
+        EmitterCategory::NoInformation => "A-C",
+    };
+    format!("a-{affiliation}-{function}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> TrafficReport {
+        TrafficReport::default()
+            .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, 0xAB4549))
+            .with_latitude(44.90708.degrees())
+            .with_longitude((-122.99488).degrees())
+            .with_altitude(5000.0.feet())
+            .with_nacp(NACp::NACp9_HFOM_30M_VFOM_45M)
+            .with_horizontal_velocity(100.0.knots())
+            .with_track_heading(90.0.degrees())
+            .with_miscellaneous_indicators(
+                MiscellaneousIndicators::default()
+                    .with_track_heading_type(TrackHeadingType::TrueTrackAngle),
+            )
+            .with_emitter_category(EmitterCategory::Rotorcraft)
+            .with_callsign("N825V".into())
+    }
+
+    #[test]
+    fn event() {
+        let time = DateTime::from_timestamp(1_760_000_000, 0).unwrap();
+        // 5000 ft is 1524 m
+        let event = CotEvent::from_report(
+            &report(),
+            false,
+            Some(76.0.meters()),
+            time,
+            Duration::from_secs(30),
+        )
+        .unwrap();
+        assert_eq!(event.uid, "ICAO-AB4549");
+        assert_eq!(event.event_type, "a-n-A-C-H");
+
+        assert_eq!(
+            event.to_xml(),
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+                r#"<event version="2.0" uid="ICAO-AB4549" type="a-n-A-C-H" how="m-g" time="2025-10-09T08:53:20.000Z" start="2025-10-09T08:53:20.000Z" stale="2025-10-09T08:53:50.000Z">"#,
+                r#"<point lat="44.9070800" lon="-122.9948800" hae="1600.0" ce="30.0" le="45.0"/>"#,
+                r#"<detail><contact callsign="N825V"/><track course="90.0" speed="51.44"/></detail>"#,
+                "</event>"
+            )
+        );
+
+        let unknown = TrafficReport {
+            altitude: None,
+            horizontal_velocity: None,
+            ..report()
+                .with_nacp(NACp::NACp0_Unknown)
+                .with_nic(NIC::NIC8_0_1NM)
+                .with_miscellaneous_indicators(MiscellaneousIndicators::default())
+                .with_callsign(String::new())
+        };
+        let event = CotEvent::from_report(
+            &unknown,
+            true,
+            Some(76.0.meters()),
+            time,
+            Duration::from_secs(30),
+        )
+        .unwrap();
+        assert_eq!(event.event_type, "a-f-A-C-H");
+        let xml = event.to_xml();
+        assert!(
+            xml.contains(r#"hae="9999999.0" ce="185.2" le="9999999.0""#),
+            "{xml}"
+        );
+        assert!(
+            xml.contains(r#"<contact callsign="AB4549"/></detail>"#),
+            "{xml}"
+        );
+
+        let no_position = report()
+            .with_latitude(0.0.degrees())
+            .with_longitude(0.0.degrees());
+        assert!(CotEvent::from_report(&no_position, false, None, time, Duration::ZERO).is_none());
+
+        // no hae without the offset, no course without a valid track
+        let no_track = report().with_miscellaneous_indicators(MiscellaneousIndicators::default());
+        let xml = CotEvent::from_report(&no_track, false, None, time, Duration::ZERO)
+            .unwrap()
+            .to_xml();
+        assert!(xml.contains(r#"hae="9999999.0""#), "{xml}");
+        assert!(xml.contains(r#"<track speed="51.44"/>"#), "{xml}");
+
+        for heading_type in [
+            TrackHeadingType::HeadingMagnetic,
+            TrackHeadingType::HeadingTrue,
+        ] {
+            let heading = report().with_miscellaneous_indicators(
+                MiscellaneousIndicators::default().with_track_heading_type(heading_type),
+            );
+            let event = CotEvent::from_report(&heading, false, None, time, Duration::ZERO).unwrap();
+            assert_eq!(event.course, None);
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! Tracks shared by the tests of the exporters
+
+use chrono::{DateTime, Utc};
+
+use crate::prelude::*;
+
+pub(crate) fn at(seconds: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(1_760_000_000 + seconds, 0).unwrap()
+}
+
+pub(crate) fn report(address: u32, latitude: f64, callsign: &str) -> TrafficReport {
+    TrafficReport::default()
+        .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, address))
+        .with_latitude(latitude.degrees())
+        .with_longitude((-122.5).degrees())
+        .with_altitude(5000.0.feet())
+        .with_horizontal_velocity(120.0.knots())
+        .with_callsign(callsign.into())
+}
+
+/// Traffic `ABCDEF` with 2 points and an ownship track with 1 point
+pub(crate) fn tracks() -> Vec<Track> {
+    let mut collector = TrackCollector::default();
+    collector.push(at(0), &report(0xABCDEF, 45.0, "").traffic().into());
+    collector.push(at(0), &report(0xABCDEF, 45.5, "").ownship().into());
+    collector.push(at(1), &report(0xABCDEF, 45.01, "N825V").traffic().into());
+    collector.into_tracks()
+}
@@ -0,0 +1,136 @@
+use std::io::Write;
+
+use chrono::SecondsFormat;
+use serde_json::{Map, Value, json};
+
+use crate::prelude::*;
+
+/// The tracks as a GeoJSON `FeatureCollection`.
+///
+/// Every track is a feature with a `LineString` (a `Point` for single report tracks).
+/// Positions have the pressure altitude in meters as third value if every report of the track
+/// has one, and only longitude and latitude otherwise, see `Track::has_altitude()`.
+/// The `TrackPoint` properties are in `coordinateProperties`, one array per property
+/// with an entry per coordinate.
+#[must_use]
+pub fn tracks_geojson(tracks: &[Track]) -> Value {
+    let features = tracks
+        .iter()
+        .map(|track| {
+            let has_altitude = track.has_altitude();
+            let coordinates = track
+                .points
+                .iter()
+                .map(|point| {
+                    let report = &point.report;
+                    let mut position = vec![report.longitude.degrees(), report.latitude.degrees()];
+                    position.extend(
+                        report
+                            .altitude
+                            .filter(|_| has_altitude)
+                            .map(FromUom::meters),
+                    );
+                    position
+                })
+                .collect::<Vec<_>>();
+            let geometry = match coordinates.as_slice() {
+                [point] => json!({ "type": "Point", "coordinates": point }),
+                _ => json!({ "type": "LineString", "coordinates": coordinates }),
+            };
+
+            let mut coordinate_properties = Map::new();
+            for point in &track.points {
+                for (key, value) in point.properties() {
+                    if let Value::Array(values) = coordinate_properties
+                        .entry(key)
+                        .or_insert_with(|| Value::Array(vec![]))
+                    {
+                        values.push(value);
+                    }
+                }
+            }
+
+            let (start, end) = track.time_span().map_or((None, None), |(start, end)| {
+                (
+                    Some(start.to_rfc3339_opts(SecondsFormat::Millis, true)),
+                    Some(end.to_rfc3339_opts(SecondsFormat::Millis, true)),
+                )
+            });
+            json!({
+                "type": "Feature",
+                "geometry": geometry,
+                "properties": {
+                    "name": track.name(),
+                    "address": format!("{:06X}", track.identity.participant_address),
+                    "address_type": format!("{:?}", track.identity.address_type),
+                    "ownship": track.ownship,
+                    "start": start,
+                    "end": end,
+                    "coordinateProperties": coordinate_properties,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+/// Write `tracks_geojson()`
+///
+/// # Errors
+///
+/// If writing fails.
+pub fn write_geojson(mut writer: impl Write, tracks: &[Track]) -> GDL90Result<()> {
+    serde_json::to_writer_pretty(&mut writer, &tracks_geojson(tracks))
+        .map_err(std::io::Error::from)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::fixtures::tracks;
+
+    #[test]
+    fn geojson() {
+        let geojson = tracks_geojson(&tracks());
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 2);
+
+        let traffic = &features[0];
+        assert_eq!(traffic["geometry"]["type"], "LineString");
+        let latitude = traffic["geometry"]["coordinates"][1][1].as_f64().unwrap();
+        assert!((latitude - 45.01).abs() < 1e-9);
+        assert_eq!(traffic["properties"]["name"], "N825V");
+        assert_eq!(traffic["properties"]["address"], "ABCDEF");
+        assert_eq!(
+            traffic["properties"]["coordinateProperties"]["callsign"],
+            json!(["", "N825V"])
+        );
+        assert_eq!(
+            traffic["properties"]["coordinateProperties"]["vertical_velocity_fpm"],
+            json!([null, null])
+        );
+
+        let ownship = &features[1];
+        assert_eq!(ownship["geometry"]["type"], "Point");
+        assert_eq!(ownship["properties"]["ownship"], true);
+
+        let mut written = vec![];
+        write_geojson(&mut written, &tracks()).unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&written).unwrap(), geojson);
+
+        let mut tracks = tracks();
+        tracks[0].points[1].report.altitude = None;
+        let geojson = tracks_geojson(&tracks);
+        let coordinates = geojson["features"][0]["geometry"]["coordinates"]
+            .as_array()
+            .unwrap();
+        assert!(
+            coordinates
+                .iter()
+                .all(|position| position.as_array().unwrap().len() == 2)
+        );
+    }
+}
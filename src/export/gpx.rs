@@ -0,0 +1,101 @@
+use std::io::Write;
+
+use chrono::SecondsFormat;
+
+use crate::{
+    export::{escape_xml, track::property_text},
+    prelude::*,
+};
+
+/// Namespace of the per-point extension elements
+pub const GPX_EXTENSIONS_NAMESPACE: &str = "urn:gdl90:track";
+
+/// Write the tracks as a GPX 1.1 file, e.g. for QGIS.
+///
+/// Every track becomes a `trk` with a single segment, `type` is `traffic` or `ownship`.
+/// The `TrackPoint` properties besides `time` are written as `gdl90:` extension elements.
+///
+/// `ele` is the pressure altitude of the report, GDL90 traffic has no geometric altitude.
+/// It is left out for reports without altitude.
+///
+/// # Errors
+///
+/// If writing fails.
+pub fn write_gpx(mut writer: impl Write, tracks: &[Track]) -> GDL90Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<gpx version="1.1" creator="gdl90" xmlns="http://www.topografix.com/GPX/1/1" xmlns:gdl90="{GPX_EXTENSIONS_NAMESPACE}">"#
+    )?;
+
+    for track in tracks {
+        writeln!(writer, "<trk>")?;
+        writeln!(writer, "<name>{}</name>", escape_xml(&track.name()))?;
+        writeln!(
+            writer,
+            "<type>{}</type>",
+            if track.ownship { "ownship" } else { "traffic" }
+        )?;
+        writeln!(
+            writer,
+            "<extensions><gdl90:address>{:06X}</gdl90:address><gdl90:address_type>{:?}</gdl90:address_type></extensions>",
+            track.identity.participant_address, track.identity.address_type
+        )?;
+        writeln!(writer, "<trkseg>")?;
+        for point in &track.points {
+            writeln!(
+                writer,
+                r#"<trkpt lat="{:.7}" lon="{:.7}">"#,
+                point.report.latitude.degrees(),
+                point.report.longitude.degrees()
+            )?;
+            if let Some(altitude) = point.report.altitude {
+                writeln!(writer, "<ele>{:.1}</ele>", altitude.meters())?;
+            }
+            writeln!(
+                writer,
+                "<time>{}</time>",
+                point.time.to_rfc3339_opts(SecondsFormat::Millis, true)
+            )?;
+            write!(writer, "<extensions>")?;
+            for (key, value) in point.properties() {
+                if key == "time" {
+                    continue;
+                }
+                if let Some(text) = property_text(&value) {
+                    write!(writer, "<gdl90:{key}>{}</gdl90:{key}>", escape_xml(&text))?;
+                }
+            }
+            writeln!(writer, "</extensions>")?;
+            writeln!(writer, "</trkpt>")?;
+        }
+        writeln!(writer, "</trkseg>")?;
+        writeln!(writer, "</trk>")?;
+    }
+
+    writeln!(writer, "</gpx>")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::fixtures::tracks;
+
+    #[test]
+    fn gpx() {
+        let mut gpx = vec![];
+        write_gpx(&mut gpx, &tracks()).unwrap();
+        let gpx = String::from_utf8(gpx).unwrap();
+
+        assert_eq!(gpx.matches("<trk>").count(), 2);
+        assert_eq!(gpx.matches("<trkpt ").count(), 3);
+        assert!(gpx.contains("<name>N825V</name>\n<type>traffic</type>"));
+        assert!(gpx.contains(
+            "<trkpt lat=\"45.0100000\" lon=\"-122.5000000\">\n<ele>1524.0</ele>\n<time>2025-10-09T08:53:21.000Z</time>"
+        ));
+        assert!(gpx.contains("<gdl90:callsign>N825V</gdl90:callsign>"));
+        assert!(gpx.contains("<type>ownship</type>"));
+        assert!(!gpx.contains("vertical_velocity_fpm"));
+    }
+}
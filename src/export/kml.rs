@@ -0,0 +1,172 @@
+use std::io::Write;
+
+use chrono::SecondsFormat;
+
+use crate::{
+    export::{escape_xml, track::property_text},
+    prelude::*,
+};
+
+/// Write the tracks as a KML document, e.g. for Google Earth.
+///
+/// Every track becomes a folder with a `LineString` placemark of the whole track, and a time
+/// stamped point placemark per report carrying the `TrackPoint` properties.
+///
+/// Reports only have a pressure altitude, which is written as absolute altitude (MSL) and is off
+/// by the difference to standard pressure. Tracks and points without altitude are clamped to
+/// ground, see `Track::has_altitude()`.
+///
+/// # Errors
+///
+/// If writing fails.
+pub fn write_kml(mut writer: impl Write, tracks: &[Track]) -> GDL90Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#)?;
+    writeln!(writer, "<Document>")?;
+    writeln!(writer, "<name>GDL90 tracks</name>")?;
+    // colors are aabbggrr
+    for (id, color, width) in [("traffic", "ff00a5ff", 2), ("ownship", "ffff7f00", 3)] {
+        writeln!(
+            writer,
+            r#"<Style id="{id}"><LineStyle><color>{color}</color><width>{width}</width></LineStyle><IconStyle><color>{color}</color><scale>0.4</scale></IconStyle><LabelStyle><scale>0</scale></LabelStyle></Style>"#
+        )?;
+    }
+
+    for track in tracks {
+        let name = escape_xml(&track.name());
+        let style = if track.ownship { "ownship" } else { "traffic" };
+
+        writeln!(writer, "<Folder>")?;
+        writeln!(writer, "<name>{name}</name>")?;
+        if track.points.len() > 1 {
+            writeln!(writer, "<Placemark>")?;
+            writeln!(writer, "<name>{name}</name>")?;
+            writeln!(writer, "<styleUrl>#{style}</styleUrl>")?;
+            if let Some((begin, end)) = track.time_span() {
+                writeln!(
+                    writer,
+                    "<TimeSpan><begin>{}</begin><end>{}</end></TimeSpan>",
+                    begin.to_rfc3339_opts(SecondsFormat::Millis, true),
+                    end.to_rfc3339_opts(SecondsFormat::Millis, true)
+                )?;
+            }
+            writeln!(writer, "<ExtendedData>")?;
+            write_data(
+                &mut writer,
+                "address",
+                &format!("{:06X}", track.identity.participant_address),
+            )?;
+            write_data(
+                &mut writer,
+                "address_type",
+                &format!("{:?}", track.identity.address_type),
+            )?;
+            write_data(&mut writer, "ownship", &track.ownship.to_string())?;
+            writeln!(writer, "</ExtendedData>")?;
+            writeln!(
+                writer,
+                "<LineString><altitudeMode>{}</altitudeMode><coordinates>",
+                altitude_mode(track.has_altitude())
+            )?;
+            for point in &track.points {
+                writeln!(
+                    writer,
+                    "{}",
+                    coordinates(&point.report, track.has_altitude())
+                )?;
+            }
+            writeln!(writer, "</coordinates></LineString>")?;
+            writeln!(writer, "</Placemark>")?;
+        }
+
+        for point in &track.points {
+            writeln!(writer, "<Placemark>")?;
+            writeln!(writer, "<styleUrl>#{style}</styleUrl>")?;
+            writeln!(
+                writer,
+                "<TimeStamp><when>{}</when></TimeStamp>",
+                point.time.to_rfc3339_opts(SecondsFormat::Millis, true)
+            )?;
+            writeln!(writer, "<ExtendedData>")?;
+            for (key, value) in point.properties() {
+                if let Some(text) = property_text(&value) {
+                    write_data(&mut writer, key, &text)?;
+                }
+            }
+            writeln!(writer, "</ExtendedData>")?;
+            let has_altitude = point.report.altitude.is_some();
+            writeln!(
+                writer,
+                "<Point><altitudeMode>{}</altitudeMode><coordinates>{}</coordinates></Point>",
+                altitude_mode(has_altitude),
+                coordinates(&point.report, has_altitude)
+            )?;
+            writeln!(writer, "</Placemark>")?;
+        }
+        writeln!(writer, "</Folder>")?;
+    }
+
+    writeln!(writer, "</Document>")?;
+    writeln!(writer, "</kml>")?;
+    Ok(())
+}
+
+fn write_data(writer: &mut impl Write, name: &str, value: &str) -> GDL90Result<()> {
+    writeln!(
+        writer,
+        r#"<Data name="{name}"><value>{}</value></Data>"#,
+        escape_xml(value)
+    )?;
+    Ok(())
+}
+
+fn altitude_mode(has_altitude: bool) -> &'static str {
+    if has_altitude {
+        "absolute"
+    } else {
+        "clampToGround"
+    }
+}
+
+/// `lon,lat[,alt]` with the altitude in meters, without it unless `with_altitude`
+fn coordinates(report: &TrafficReport, with_altitude: bool) -> String {
+    let (lat, lon) = (report.latitude.degrees(), report.longitude.degrees());
+    match report.altitude.filter(|_| with_altitude) {
+        Some(altitude) => format!("{lon:.7},{lat:.7},{:.1}", altitude.meters()),
+        None => format!("{lon:.7},{lat:.7}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::fixtures::tracks;
+
+    #[test]
+    fn kml() {
+        let mut kml = vec![];
+        write_kml(&mut kml, &tracks()).unwrap();
+        let kml = String::from_utf8(kml).unwrap();
+
+        assert_eq!(kml.matches("<Folder>").count(), 2);
+        assert_eq!(kml.matches("<LineString>").count(), 1);
+        assert_eq!(kml.matches("<Point>").count(), 3);
+        assert!(kml.contains("-122.5000000,45.0000000,1524.0\n-122.5000000,45.0100000,1524.0\n"));
+        assert!(kml.contains(r#"<Data name="callsign"><value>N825V</value></Data>"#));
+        assert!(kml.contains("<when>2025-10-09T08:53:21.000Z</when>"));
+        assert!(kml.ends_with("</kml>\n"));
+
+        // no 0 m points in an otherwise absolute track
+        let mut tracks = tracks();
+        tracks[0].points[0].report.altitude = None;
+        let mut kml = vec![];
+        write_kml(&mut kml, &tracks).unwrap();
+        let kml = String::from_utf8(kml).unwrap();
+        assert!(kml.contains(
+            "<LineString><altitudeMode>clampToGround</altitudeMode><coordinates>\n-122.5000000,45.0000000\n-122.5000000,45.0100000\n"
+        ));
+        assert!(kml.contains(
+            "<Point><altitudeMode>absolute</altitudeMode><coordinates>-122.5000000,45.0100000,1524.0</coordinates>"
+        ));
+    }
+}
@@ -0,0 +1,30 @@
+//! Exporting recorded traffic for other tools
+
+pub mod cot;
+#[cfg(test)]
+mod fixtures;
+pub mod geojson;
+pub mod gpx;
+pub mod kml;
+#[cfg(feature = "arrow")]
+pub mod record_batch;
+pub mod table;
+pub mod track;
+
+pub use self::{cot::*, geojson::*, gpx::*, kml::*, table::*, track::*};
+
+/// Escape text for XML element content and attribute values
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,372 @@
+//! Arrow and Parquet output of `Table`s, enabled with the `arrow` feature.
+
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    io::Write,
+    sync::Arc,
+};
+
+use arrow::{
+    array::{
+        ArrayRef, BooleanArray, Float64Array, StringArray, TimestampNanosecondArray, UInt64Array,
+    },
+    datatypes::{DataType, Field, Schema, TimeUnit},
+    record_batch::RecordBatch,
+};
+use chrono::{DateTime, Utc};
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression, ZstdLevel},
+    errors::ParquetError,
+    file::properties::WriterProperties,
+};
+
+use crate::{export::table::received_row, prelude::*};
+
+/// Zstd compression, for all Parquet output unless other properties are given
+#[must_use]
+pub fn parquet_properties() -> WriterProperties {
+    WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build()
+}
+
+fn export(err: ParquetError) -> GDL90Error {
+    GDL90Error::Export(err.to_string())
+}
+
+impl ColumnType {
+    #[must_use]
+    pub fn arrow_type(self) -> DataType {
+        match self {
+            Self::Timestamp => DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+            Self::Bool => DataType::Boolean,
+            Self::UInt => DataType::UInt64,
+            Self::Float => DataType::Float64,
+            Self::Text => DataType::Utf8,
+        }
+    }
+}
+
+impl Table {
+    /// Column names and types, `UInt` and `Float` columns are nullable
+    #[must_use]
+    pub fn arrow_schema(&self) -> Schema {
+        Schema::new(
+            self.columns()
+                .iter()
+                .map(|column| {
+                    Field::new(
+                        column.name,
+                        column.column_type.arrow_type(),
+                        matches!(column.column_type, ColumnType::UInt | ColumnType::Float),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// All rows as a single record batch
+    ///
+    /// # Errors
+    ///
+    /// If a timestamp is out of range for nanoseconds.
+    pub fn to_record_batch(&self) -> GDL90Result<RecordBatch> {
+        let columns = self
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(i, column)| -> ArrayRef {
+                let cells = self.rows().iter().map(move |row| &row[i]);
+                match column.column_type {
+                    ColumnType::Timestamp => Arc::new(
+                        cells
+                            .map(|cell| match cell {
+                                Cell::Timestamp(time) => time.timestamp_nanos_opt(),
+                                _ => None,
+                            })
+                            .collect::<TimestampNanosecondArray>()
+                            .with_timezone("UTC"),
+                    ),
+                    ColumnType::Bool => Arc::new(
+                        cells
+                            .map(|cell| match cell {
+                                Cell::Bool(value) => Some(*value),
+                                _ => None,
+                            })
+                            .collect::<BooleanArray>(),
+                    ),
+                    ColumnType::UInt => Arc::new(
+                        cells
+                            .map(|cell| match cell {
+                                Cell::UInt(value) => *value,
+                                _ => None,
+                            })
+                            .collect::<UInt64Array>(),
+                    ),
+                    ColumnType::Float => Arc::new(
+                        cells
+                            .map(|cell| match cell {
+                                Cell::Float(value) => *value,
+                                _ => None,
+                            })
+                            .collect::<Float64Array>(),
+                    ),
+                    ColumnType::Text => Arc::new(
+                        cells
+                            .map(|cell| match cell {
+                                Cell::Text(text) => Some(text.as_str()),
+                                _ => None,
+                            })
+                            .collect::<StringArray>(),
+                    ),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        RecordBatch::try_new(Arc::new(self.arrow_schema()), columns)
+            .map_err(|err| GDL90Error::Export(err.to_string()))
+    }
+
+    /// Write as a Parquet file, see `parquet_properties()`
+    ///
+    /// # Errors
+    ///
+    /// See `to_record_batch()`, or if writing fails.
+    pub fn write_parquet(&self, writer: impl Write + Send) -> GDL90Result<()> {
+        let batch = self.to_record_batch()?;
+
+        let mut writer = ArrowWriter::try_new(writer, batch.schema(), Some(parquet_properties()))
+            .map_err(export)?;
+        writer.write(&batch).map_err(export)?;
+        writer.close().map_err(export)?;
+        Ok(())
+    }
+}
+
+/// Writes messages to a Parquet file per message type, a record batch every `batch_rows` rows.
+///
+/// Same tables as `MessageTables`, but at most `batch_rows` rows per table are kept in memory,
+/// for recordings that don't fit into it. `open` creates the writer of a table when its first
+/// message is pushed. Zstd compressed unless other properties are set.
+///
+/// ```ignore
+/// let mut parquet = ParquetTableWriter::new(|name| Ok(File::create(format!("{name}.parquet"))?));
+/// for packet in SessionReader::new(File::open("flight.gdl90ses")?)? {
+///     let packet = packet?;
+///     for message in packet.messages().into_iter().flatten() {
+///         parquet.push(packet.received_at, &message)?;
+///     }
+/// }
+/// parquet.finish()?;
+/// ```
+pub struct ParquetTableWriter<W: Write + Send, F> {
+    open: F,
+    batch_rows: usize,
+    properties: WriterProperties,
+    writers: BTreeMap<&'static str, (Table, ArrowWriter<W>)>,
+}
+
+impl<W: Write + Send, F: FnMut(&'static str) -> GDL90Result<W>> ParquetTableWriter<W, F> {
+    pub const DEFAULT_BATCH_ROWS: usize = 65_536;
+
+    #[must_use]
+    pub fn new(open: F) -> Self {
+        Self {
+            open,
+            batch_rows: Self::DEFAULT_BATCH_ROWS,
+            properties: parquet_properties(),
+            writers: BTreeMap::new(),
+        }
+    }
+
+    /// Rows per record batch, at least 1
+    #[must_use]
+    pub fn with_batch_rows(mut self, batch_rows: usize) -> Self {
+        self.batch_rows = batch_rows.max(1);
+        self
+    }
+
+    /// Compression, row group size, etc. of the files opened after this
+    #[must_use]
+    pub fn with_properties(mut self, properties: WriterProperties) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    /// Add a row for the message, returns `false` if its type is not tabulated
+    ///
+    /// # Errors
+    ///
+    /// If opening the table's writer or writing a full batch fails.
+    pub fn push(&mut self, received_at: DateTime<Utc>, message: &Message) -> GDL90Result<bool> {
+        let Some((name, fields)) = received_row(received_at, message) else {
+            return Ok(false);
+        };
+
+        let (table, writer) = match self.writers.entry(name) {
+            Entry::Occupied(entry) => {
+                let (table, writer) = entry.into_mut();
+                table.push_row(fields);
+                (table, writer)
+            }
+            Entry::Vacant(entry) => {
+                let mut table = Table::new(name);
+                table.push_row(fields);
+                let writer = ArrowWriter::try_new(
+                    (self.open)(name)?,
+                    Arc::new(table.arrow_schema()),
+                    Some(self.properties.clone()),
+                )
+                .map_err(export)?;
+                let (table, writer) = entry.insert((table, writer));
+                (table, writer)
+            }
+        };
+
+        if table.len() >= self.batch_rows {
+            write_batch(table, writer)?;
+        }
+        Ok(true)
+    }
+
+    /// Write the remaining rows, close the files and return the writers, by table name
+    ///
+    /// # Errors
+    ///
+    /// If writing fails.
+    pub fn finish(self) -> GDL90Result<BTreeMap<&'static str, W>> {
+        self.writers
+            .into_iter()
+            .map(|(name, (mut table, mut writer))| -> GDL90Result<_> {
+                write_batch(&mut table, &mut writer)?;
+                Ok((name, writer.into_inner().map_err(export)?))
+            })
+            .collect()
+    }
+}
+
+/// Write the buffered rows as a record batch and drop them
+fn write_batch<W: Write + Send>(table: &mut Table, writer: &mut ArrowWriter<W>) -> GDL90Result<()> {
+    if !table.is_empty() {
+        writer.write(&table.to_record_batch()?).map_err(export)?;
+        table.clear();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{Array, AsArray},
+        datatypes::TimestampNanosecondType,
+    };
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+
+    fn tables() -> MessageTables {
+        let at = |s| DateTime::from_timestamp(1_760_000_000 + s, 0).unwrap();
+        let report = TrafficReport::default()
+            .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, 0xABCDEF))
+            .with_latitude(45.0.degrees())
+            .with_callsign("N825V, \"x\"".into());
+
+        let mut tables = MessageTables::default();
+        tables.push(
+            at(0),
+            &Heartbeat::default().with_utc_ok().with_timestamp(60).into(),
+        );
+        tables.push(at(0), &report.clone().ownship().into());
+        tables.push(at(1), &report.traffic().into());
+        tables
+    }
+
+    #[test]
+    fn record_batch() {
+        let tables = tables();
+        let traffic = tables.get("traffic_report").unwrap();
+        let batch = traffic.to_record_batch().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), traffic.columns().len());
+
+        let received_at = batch.column(0).as_primitive::<TimestampNanosecondType>();
+        assert_eq!(received_at.value(1), 1_760_000_001_000_000_000);
+
+        let altitude = batch.column_by_name("altitude_ft").unwrap();
+        assert_eq!(altitude.data_type(), &DataType::Float64);
+        assert_eq!(altitude.null_count(), 2);
+
+        let callsign = batch.column_by_name("callsign").unwrap().as_string::<i32>();
+        assert_eq!(callsign.value(0), "N825V, \"x\"");
+    }
+
+    #[test]
+    fn parquet_round_trip() {
+        let tables = tables();
+        let heartbeat = tables.get("heartbeat").unwrap();
+        let path =
+            std::env::temp_dir().join(format!("gdl90-heartbeat-{}.parquet", std::process::id()));
+
+        heartbeat
+            .write_parquet(std::fs::File::create(&path).unwrap())
+            .unwrap();
+        let batches = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let expected = heartbeat.to_record_batch().unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].columns(), expected.columns());
+        assert_eq!(batches[0].schema().fields(), expected.schema().fields());
+    }
+
+    #[test]
+    fn streaming() {
+        let at = |s| DateTime::from_timestamp(1_760_000_000 + s, 0).unwrap();
+        let report = TrafficReport::default().with_latitude(45.0.degrees());
+        let path = |name: &str| {
+            std::env::temp_dir().join(format!("gdl90-{name}-{}.parquet", std::process::id()))
+        };
+
+        let mut parquet = ParquetTableWriter::new(|name| Ok(std::fs::File::create(path(name))?))
+            .with_batch_rows(2);
+        for s in 0..5 {
+            assert!(
+                parquet
+                    .push(at(s), &report.clone().traffic().into())
+                    .unwrap()
+            );
+        }
+        assert!(!parquet.push(at(5), &Message::BasicReport).unwrap());
+        let files = parquet.finish().unwrap();
+        assert_eq!(
+            files.keys().copied().collect::<Vec<_>>(),
+            ["traffic_report"]
+        );
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(
+            std::fs::File::open(path("traffic_report")).unwrap(),
+        )
+        .unwrap();
+        let compression = builder.metadata().row_group(0).column(0).compression();
+        assert!(matches!(compression, Compression::ZSTD(_)));
+        let batches = builder
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        std::fs::remove_file(path("traffic_report")).unwrap();
+
+        let rows = batches.iter().map(RecordBatch::num_rows).sum::<usize>();
+        assert_eq!(rows, 5);
+        let received_at = batches[0]
+            .column(0)
+            .as_primitive::<TimestampNanosecondType>();
+        assert_eq!(received_at.value(1), 1_760_000_001_000_000_000);
+    }
+}
@@ -0,0 +1,636 @@
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    io::Write,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::prelude::*;
+
+/// Type of a `Table` column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    /// UTC, nanosecond resolution
+    Timestamp,
+    Bool,
+    /// Nullable
+    UInt,
+    /// Nullable
+    Float,
+    Text,
+}
+
+/// A value of a `Table` row
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Timestamp(DateTime<Utc>),
+    Bool(bool),
+    UInt(Option<u64>),
+    Float(Option<f64>),
+    Text(String),
+}
+
+impl Cell {
+    #[must_use]
+    pub fn column_type(&self) -> ColumnType {
+        match self {
+            Self::Timestamp(_) => ColumnType::Timestamp,
+            Self::Bool(_) => ColumnType::Bool,
+            Self::UInt(_) => ColumnType::UInt,
+            Self::Float(_) => ColumnType::Float,
+            Self::Text(_) => ColumnType::Text,
+        }
+    }
+
+    fn csv(&self) -> String {
+        match self {
+            Self::Timestamp(time) => time.to_rfc3339_opts(SecondsFormat::Nanos, true),
+            Self::Bool(value) => value.to_string(),
+            Self::UInt(value) => value.map(|v| v.to_string()).unwrap_or_default(),
+            Self::Float(value) => value.map(|v| v.to_string()).unwrap_or_default(),
+            Self::Text(text) if text.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            }
+            Self::Text(text) => text.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    /// Carries the unit, e.g. `altitude_ft`
+    pub name: &'static str,
+    pub column_type: ColumnType,
+}
+
+/// Flattened messages of one type, a row per message.
+///
+/// The first column is always `received_at`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    name: &'static str,
+    columns: Vec<Column>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub(crate) fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            columns: vec![],
+            rows: vec![],
+        }
+    }
+
+    /// Append a row, the first one sets the columns
+    pub(crate) fn push_row(&mut self, fields: impl IntoIterator<Item = (&'static str, Cell)>) {
+        let set_columns = self.columns.is_empty();
+        let mut row = Vec::with_capacity(self.columns.len());
+        for (column, cell) in fields {
+            if set_columns {
+                self.columns.push(Column {
+                    name: column,
+                    column_type: cell.column_type(),
+                });
+            }
+            row.push(cell);
+        }
+        self.rows.push(row);
+    }
+
+    /// Drop the rows, the columns are kept
+    pub(crate) fn clear(&mut self) {
+        self.rows.clear();
+    }
+
+    /// Table name, e.g. `traffic_report`
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    #[must_use]
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> &[Vec<Cell>] {
+        &self.rows
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Write as CSV with a header line.
+    ///
+    /// Timestamps are RFC 3339, unavailable values are empty.
+    ///
+    /// # Errors
+    ///
+    /// If writing fails.
+    pub fn write_csv(&self, mut writer: impl Write) -> GDL90Result<()> {
+        self.write_csv_header(&mut writer)?;
+        self.write_csv_rows(&mut writer)
+    }
+
+    fn write_csv_header(&self, writer: &mut impl Write) -> GDL90Result<()> {
+        let header = self
+            .columns
+            .iter()
+            .map(|column| column.name)
+            .collect::<Vec<_>>();
+        writeln!(writer, "{}", header.join(","))?;
+        Ok(())
+    }
+
+    fn write_csv_rows(&self, writer: &mut impl Write) -> GDL90Result<()> {
+        for row in &self.rows {
+            let line = row.iter().map(Cell::csv).collect::<Vec<_>>();
+            writeln!(writer, "{}", line.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Flattens decoded messages into one `Table` per message type.
+///
+/// | table                        | messages                           |
+/// |------------------------------|------------------------------------|
+/// | `heartbeat`                  | `Heartbeat`                        |
+/// | `initialization`             | `Initialization`                   |
+/// | `uplink_data`                | `UplinkData`                       |
+/// | `height_above_terrain`       | `HeightAboveTerrain`               |
+/// | `traffic_report`             | Ownship and Traffic, see `ownship` |
+/// | `ownship_geometric_altitude` | `OwnshipGeometricAltitude`         |
+/// | `foreflight_id`              | `ForeFlightID`                     |
+/// | `foreflight_ahrs`            | `ForeFlightAHRS`                   |
+/// | `precise_ownship`            | `PreciseOwnship`                   |
+/// | `precise_ownship_report`     | `PreciseOwnshipReport`             |
+/// | `precise_traffic`            | `PreciseTraffic`                   |
+///
+/// Basic and Long Reports, Ping/Pong and extensions are not tabulated.
+/// All rows are kept in memory, `CsvTableWriter` and `ParquetTableWriter` stream them instead.
+///
+/// ```ignore
+/// let mut tables = MessageTables::default();
+/// for packet in SessionReader::new(File::open("flight.gdl90ses")?)? {
+///     let packet = packet?;
+///     for message in packet.messages().into_iter().flatten() {
+///         tables.push(packet.received_at, &message);
+///     }
+/// }
+/// for table in tables.tables() {
+///     table.write_csv(File::create(format!("{}.csv", table.name()))?)?;
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MessageTables {
+    tables: BTreeMap<&'static str, Table>,
+}
+
+impl MessageTables {
+    /// Add a row for the message, returns `false` if its type is not tabulated
+    pub fn push(&mut self, received_at: DateTime<Utc>, message: &Message) -> bool {
+        let Some((name, fields)) = received_row(received_at, message) else {
+            return false;
+        };
+
+        self.tables
+            .entry(name)
+            .or_insert_with(|| Table::new(name))
+            .push_row(fields);
+        true
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Table> {
+        self.tables.get(name)
+    }
+
+    /// Tables with at least one row, by name
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.values()
+    }
+}
+
+/// Writes every message as a CSV row right away, a file per message type.
+///
+/// Same tables as `MessageTables`, but no rows are kept, for recordings that don't fit into memory.
+/// `open` creates the writer of a table when its first message is pushed, the header goes first.
+///
+/// ```ignore
+/// let mut csv = CsvTableWriter::new(|name| {
+///     Ok(BufWriter::new(File::create(format!("{name}.csv"))?))
+/// });
+/// for packet in SessionReader::new(File::open("flight.gdl90ses")?)? {
+///     let packet = packet?;
+///     for message in packet.messages().into_iter().flatten() {
+///         csv.push(packet.received_at, &message)?;
+///     }
+/// }
+/// csv.finish()?;
+/// ```
+pub struct CsvTableWriter<W, F> {
+    open: F,
+    writers: BTreeMap<&'static str, (Table, W)>,
+}
+
+impl<W: Write, F: FnMut(&'static str) -> GDL90Result<W>> CsvTableWriter<W, F> {
+    #[must_use]
+    pub fn new(open: F) -> Self {
+        Self {
+            open,
+            writers: BTreeMap::new(),
+        }
+    }
+
+    /// Write a row for the message, returns `false` if its type is not tabulated
+    ///
+    /// # Errors
+    ///
+    /// If opening the table's writer or writing fails.
+    pub fn push(&mut self, received_at: DateTime<Utc>, message: &Message) -> GDL90Result<bool> {
+        let Some((name, fields)) = received_row(received_at, message) else {
+            return Ok(false);
+        };
+
+        let (table, writer) = match self.writers.entry(name) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert((Table::new(name), (self.open)(name)?)),
+        };
+        let header = table.columns.is_empty();
+        table.push_row(fields);
+        if header {
+            table.write_csv_header(writer)?;
+        }
+        table.write_csv_rows(writer)?;
+        table.clear();
+        Ok(true)
+    }
+
+    /// Flush and return the writers, by table name
+    ///
+    /// # Errors
+    ///
+    /// If flushing fails.
+    pub fn finish(self) -> GDL90Result<BTreeMap<&'static str, W>> {
+        self.writers
+            .into_iter()
+            .map(|(name, (_, mut writer))| -> GDL90Result<_> {
+                writer.flush()?;
+                Ok((name, writer))
+            })
+            .collect()
+    }
+}
+
+fn text(value: impl std::fmt::Debug) -> Cell {
+    Cell::Text(format!("{value:?}"))
+}
+
+fn float(value: f64) -> Cell {
+    Cell::Float(Some(value))
+}
+
+fn uint(value: impl Into<u64>) -> Cell {
+    Cell::UInt(Some(value.into()))
+}
+
+/// Table name and columns of a message, starting with `received_at`
+pub(crate) fn received_row(
+    received_at: DateTime<Utc>,
+    message: &Message,
+) -> Option<(&'static str, impl Iterator<Item = (&'static str, Cell)>)> {
+    let (name, fields) = message_row(message)?;
+    let fields = [("received_at", Cell::Timestamp(received_at))]
+        .into_iter()
+        .chain(fields);
+    Some((name, fields))
+}
+
+/// Table name and columns of a message
+#[allow(clippy::too_many_lines)]
+fn message_row(message: &Message) -> Option<(&'static str, Vec<(&'static str, Cell)>)> {
+    let row = match message {
+        Message::Heartbeat(hb) => (
+            "heartbeat",
+            vec![
+                ("gps_pos_valid", Cell::Bool(hb.gps_pos_valid)),
+                ("maint_reqd", Cell::Bool(hb.maint_reqd)),
+                ("ident", Cell::Bool(hb.ident)),
+                ("addr_type", Cell::Bool(hb.addr_type)),
+                ("gps_batt_low", Cell::Bool(hb.gps_batt_low)),
+                ("ratcs", Cell::Bool(hb.ratcs)),
+                ("uat_initialized", Cell::Bool(hb.uat_initialized)),
+                ("csa_requested", Cell::Bool(hb.csa_requested)),
+                ("csa_not_available", Cell::Bool(hb.csa_not_available)),
+                ("utc_ok", Cell::Bool(hb.utc_ok)),
+                ("timestamp_s", uint(hb.timestamp())),
+                ("uplink_count", uint(hb.uplink_count())),
+                ("basic_long_count", uint(hb.basic_long_count())),
+            ],
+        ),
+        Message::Initialization(init) => (
+            "initialization",
+            vec![
+                ("audio_test", Cell::Bool(init.audio_test)),
+                ("audio_inhibit", Cell::Bool(init.audio_inhibit)),
+                ("cdti_ok", Cell::Bool(init.cdti_ok)),
+                ("csa_audio_disable", Cell::Bool(init.csa_audio_disable)),
+                ("csa_disable", Cell::Bool(init.csa_disable)),
+            ],
+        ),
+        Message::UplinkData(uplink) => (
+            "uplink_data",
+            vec![
+                (
+                    "time_of_reception_ns",
+                    Cell::UInt(
+                        uplink
+                            .time_of_reception
+                            .map(|t| u64::try_from(t.as_nanos()).unwrap_or(u64::MAX)),
+                    ),
+                ),
+                (
+                    "uplink_payload_hex",
+                    Cell::Text(
+                        uplink
+                            .uplink_payload
+                            .iter()
+                            .map(|b| format!("{b:02X}"))
+                            .collect(),
+                    ),
+                ),
+            ],
+        ),
+        Message::HeightAboveTerrain(hat) => (
+            "height_above_terrain",
+            vec![(
+                "height_above_terrain_ft",
+                Cell::Float(hat.height_above_terrain.map(FromUom::feet)),
+            )],
+        ),
+        Message::Ownship(OwnshipMessage(report)) => ("traffic_report", report_row(report, true)),
+        Message::Traffic(TrafficMessage(report)) => ("traffic_report", report_row(report, false)),
+        Message::OwnshipGeometricAltitude(oga) => (
+            "ownship_geometric_altitude",
+            vec![
+                (
+                    "ownship_geo_altitude_ft",
+                    float(oga.ownship_geo_altitude.feet()),
+                ),
+                (
+                    "vertical_warning_indicator",
+                    Cell::Bool(oga.vertical_metrics.vertical_warning_indicator),
+                ),
+                (
+                    "vfom_m",
+                    Cell::Float(oga.vertical_metrics.vfom.map(FromUom::meters)),
+                ),
+            ],
+        ),
+        Message::ForeFlight(ForeFlightMessage::ID(id)) => (
+            "foreflight_id",
+            vec![
+                ("version", uint(id.version)),
+                ("device_serial_number", uint(id.device_serial_number)),
+                ("device_name", Cell::Text(id.device_name.clone())),
+                ("device_long_name", Cell::Text(id.device_long_name.clone())),
+                (
+                    "geometric_altitude_datum",
+                    text(id.capabilities.geometric_altitude_datum()),
+                ),
+                (
+                    "internet_policy",
+                    text(id.capabilities.foreflight_internet_policy()),
+                ),
+            ],
+        ),
+        Message::ForeFlight(ForeFlightMessage::AHRS(ahrs)) => (
+            "foreflight_ahrs",
+            vec![
+                ("roll_deg", Cell::Float(ahrs.roll.map(FromUom::degrees))),
+                ("pitch_deg", Cell::Float(ahrs.pitch.map(FromUom::degrees))),
+                ("heading_type", text(ahrs.heading_type)),
+                (
+                    "heading_deg",
+                    Cell::Float(ahrs.heading.map(FromUom::degrees)),
+                ),
+                (
+                    "indicated_airspeed_kt",
+                    Cell::Float(ahrs.indicated_airspeed.map(FromUom::knots)),
+                ),
+                (
+                    "true_airspeed_kt",
+                    Cell::Float(ahrs.true_airspeed.map(FromUom::knots)),
+                ),
+            ],
+        ),
+        Message::Custom(CustomMessage::PreciseOwnship(ownship)) => (
+            "precise_ownship",
+            vec![
+                ("latitude_deg", float(ownship.latitude.degrees())),
+                ("longitude_deg", float(ownship.longitude.degrees())),
+                ("altitude_ft", float(ownship.altitude.feet())),
+                ("ground_speed_kt", float(ownship.ground_speed.knots())),
+            ],
+        ),
+        Message::Custom(CustomMessage::PreciseOwnshipReport(report)) => (
+            "precise_ownship_report",
+            vec![
+                ("version", uint(report.version)),
+                ("timestamp", Cell::Timestamp(report.timestamp)),
+                ("latitude_deg", float(report.latitude.degrees())),
+                ("longitude_deg", float(report.longitude.degrees())),
+                ("altitude_ft", float(report.altitude.feet())),
+                ("ground_speed_kt", float(report.ground_speed.knots())),
+                ("track_deg", float(report.track.degrees())),
+                (
+                    "vertical_speed_fpm",
+                    float(report.vertical_speed.feet_per_minute()),
+                ),
+                (
+                    "horizontal_accuracy_m",
+                    Cell::Float(report.horizontal_accuracy.map(FromUom::meters)),
+                ),
+                (
+                    "vertical_accuracy_m",
+                    Cell::Float(report.vertical_accuracy.map(FromUom::meters)),
+                ),
+            ],
+        ),
+        Message::Custom(CustomMessage::PreciseTraffic(traffic)) => (
+            "precise_traffic",
+            vec![
+                ("version", uint(traffic.version)),
+                ("timestamp", Cell::Timestamp(traffic.timestamp)),
+                ("address_type", text(traffic.target_identity.address_type)),
+                (
+                    "participant_address",
+                    Cell::Text(format!(
+                        "{:06X}",
+                        traffic.target_identity.participant_address
+                    )),
+                ),
+                ("latitude_deg", float(traffic.latitude.degrees())),
+                ("longitude_deg", float(traffic.longitude.degrees())),
+                ("altitude_ft", float(traffic.altitude.feet())),
+                ("ground_speed_kt", float(traffic.ground_speed.knots())),
+                ("track_deg", float(traffic.track.degrees())),
+                (
+                    "vertical_speed_fpm",
+                    float(traffic.vertical_speed.feet_per_minute()),
+                ),
+                ("emitter_category", text(traffic.emitter_category)),
+                (
+                    "callsign",
+                    Cell::Text(traffic.callsign.trim_end().to_string()),
+                ),
+            ],
+        ),
+        Message::Custom(CustomMessage::Ping(_) | CustomMessage::Pong(_))
+        | Message::BasicReport
+        | Message::LongReport
+        | Message::Extension(_) => return None,
+    };
+    Some(row)
+}
+
+fn report_row(report: &TrafficReport, ownship: bool) -> Vec<(&'static str, Cell)> {
+    let misc = &report.miscellaneous_indicators;
+    vec![
+        ("ownship", Cell::Bool(ownship)),
+        ("traffic_alert_status", text(report.traffic_alert_status)),
+        ("address_type", text(report.target_identity.address_type)),
+        (
+            "participant_address",
+            Cell::Text(format!(
+                "{:06X}",
+                report.target_identity.participant_address
+            )),
+        ),
+        ("latitude_deg", float(report.latitude.degrees())),
+        ("longitude_deg", float(report.longitude.degrees())),
+        (
+            "altitude_ft",
+            Cell::Float(report.altitude.map(FromUom::feet)),
+        ),
+        ("air_ground_state", text(misc.air_ground_state)),
+        ("report_type", text(misc.report_type)),
+        ("track_heading_type", text(misc.track_heading_type)),
+        ("nic", uint(u8::from(report.nic))),
+        ("nacp", uint(u8::from(report.nacp))),
+        (
+            "horizontal_velocity_kt",
+            Cell::Float(report.horizontal_velocity.map(FromUom::knots)),
+        ),
+        (
+            "vertical_velocity_fpm",
+            Cell::Float(report.vertical_velocity.map(FromUom::feet_per_minute)),
+        ),
+        ("track_heading_deg", float(report.track_heading.degrees())),
+        ("emitter_category", text(report.emitter_category)),
+        (
+            "callsign",
+            Cell::Text(report.callsign.trim_end().to_string()),
+        ),
+        (
+            "emergency_priority_code",
+            text(report.emergency_priority_code),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flatten() {
+        let at = |s| DateTime::from_timestamp(1_760_000_000 + s, 0).unwrap();
+        let report = TrafficReport::default()
+            .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, 0xABCDEF))
+            .with_latitude(45.0.degrees())
+            .with_callsign("N825V, \"x\"".into());
+
+        let mut tables = MessageTables::default();
+        assert!(tables.push(
+            at(0),
+            &Heartbeat::default().with_utc_ok().with_timestamp(60).into()
+        ));
+        assert!(tables.push(at(0), &report.clone().ownship().into()));
+        assert!(tables.push(at(1), &report.clone().traffic().into()));
+        assert!(tables.push(at(1), &ForeFlightAHRS::default().into()));
+        assert!(!tables.push(at(1), &Message::BasicReport));
+
+        let names = tables.tables().map(Table::name).collect::<Vec<_>>();
+        assert_eq!(names, ["foreflight_ahrs", "heartbeat", "traffic_report"]);
+
+        let traffic = tables.get("traffic_report").unwrap();
+        assert_eq!(traffic.len(), 2);
+        assert_eq!(traffic.columns()[0].name, "received_at");
+        assert_eq!(traffic.rows()[0][1], Cell::Bool(true));
+        assert_eq!(traffic.rows()[1][1], Cell::Bool(false));
+        let altitude = traffic
+            .columns()
+            .iter()
+            .position(|c| c.name == "altitude_ft")
+            .unwrap();
+        assert_eq!(traffic.columns()[altitude].column_type, ColumnType::Float);
+        assert!(
+            traffic
+                .rows()
+                .iter()
+                .all(|row| row.len() == traffic.columns().len())
+        );
+
+        let mut csv = vec![];
+        tables
+            .get("heartbeat")
+            .unwrap()
+            .write_csv(&mut csv)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "received_at,gps_pos_valid,maint_reqd,ident,addr_type,gps_batt_low,ratcs,\
+             uat_initialized,csa_requested,csa_not_available,utc_ok,timestamp_s,uplink_count,\
+             basic_long_count\n\
+             2025-10-09T08:53:20.000000000Z,false,false,false,false,false,false,false,false,\
+             false,true,60,0,0\n"
+        );
+
+        let mut csv = vec![];
+        traffic.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.contains(",\"N825V, \"\"x\"\"\","), "{csv}");
+        // unavailable velocities
+        assert!(csv.contains(",,,"), "{csv}");
+
+        // streamed rows are the same as the whole table
+        let mut streamed = CsvTableWriter::new(|_| Ok(Vec::<u8>::new()));
+        assert!(
+            streamed
+                .push(at(0), &report.clone().ownship().into())
+                .unwrap()
+        );
+        assert!(streamed.push(at(1), &report.traffic().into()).unwrap());
+        assert!(!streamed.push(at(1), &Message::BasicReport).unwrap());
+        let streamed = streamed.finish().unwrap();
+        assert_eq!(
+            streamed.keys().copied().collect::<Vec<_>>(),
+            ["traffic_report"]
+        );
+        assert_eq!(
+            String::from_utf8(streamed["traffic_report"].clone()).unwrap(),
+            csv
+        );
+    }
+}
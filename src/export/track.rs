@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Value, json};
+
+use crate::prelude::*;
+
+/// A position report of a `Track`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackPoint {
+    pub time: DateTime<Utc>,
+    pub report: TrafficReport,
+}
+
+impl TrackPoint {
+    /// Per-point properties written by the exporters, `null` if unavailable.
+    ///
+    /// Units are part of the names, as in the `serde` schema.
+    #[must_use]
+    pub fn properties(&self) -> Vec<(&'static str, Value)> {
+        let report = &self.report;
+        let track_heading = (report.miscellaneous_indicators.track_heading_type
+            != TrackHeadingType::NotValid)
+            .then_some(report.track_heading);
+        vec![
+            (
+                "time",
+                json!(self.time.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            ),
+            ("callsign", json!(report.callsign.trim_end())),
+            ("altitude_ft", json!(report.altitude.map(FromUom::feet))),
+            (
+                "horizontal_velocity_kt",
+                json!(report.horizontal_velocity.map(FromUom::knots)),
+            ),
+            (
+                "vertical_velocity_fpm",
+                json!(report.vertical_velocity.map(FromUom::feet_per_minute)),
+            ),
+            (
+                "track_heading_deg",
+                json!(track_heading.map(FromUom::degrees)),
+            ),
+            ("nic", json!(u8::from(report.nic))),
+            ("nacp", json!(u8::from(report.nacp))),
+            (
+                "traffic_alert",
+                json!(report.traffic_alert_status == TrafficAlertStatus::TrafficAlert),
+            ),
+        ]
+    }
+}
+
+/// All reports of one target, in the order they were received
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub identity: TargetIdentity,
+    /// Built from ownship reports
+    pub ownship: bool,
+    pub points: Vec<TrackPoint>,
+}
+
+impl Track {
+    /// Last callsign the target sent, its address in hex if it never sent one
+    #[must_use]
+    pub fn name(&self) -> String {
+        self.points
+            .iter()
+            .rev()
+            .map(|point| point.report.callsign.trim())
+            .find(|callsign| !callsign.is_empty())
+            .map_or_else(
+                || format!("{:06X}", self.identity.participant_address),
+                ToString::to_string,
+            )
+    }
+
+    /// Start and end time, `None` for a track without points
+    #[must_use]
+    pub fn time_span(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        Some((self.points.first()?.time, self.points.last()?.time))
+    }
+
+    /// Every point has an altitude, exporters write the track without altitudes otherwise
+    #[must_use]
+    pub fn has_altitude(&self) -> bool {
+        self.points
+            .iter()
+            .all(|point| point.report.altitude.is_some())
+    }
+}
+
+/// Groups traffic and ownship reports into `Track`s by `TargetIdentity`.
+///
+/// Ownship reports get tracks of their own, even if the ownship address also shows up as traffic.
+/// Reports without a valid position (latitude and longitude 0) are skipped.
+///
+/// ```ignore
+/// let mut collector = TrackCollector::default();
+/// for packet in SessionReader::new(File::open("flight.gdl90ses")?)? {
+///     let packet = packet?;
+///     for message in packet.messages().into_iter().flatten() {
+///         collector.push(packet.received_at, &message);
+///     }
+/// }
+/// write_kml(File::create("flight.kml")?, collector.tracks())?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TrackCollector {
+    tracks: Vec<Track>,
+    index: HashMap<(TargetIdentity, bool), usize>,
+}
+
+impl TrackCollector {
+    /// Add the message if it is a traffic or ownship report, returns `false` otherwise
+    pub fn push(&mut self, time: DateTime<Utc>, message: &Message) -> bool {
+        match message {
+            Message::Traffic(TrafficMessage(report)) => {
+                self.push_report(time, report.clone(), false)
+            }
+            Message::Ownship(OwnshipMessage(report)) => {
+                self.push_report(time, report.clone(), true)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `false` if the report has no valid position
+    pub fn push_report(
+        &mut self,
+        time: DateTime<Utc>,
+        report: TrafficReport,
+        ownship: bool,
+    ) -> bool {
+        if !report.has_position() {
+            return false;
+        }
+
+        let identity = report.target_identity;
+        let index = *self.index.entry((identity, ownship)).or_insert_with(|| {
+            self.tracks.push(Track {
+                identity,
+                ownship,
+                points: vec![],
+            });
+            self.tracks.len() - 1
+        });
+        self.tracks[index].points.push(TrackPoint { time, report });
+        true
+    }
+
+    /// Tracks in the order their targets first appeared
+    #[must_use]
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    #[must_use]
+    pub fn into_tracks(self) -> Vec<Track> {
+        self.tracks
+    }
+}
+
+/// A property value as XML text, `None` for `null`
+pub(crate) fn property_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(text) => Some(text.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::fixtures::{at, report, tracks};
+
+    #[test]
+    fn collect() {
+        let mut collector = TrackCollector::default();
+        assert!(!collector.push(at(0), &Heartbeat::default().into()));
+        assert!(
+            !collector.push(
+                at(0),
+                &report(1, 0.0, "")
+                    .with_longitude(0.0.degrees())
+                    .traffic()
+                    .into()
+            )
+        );
+
+        let tracks = tracks();
+        assert_eq!(tracks.len(), 2);
+        assert!(!tracks[0].ownship);
+        assert_eq!(tracks[0].points.len(), 2);
+        assert_eq!(tracks[0].name(), "N825V");
+        assert_eq!(tracks[0].time_span(), Some((at(0), at(1))));
+        assert!(tracks[0].has_altitude());
+        assert!(tracks[1].ownship);
+        assert_eq!(tracks[1].name(), "ABCDEF");
+
+        let properties = tracks[0].points[0].properties();
+        assert_eq!(properties[0], ("time", json!("2025-10-09T08:53:20.000Z")));
+        assert_eq!(properties[1], ("callsign", json!("")));
+        assert_eq!(properties[4], ("vertical_velocity_fpm", Value::Null));
+        assert_eq!(property_text(&properties[4].1), None);
+        assert_eq!(property_text(&properties[6].1).as_deref(), Some("0"));
+        assert_eq!(property_text(&properties[8].1).as_deref(), Some("false"));
+
+        let mut point = tracks[0].points[0].clone();
+        point.report.track_heading = 90.0.degrees();
+        assert_eq!(point.properties()[5], ("track_heading_deg", Value::Null));
+        point.report.miscellaneous_indicators.track_heading_type = TrackHeadingType::HeadingTrue;
+        let heading = point.properties()[5].1.as_f64().unwrap();
+        assert!((heading - 90.0).abs() < 1e-9);
+    }
+}
@@ -286,6 +286,7 @@ mod ffi {
         Unrestricted = 0,
         Expensive = 1,
         Disallowed = 2,
+        Reserved = 3,
     }
 
     #[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, BitAnd, BitOr, BitXor)]
@@ -304,6 +305,8 @@ mod ffi {
         pub device_long_name: String,
         pub foreflight_internet_policy: FFInternetPolicy,
         pub geometric_altitude_datum: FFGeometricAltitudeDatum,
+        /// Raw capabilities mask, including reserved bits
+        pub capabilities_mask: u32,
     }
 
     #[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, BitAnd, BitOr, BitXor)]
@@ -588,11 +591,12 @@ impl From<&rs::ForeFlightID> for ffi::ForeFlightID {
             device_name: v.device_name.clone(),
             device_long_name: v.device_long_name.clone(),
             foreflight_internet_policy: ffi::FFInternetPolicy {
-                repr: v.capabilities.foreflight_internet_policy as u8,
+                repr: v.capabilities.foreflight_internet_policy() as u8,
             },
             geometric_altitude_datum: ffi::FFGeometricAltitudeDatum {
-                repr: v.capabilities.geometric_altitude_datum as u8,
+                repr: v.capabilities.geometric_altitude_datum() as u8,
             },
+            capabilities_mask: v.capabilities.mask(),
         }
     }
 }
@@ -195,6 +195,7 @@ mod ffi {
         Unrestricted = 0,
         Expensive = 1,
         Disallowed = 2,
+        Reserved = 3,
     }
 
     enum GeometricAltitudeDatum {
@@ -212,6 +213,8 @@ mod ffi {
         device_long_name: String,
         foreflight_internet_policy: ForeFlightInternetPolicy,
         geometric_altitude_datum: GeometricAltitudeDatum,
+        /// Raw capabilities mask, including reserved bits
+        capabilities_mask: u32,
     }
 
     enum AHRSHeadingType {
@@ -528,8 +531,9 @@ impl From<&rs::ForeFlightID> for ffi::ForeFlightID {
             device_serial_number: v.device_serial_number,
             device_name: v.device_name.clone(),
             device_long_name: v.device_long_name.clone(),
-            foreflight_internet_policy: v.capabilities.foreflight_internet_policy.into(),
-            geometric_altitude_datum: v.capabilities.geometric_altitude_datum.into(),
+            foreflight_internet_policy: v.capabilities.foreflight_internet_policy().into(),
+            geometric_altitude_datum: v.capabilities.geometric_altitude_datum().into(),
+            capabilities_mask: v.capabilities.mask(),
         }
     }
 }
@@ -636,7 +640,7 @@ bridge_enums![
     ),
     (
         ForeFlightInternetPolicy,
-        [Unrestricted, Expensive, Disallowed]
+        [Unrestricted, Expensive, Disallowed, Reserved]
     ),
     (GeometricAltitudeDatum, [WGS84, MSL]),
     (AHRSHeadingType, [True, Magnetic])
@@ -0,0 +1,36 @@
+//! Reports shared by the tests of the FLARM sentences
+
+use crate::prelude::*;
+
+/// Northbound at 50 kt over 45°N 122°W
+pub(crate) fn ownship() -> TrafficReport {
+    TrafficReport::default()
+        .with_target_identity(TargetIdentity::new(AddressType::AdsbSelfAssigned, 0xF00001))
+        .with_latitude(45.0.degrees())
+        .with_longitude((-122.0).degrees())
+        .with_altitude(3000.0.feet())
+        .with_horizontal_velocity(50.0.knots())
+        .with_track_heading(0.0.degrees())
+        .with_miscellaneous_indicators(
+            MiscellaneousIndicators::default()
+                .with_air_ground_state(AirGroundState::Airborne)
+                .with_track_heading_type(TrackHeadingType::TrueTrackAngle),
+        )
+}
+
+/// Glider 1 km north at the same altitude, southbound at 50 kt
+pub(crate) fn glider() -> TrafficReport {
+    TrafficReport::default()
+        .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, 0x3D1234))
+        .with_latitude((45.0 + 1000.0 / 111_195.0).degrees())
+        .with_longitude((-122.0).degrees())
+        .with_altitude(3000.0.feet())
+        .with_horizontal_velocity(50.0.knots())
+        .with_track_heading(180.0.degrees())
+        .with_vertical_velocity(128.0.feet_per_minute())
+        .with_miscellaneous_indicators(
+            MiscellaneousIndicators::default()
+                .with_track_heading_type(TrackHeadingType::TrueTrackAngle),
+        )
+        .with_emitter_category(EmitterCategory::GliderSailplane)
+}
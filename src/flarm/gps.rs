@@ -0,0 +1,184 @@
+use chrono::{DateTime, Timelike, Utc};
+
+use crate::{
+    flarm::{optional_field, parse_field},
+    prelude::*,
+};
+
+/// `$GPRMC` with the ownship position, speed and true track.
+///
+/// Without a valid position the status is `V` and the position fields are empty.
+#[must_use]
+pub fn gprmc(ownship: &TrafficReport, time: DateTime<Utc>) -> String {
+    let position = nmea_position(ownship);
+    let track = (ownship.miscellaneous_indicators.track_heading_type
+        == TrackHeadingType::TrueTrackAngle)
+        .then(|| format!("{:.1}", ownship.track_heading.degrees().rem_euclid(360.0)));
+
+    nmea_sentence(&format!(
+        "GPRMC,{},{},{},{},{},{},,,{}",
+        nmea_time(time),
+        if position.is_some() { 'A' } else { 'V' },
+        position.as_deref().unwrap_or(",,,"),
+        optional_field(
+            ownship
+                .horizontal_velocity
+                .map(|speed| format!("{:.1}", speed.knots()))
+        ),
+        optional_field(track),
+        time.format("%d%m%y"),
+        if position.is_some() { 'A' } else { 'N' },
+    ))
+}
+
+/// `$GPGGA` with the ownship position.
+///
+/// The altitude is `geometric_altitude`, the GDL90 height above the WGS-84 ellipsoid, with a geoid
+/// separation of 0 as there is no geoid model to convert it to MSL. Altitude plus separation is
+/// the ellipsoid height as NMEA defines it, the MSL altitude is off by the local geoid height.
+/// Both are empty without `geometric_altitude`, the pressure altitude of the report isn't used.
+/// Satellite count and HDOP are left empty.
+#[must_use]
+pub fn gpgga(
+    ownship: &TrafficReport,
+    geometric_altitude: Option<Length>,
+    time: DateTime<Utc>,
+) -> String {
+    let position = nmea_position(ownship);
+
+    nmea_sentence(&format!(
+        "GPGGA,{},{},{},,,{},M,{},M,,",
+        nmea_time(time),
+        position.as_deref().unwrap_or(",,,"),
+        u8::from(position.is_some()),
+        optional_field(geometric_altitude.map(|altitude| format!("{:.1}", altitude.meters()))),
+        if geometric_altitude.is_some() {
+            "0.0"
+        } else {
+            ""
+        },
+    ))
+}
+
+/// `hhmmss.ss`
+fn nmea_time(time: DateTime<Utc>) -> String {
+    format!(
+        "{}.{:02}",
+        time.format("%H%M%S"),
+        time.nanosecond() % 1_000_000_000 / 10_000_000
+    )
+}
+
+/// `ddmm.mmmm,N,dddmm.mmmm,E`, `None` without a valid position
+fn nmea_position(report: &TrafficReport) -> Option<String> {
+    if !report.has_position() {
+        return None;
+    }
+
+    let coordinate = |angle: Angle, degree_digits: usize, hemispheres: [char; 2]| {
+        let degrees = angle.degrees();
+        // in 1/10000 minutes, so rounding carries into the degrees
+        let units: u32 = (degrees.abs() * 60.0 * 10_000.0).round().clamp_into();
+        format!(
+            "{:0degree_digits$}{:02}.{:04},{}",
+            units / 600_000,
+            units % 600_000 / 10_000,
+            units % 10_000,
+            hemispheres[usize::from(degrees < 0.0)]
+        )
+    };
+
+    Some(format!(
+        "{},{}",
+        coordinate(report.latitude, 2, ['N', 'S']),
+        coordinate(report.longitude, 3, ['E', 'W'])
+    ))
+}
+
+/// `ddmm.mmmm,N,dddmm.mmmm,E` starting at field `index`, `None` if empty
+pub(crate) fn parse_nmea_position(
+    fields: &[&str],
+    index: usize,
+) -> GDL90Result<Option<(Angle, Angle)>> {
+    let coordinate = |index: usize, negative: &str| -> GDL90Result<Option<Angle>> {
+        let Some(value) = parse_field::<f64>(fields, index)? else {
+            return Ok(None);
+        };
+        let degrees = (value / 100.0).trunc();
+        let degrees = degrees + (value - degrees * 100.0) / 60.0;
+        let sign = if fields.get(index + 1).map(|field| field.trim()) == Some(negative) {
+            -1.0
+        } else {
+            1.0
+        };
+        Ok(Some((sign * degrees).degrees()))
+    };
+
+    Ok(coordinate(index, "S")?.zip(coordinate(index + 2, "W")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::flarm::fixtures::ownship;
+
+    #[test]
+    fn sentences() {
+        let time = Utc.with_ymd_and_hms(2025, 7, 4, 13, 5, 9).unwrap()
+            + chrono::Duration::milliseconds(250);
+        let ownship = ownship()
+            .with_latitude(45.5887.degrees())
+            .with_longitude((-122.5975).degrees());
+
+        assert_eq!(
+            gprmc(&ownship, time),
+            nmea_sentence("GPRMC,130509.25,A,4535.3220,N,12235.8500,W,50.0,0.0,040725,,,A")
+        );
+        assert_eq!(
+            gpgga(&ownship, Some(1000.0.meters()), time),
+            nmea_sentence("GPGGA,130509.25,4535.3220,N,12235.8500,W,1,,,1000.0,M,0.0,M,,")
+        );
+        // no pressure altitude as MSL
+        assert_eq!(
+            gpgga(&ownship, None, time),
+            nmea_sentence("GPGGA,130509.25,4535.3220,N,12235.8500,W,1,,,,M,,M,,")
+        );
+
+        let heading = ownship.clone().with_miscellaneous_indicators(
+            MiscellaneousIndicators::default()
+                .with_track_heading_type(TrackHeadingType::HeadingTrue),
+        );
+        assert_eq!(
+            gprmc(&heading, time),
+            nmea_sentence("GPRMC,130509.25,A,4535.3220,N,12235.8500,W,50.0,,040725,,,A")
+        );
+
+        let no_fix = TrafficReport {
+            altitude: None,
+            horizontal_velocity: None,
+            ..TrafficReport::default()
+        };
+        assert_eq!(
+            gprmc(&no_fix, time),
+            nmea_sentence("GPRMC,130509.25,V,,,,,,,040725,,,N")
+        );
+        assert_eq!(
+            gpgga(&no_fix, None, time),
+            nmea_sentence("GPGGA,130509.25,,,,,0,,,,M,,M,,")
+        );
+    }
+
+    #[test]
+    fn parse_position() {
+        let (latitude, longitude) = parse_nmea_position(&["4535.3220", "N", "12235.8500", "W"], 0)
+            .unwrap()
+            .unwrap();
+        assert_eq_f!(latitude, 45.5887.degrees(), 1e-9);
+        assert_eq_f!(longitude, (-122.5975).degrees(), 1e-9);
+
+        assert_eq!(parse_nmea_position(&["", "", "", ""], 0).unwrap(), None);
+        assert!(parse_nmea_position(&["45x", "N", "12235.8500", "W"], 0).is_err());
+    }
+}
@@ -0,0 +1,301 @@
+//! FLARM NMEA, the data port protocol of FLARM devices.
+//!
+//! Gliding instruments such as XCSoar and LXNAV read traffic as `$PFLAA` and `$PFLAU` sentences
+//! and the ownship position as `$GPRMC` and `$GPGGA`, see the FLARM Data Port ICD.
+
+use std::str::FromStr;
+
+#[cfg(test)]
+mod fixtures;
+pub mod gps;
+pub mod nmea;
+pub mod pflaa;
+pub mod pflau;
+
+pub use self::{gps::*, nmea::*, pflaa::*, pflau::*};
+use crate::prelude::*;
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, num_enum::FromPrimitive, num_enum::IntoPrimitive,
+)]
+#[repr(u8)]
+/// `AcftType` of `$PFLAA`, written as a hex digit
+pub enum FlarmAircraftType {
+    #[default]
+    Unknown = 0x0,
+    /// Glider or motor glider
+    Glider = 0x1,
+    TowPlane = 0x2,
+    /// Helicopter or gyrocopter
+    Helicopter = 0x3,
+    Skydiver = 0x4,
+    DropPlane = 0x5,
+    HangGlider = 0x6,
+    Paraglider = 0x7,
+    /// Aircraft with reciprocating engine(s)
+    PistonAircraft = 0x8,
+    /// Aircraft with jet or turboprop engine(s)
+    JetTurboprop = 0x9,
+    Balloon = 0xB,
+    Airship = 0xC,
+    UnmannedAerialVehicle = 0xD,
+    StaticObstacle = 0xF,
+    // 0xA, 0xE => reserved
+}
+
+impl From<EmitterCategory> for FlarmAircraftType {
+    fn from(category: EmitterCategory) -> Self {
+        match category {
+            EmitterCategory::Light | EmitterCategory::Small => Self::PistonAircraft,
+            EmitterCategory::Large
+            | EmitterCategory::HighVortexLarge
+            | EmitterCategory::Heavy
+            | EmitterCategory::HighlyManeuverable => Self::JetTurboprop,
+            EmitterCategory::Rotorcraft => Self::Helicopter,
+            EmitterCategory::GliderSailplane => Self::Glider,
+            EmitterCategory::LighterThanAir => Self::Balloon,
+            EmitterCategory::ParachutistSkyDiver => Self::Skydiver,
+            EmitterCategory::UltraLightHangGliderParaglider => Self::HangGlider,
+            EmitterCategory::UnmannedAerialVehicle => Self::UnmannedAerialVehicle,
+            EmitterCategory::PointObstacleIncludesTetheredBalloons
+            | EmitterCategory::ClusterObstacle
+            | EmitterCategory::LineObstacle => Self::StaticObstacle,
+            EmitterCategory::NoInformation
+            | EmitterCategory::SpaceTransatmosphericVehicle
+            | EmitterCategory::SurfaceVehicleEmergencyVehicle
+            | EmitterCategory::SurfaceVehicleServiceVehicle => Self::Unknown,
+        }
+    }
+}
+
+impl From<FlarmAircraftType> for EmitterCategory {
+    fn from(aircraft_type: FlarmAircraftType) -> Self {
+        match aircraft_type {
+            FlarmAircraftType::Glider => Self::GliderSailplane,
+            FlarmAircraftType::TowPlane
+            | FlarmAircraftType::DropPlane
+            | FlarmAircraftType::PistonAircraft => Self::Light,
+            FlarmAircraftType::JetTurboprop => Self::Small,
+            FlarmAircraftType::Helicopter => Self::Rotorcraft,
+            FlarmAircraftType::Skydiver => Self::ParachutistSkyDiver,
+            FlarmAircraftType::HangGlider | FlarmAircraftType::Paraglider => {
+                Self::UltraLightHangGliderParaglider
+            }
+            FlarmAircraftType::Balloon | FlarmAircraftType::Airship => Self::LighterThanAir,
+            FlarmAircraftType::UnmannedAerialVehicle => Self::UnmannedAerialVehicle,
+            FlarmAircraftType::StaticObstacle => Self::PointObstacleIncludesTetheredBalloons,
+            FlarmAircraftType::Unknown => Self::NoInformation,
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    num_enum::FromPrimitive,
+    num_enum::IntoPrimitive,
+)]
+#[repr(u8)]
+/// `AlarmLevel` of `$PFLAA` and `$PFLAU`, by time to impact
+pub enum AlarmLevel {
+    #[default]
+    NoAlarm = 0,
+    /// 13 to 18 seconds
+    Low = 1,
+    /// 9 to 12 seconds
+    Important = 2,
+    /// 0 to 8 seconds
+    Urgent = 3,
+}
+
+impl AlarmLevel {
+    #[must_use]
+    pub fn from_time_to_impact(seconds: f64) -> Self {
+        if !(0.0..=18.0).contains(&seconds) {
+            Self::NoAlarm
+        } else if seconds <= 8.0 {
+            Self::Urgent
+        } else if seconds <= 12.0 {
+            Self::Important
+        } else {
+            Self::Low
+        }
+    }
+}
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, num_enum::FromPrimitive, num_enum::IntoPrimitive,
+)]
+#[repr(u8)]
+/// `IDType` of `$PFLAA`, what kind of address `ID` is
+pub enum FlarmIdType {
+    #[default]
+    Random = 0,
+    Icao = 1,
+    Flarm = 2,
+}
+
+impl From<AddressType> for FlarmIdType {
+    fn from(address_type: AddressType) -> Self {
+        match address_type {
+            AddressType::AdsbIcao | AddressType::TisbIcao => Self::Icao,
+            _ => Self::Random,
+        }
+    }
+}
+
+impl From<AlarmLevel> for TrafficAlertStatus {
+    fn from(alarm_level: AlarmLevel) -> Self {
+        match alarm_level {
+            AlarmLevel::NoAlarm => Self::NoAlert,
+            AlarmLevel::Low | AlarmLevel::Important | AlarmLevel::Urgent => Self::TrafficAlert,
+        }
+    }
+}
+
+impl From<FlarmIdType> for AddressType {
+    /// FLARM and random IDs are self-assigned
+    fn from(id_type: FlarmIdType) -> Self {
+        match id_type {
+            FlarmIdType::Icao => Self::AdsbIcao,
+            FlarmIdType::Random | FlarmIdType::Flarm => Self::AdsbSelfAssigned,
+        }
+    }
+}
+
+/// XOR of the bytes between `$` and `*`
+#[must_use]
+pub fn nmea_checksum(body: &str) -> u8 {
+    body.bytes().fold(0, |checksum, byte| checksum ^ byte)
+}
+
+/// `$<body>*<checksum>\r\n`
+#[must_use]
+pub fn nmea_sentence(body: &str) -> String {
+    format!("${body}*{:02X}\r\n", nmea_checksum(body))
+}
+
+/// Sentence type and fields of `$<body>*<checksum>`, e.g. `("PFLAU", ["0", "1", ...])`.
+///
+/// Surrounding whitespace, including the line ending, is ignored.
+///
+/// # Errors
+///
+/// If the `$` or the checksum is missing, or the checksum doesn't match.
+pub fn nmea_fields(sentence: &str) -> GDL90Result<(&str, Vec<&str>)> {
+    let sentence = sentence.trim();
+    let invalid = |reason: &str| GDL90Error::InvalidNmea(format!("{reason}: {sentence}"));
+
+    let (body, checksum) = sentence
+        .strip_prefix('$')
+        .and_then(|sentence| sentence.split_once('*'))
+        .ok_or_else(|| invalid("not a sentence"))?;
+    if u8::from_str_radix(checksum, 16).ok() != Some(nmea_checksum(body)) {
+        bail!(invalid("checksum mismatch"));
+    }
+
+    let (sentence_type, fields) = body.split_once(',').unwrap_or((body, ""));
+    Ok((sentence_type, fields.split(',').collect()))
+}
+
+/// Field `index`, `None` if it's empty or missing
+pub(crate) fn parse_field<T: FromStr>(fields: &[&str], index: usize) -> GDL90Result<Option<T>> {
+    match fields.get(index).copied().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(field) => field
+            .parse()
+            .map(Some)
+            .map_err(|_| GDL90Error::InvalidNmea(format!("field {index}: {field:?}"))),
+    }
+}
+
+/// Hex field `index`, `None` if it's empty or missing
+pub(crate) fn parse_hex_field(fields: &[&str], index: usize) -> GDL90Result<Option<u32>> {
+    match fields.get(index).copied().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(field) => u32::from_str_radix(field, 16)
+            .map(Some)
+            .map_err(|_| GDL90Error::InvalidNmea(format!("field {index}: {field:?}"))),
+    }
+}
+
+/// 24 bit hex address field `index`, like `parse_hex_field()` but larger values are rejected
+pub(crate) fn parse_address_field(fields: &[&str], index: usize) -> GDL90Result<Option<u32>> {
+    match parse_hex_field(fields, index)? {
+        Some(address) if address > 0xFF_FFFF => Err(GDL90Error::InvalidNmea(format!(
+            "field {index}: address {address:X} is longer than 24 bits"
+        ))),
+        address => Ok(address),
+    }
+}
+
+/// Field `index` that must not be empty
+pub(crate) fn required<T>(value: Option<T>, index: usize) -> GDL90Result<T> {
+    value.ok_or_else(|| GDL90Error::InvalidNmea(format!("field {index} is empty")))
+}
+
+/// `Some` value or an empty field
+pub(crate) fn optional_field<T: std::fmt::Display>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentence() {
+        assert_eq!(
+            nmea_sentence("PFLAU,0,1,2,1,0,,0,,,"),
+            "$PFLAU,0,1,2,1,0,,0,,,*4C\r\n"
+        );
+        assert_eq!(AlarmLevel::from_time_to_impact(10.0), AlarmLevel::Important);
+        assert_eq!(AlarmLevel::from_time_to_impact(30.0), AlarmLevel::NoAlarm);
+        assert_eq!(
+            FlarmAircraftType::from(EmitterCategory::GliderSailplane),
+            FlarmAircraftType::Glider
+        );
+        assert_eq!(FlarmAircraftType::from(0xE_u8), FlarmAircraftType::Unknown);
+        assert_eq!(
+            EmitterCategory::from(FlarmAircraftType::Paraglider),
+            EmitterCategory::UltraLightHangGliderParaglider
+        );
+        assert_eq!(
+            TrafficAlertStatus::from(AlarmLevel::Important),
+            TrafficAlertStatus::TrafficAlert
+        );
+    }
+
+    #[test]
+    fn fields() {
+        assert_eq!(
+            nmea_fields("$PFLAU,0,1,2,1,0,,0,,,*4C\r\n").unwrap(),
+            ("PFLAU", vec!["0", "1", "2", "1", "0", "", "0", "", "", ""])
+        );
+        assert!(matches!(
+            nmea_fields("$PFLAU,0,1,2,1,0,,0,,,*4D"),
+            Err(GDL90Error::InvalidNmea(_))
+        ));
+        assert!(nmea_fields("PFLAU,0,1,2,1,0,,0,,,*4C").is_err());
+        assert!(nmea_fields("$PFLAU,0,1,2,1,0,,0,,,").is_err());
+
+        let fields = ["12", "", "x", "3D1234", "1234ABCD"];
+        assert_eq!(parse_field::<i32>(&fields, 0).unwrap(), Some(12));
+        assert_eq!(parse_field::<i32>(&fields, 1).unwrap(), None);
+        assert_eq!(parse_field::<i32>(&fields, 9).unwrap(), None);
+        assert!(parse_field::<i32>(&fields, 2).is_err());
+        assert_eq!(parse_hex_field(&fields, 3).unwrap(), Some(0x3D1234));
+        assert_eq!(parse_address_field(&fields, 3).unwrap(), Some(0x3D1234));
+        assert_eq!(parse_address_field(&fields, 1).unwrap(), None);
+        assert!(matches!(
+            parse_address_field(&fields, 4),
+            Err(GDL90Error::InvalidNmea(_))
+        ));
+    }
+}
@@ -0,0 +1,255 @@
+use crate::{
+    flarm::{gps::parse_nmea_position, parse_field},
+    prelude::*,
+};
+
+/// Longer lines are dropped, NMEA sentences are at most 82 characters
+const MAX_LINE_LENGTH: usize = 256;
+
+/// Turns the FLARM NMEA of a PowerFLARM or similar device into absolute `TrafficReport`s.
+///
+/// `$PFLAA` targets are relative to ownship, so nothing is returned until there is an ownship position,
+/// either set from GDL90 ownship reports with `set_ownship()` or taken from the device's own
+/// `$GPRMC` and `$GPGGA`, whichever came last.
+/// A `$PFLAU` alarm applies to the `$PFLAA` of the target it names, which follows it.
+///
+/// Report altitudes are pressure altitudes as in GDL90, from `set_ownship()` or the device's
+/// barometric `$PGRMZ`. The GPS altitude of `$GPGGA` is kept apart as `geometric_altitude()`.
+///
+/// ```ignore
+/// let mut decoder = FlarmDecoder::default();
+/// let mut buf = [0; 1024];
+/// loop {
+///     let len = port.read(&mut buf).await?;
+///     for report in decoder.push_bytes(&buf[..len]).into_iter().flatten() {
+///         server.update_traffic(report).await?;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FlarmDecoder {
+    ownship: Option<TrafficReport>,
+    /// MSL altitude of the last `$GPGGA`
+    geometric_altitude: Option<Length>,
+    /// ID and level of the last `$PFLAU` alarm
+    alarm: Option<(u32, AlarmLevel)>,
+    line: Vec<u8>,
+}
+
+impl FlarmDecoder {
+    pub fn set_ownship(&mut self, ownship: TrafficReport) {
+        self.ownship = Some(ownship);
+    }
+
+    #[must_use]
+    pub fn ownship(&self) -> Option<&TrafficReport> {
+        self.ownship.as_ref()
+    }
+
+    #[must_use]
+    pub fn geometric_altitude(&self) -> Option<Length> {
+        self.geometric_altitude
+    }
+
+    /// Returns the results of the lines `bytes` completes, see `push_sentence()`.
+    ///
+    /// Lines that don't start with `$`, e.g. a device banner, are skipped.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Vec<GDL90Result<TrafficReport>> {
+        let mut reports = vec![];
+
+        for &byte in bytes {
+            if byte != b'\n' {
+                if self.line.len() < MAX_LINE_LENGTH {
+                    self.line.push(byte);
+                }
+                continue;
+            }
+
+            let line = std::mem::take(&mut self.line);
+            let line = String::from_utf8_lossy(&line);
+            if line.len() >= MAX_LINE_LENGTH || !line.trim_start().starts_with('$') {
+                continue;
+            }
+            reports.extend(self.push_sentence(&line).transpose());
+        }
+
+        reports
+    }
+
+    /// Returns the target of a `$PFLAA`.
+    ///
+    /// `None` without an ownship position and for other sentences, which only update the decoder.
+    ///
+    /// # Errors
+    ///
+    /// If the sentence or a field is malformed.
+    pub fn push_sentence(&mut self, sentence: &str) -> GDL90Result<Option<TrafficReport>> {
+        let (sentence_type, fields) = nmea_fields(sentence)?;
+
+        match sentence_type {
+            "PFLAA" => {
+                let mut pflaa = Pflaa::from_fields(&fields)?;
+                if let Some((_, alarm_level)) = self.alarm.filter(|(id, _)| *id == pflaa.id) {
+                    pflaa.alarm_level = pflaa.alarm_level.max(alarm_level);
+                }
+
+                Ok(self
+                    .ownship
+                    .as_ref()
+                    .filter(|ownship| ownship.has_position())
+                    .map(|ownship| pflaa.to_report(ownship)))
+            }
+            "PFLAU" => {
+                let pflau = Pflau::from_fields(&fields)?;
+                self.alarm = pflau
+                    .id
+                    .filter(|_| pflau.alarm_level != AlarmLevel::NoAlarm)
+                    .map(|id| (id, pflau.alarm_level));
+                Ok(None)
+            }
+            "GPRMC" | "GNRMC" => {
+                // without a fix the last position is kept
+                if fields.get(1).copied() != Some("A") {
+                    return Ok(None);
+                }
+                if let Some((latitude, longitude)) = parse_nmea_position(&fields, 2)? {
+                    let ownship = self.ownship.get_or_insert_with(TrafficReport::default);
+                    ownship.latitude = latitude;
+                    ownship.longitude = longitude;
+                    ownship.horizontal_velocity =
+                        parse_field::<f64>(&fields, 6)?.map(IntoUom::knots);
+                    let track = parse_field::<f64>(&fields, 7)?;
+                    ownship.track_heading = track.map(IntoUom::degrees).unwrap_or_default();
+                    ownship.miscellaneous_indicators.track_heading_type = if track.is_some() {
+                        TrackHeadingType::TrueTrackAngle
+                    } else {
+                        TrackHeadingType::NotValid
+                    };
+                }
+                Ok(None)
+            }
+            "GPGGA" | "GNGGA" => {
+                if matches!(parse_field::<u8>(&fields, 5)?, None | Some(0)) {
+                    return Ok(None);
+                }
+                if let Some((latitude, longitude)) = parse_nmea_position(&fields, 1)? {
+                    let ownship = self.ownship.get_or_insert_with(TrafficReport::default);
+                    ownship.latitude = latitude;
+                    ownship.longitude = longitude;
+                    if let Some(altitude) = parse_field::<f64>(&fields, 8)? {
+                        self.geometric_altitude = Some(altitude.meters());
+                    }
+                }
+                Ok(None)
+            }
+            "PGRMZ" => {
+                // feet unless the unit says meters
+                let altitude = parse_field::<f64>(&fields, 0)?.map(|altitude| {
+                    if fields.get(1).copied() == Some("M") {
+                        altitude.meters()
+                    } else {
+                        altitude.feet()
+                    }
+                });
+                if let Some(altitude) = altitude {
+                    self.ownship
+                        .get_or_insert_with(TrafficReport::default)
+                        .altitude = Some(altitude);
+                }
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::flarm::fixtures::{glider, ownship};
+
+    #[test]
+    fn traffic() {
+        let mut decoder = FlarmDecoder::default();
+        let pflaa = Pflaa::from_reports(&ownship(), &glider()).unwrap();
+        assert!(
+            decoder
+                .push_sentence(&pflaa.to_sentence())
+                .unwrap()
+                .is_none()
+        );
+
+        decoder.set_ownship(ownship());
+        let pflau = nmea_sentence("PFLAU,1,1,2,1,2,0,2,0,1000,3D1234");
+        assert!(decoder.push_sentence(&pflau).unwrap().is_none());
+        let report = decoder
+            .push_sentence(&pflaa.to_sentence())
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.target_identity, glider().target_identity);
+        assert_eq!(
+            report.traffic_alert_status,
+            TrafficAlertStatus::TrafficAlert
+        );
+        assert_eq_f!(report.latitude, glider().latitude, 1e-6);
+
+        // the alarm was for another target
+        let pflau = nmea_sentence("PFLAU,1,1,2,1,2,0,2,0,1000,3D0001");
+        decoder.push_sentence(&pflau).unwrap();
+        let report = decoder
+            .push_sentence(&pflaa.to_sentence())
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.traffic_alert_status, TrafficAlertStatus::NoAlert);
+
+        assert!(decoder.push_sentence("$PFLAA,0*00").is_err());
+        assert!(
+            decoder
+                .push_sentence(&nmea_sentence("PGRMZ,1000,F,2"))
+                .unwrap()
+                .is_none()
+        );
+        assert_eq_f!(
+            decoder.ownship().unwrap().altitude.unwrap(),
+            1000.0.feet(),
+            1e-9
+        );
+    }
+
+    #[test]
+    fn bytes() {
+        let mut decoder = FlarmDecoder::default();
+        let time = Utc::now();
+        let pflaa = Pflaa::from_reports(&ownship(), &glider())
+            .unwrap()
+            .to_sentence();
+
+        let stream = [
+            "PowerFLARM banner\r\n".to_string(),
+            gprmc(&ownship(), time),
+            gpgga(&ownship(), Some(1000.0.meters()), time),
+            nmea_sentence("PGRMZ,3000,F,2"),
+            nmea_sentence("PFLAU,1,1,2,1,0,0,0,0,1000,3D1234"),
+            pflaa.replace("*", "0*"),
+            pflaa,
+        ]
+        .concat();
+        let (first, second) = stream.as_bytes().split_at(100);
+        assert!(decoder.push_bytes(first).is_empty());
+        let results = decoder.push_bytes(second);
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[0], Err(GDL90Error::InvalidNmea(_))));
+
+        let ownship = decoder.ownship().unwrap();
+        assert_eq_f!(ownship.latitude, 45.0.degrees(), 1e-6);
+        assert_eq_f!(ownship.altitude.unwrap(), 3000.0.feet(), 1e-9);
+        assert_eq_f!(ownship.track_heading, 0.0.degrees(), 1e-9);
+        assert_eq_f!(decoder.geometric_altitude().unwrap(), 1000.0.meters(), 1e-9);
+
+        let report = results[1].as_ref().unwrap();
+        assert_eq_f!(report.latitude, glider().latitude, 1e-6);
+        assert_eq_f!(report.altitude.unwrap(), 3000.0.feet(), 1e-9);
+    }
+}
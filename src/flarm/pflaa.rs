@@ -0,0 +1,347 @@
+use std::str::FromStr;
+
+use crate::{
+    flarm::{optional_field, parse_address_field, parse_field, parse_hex_field, required},
+    prelude::*,
+};
+
+/// Horizontal miss distance at the closest point of approach below which a target raises an alarm
+const PROTECTED_RADIUS_M: f64 = 150.0;
+
+/// Vertical miss distance at the closest point of approach below which a target raises an alarm
+const PROTECTED_HEIGHT_M: f64 = 100.0;
+
+/// `$PFLAA`, a traffic target relative to ownship
+#[derive(Debug, Clone, Copy, PartialEq, Builder)]
+pub struct Pflaa {
+    pub alarm_level: AlarmLevel,
+
+    pub relative_north: Length,
+
+    pub relative_east: Length,
+
+    /// Positive if the target is above ownship, `None` if either altitude is unknown
+    pub relative_vertical: Option<Length>,
+
+    pub id_type: FlarmIdType,
+
+    /// 24 bit address
+    pub id: u32,
+
+    /// True track
+    pub track: Option<Angle>,
+
+    pub ground_speed: Option<Velocity>,
+
+    pub climb_rate: Option<Velocity>,
+
+    pub aircraft_type: FlarmAircraftType,
+}
+
+impl Pflaa {
+    /// `target` relative to `ownship`, `None` if either has no valid position.
+    ///
+    /// The relative vertical distance is between the pressure altitudes.
+    /// The alarm level is estimated from the closest point of approach with both aircraft keeping
+    /// their velocities, GDL90 traffic alerts are at least `AlarmLevel::Low`.
+    #[must_use]
+    pub fn from_reports(ownship: &TrafficReport, target: &TrafficReport) -> Option<Self> {
+        if !ownship.has_position() || !target.has_position() {
+            return None;
+        }
+
+        let (relative_north, relative_east) = relative_position(
+            ownship.latitude,
+            ownship.longitude,
+            target.latitude,
+            target.longitude,
+        );
+        let relative_vertical = target
+            .altitude
+            .zip(ownship.altitude)
+            .map(|(target, ownship)| target - ownship);
+
+        let mut alarm_level = relative_vertical.map_or(AlarmLevel::NoAlarm, |vertical| {
+            closest_approach_alarm(
+                relative_north.meters(),
+                relative_east.meters(),
+                vertical.meters(),
+                ownship,
+                target,
+            )
+        });
+        if target.traffic_alert_status == TrafficAlertStatus::TrafficAlert {
+            alarm_level = alarm_level.max(AlarmLevel::Low);
+        }
+
+        Some(Self {
+            alarm_level,
+            relative_north,
+            relative_east,
+            relative_vertical,
+            id_type: target.target_identity.address_type.into(),
+            id: target.target_identity.participant_address,
+            track: (target.miscellaneous_indicators.track_heading_type
+                != TrackHeadingType::NotValid)
+                .then_some(target.track_heading),
+            ground_speed: target.horizontal_velocity,
+            climb_rate: target.vertical_velocity,
+            aircraft_type: target.emitter_category.into(),
+        })
+    }
+
+    /// The target as an absolute `TrafficReport`, `ownship` being where the FLARM is.
+    ///
+    /// The altitude is the ownship altitude plus the relative vertical distance.
+    /// Alarms are traffic alerts, there is no callsign, NIC or NACp.
+    #[must_use]
+    pub fn to_report(&self, ownship: &TrafficReport) -> TrafficReport {
+        let (latitude, longitude) = offset_position(
+            ownship.latitude,
+            ownship.longitude,
+            self.relative_north,
+            self.relative_east,
+        );
+        let track_heading_type = if self.track.is_some() {
+            TrackHeadingType::TrueTrackAngle
+        } else {
+            TrackHeadingType::NotValid
+        };
+
+        TrafficReport {
+            traffic_alert_status: self.alarm_level.into(),
+            target_identity: TargetIdentity::new(self.id_type.into(), self.id),
+            latitude,
+            longitude,
+            altitude: ownship
+                .altitude
+                .zip(self.relative_vertical)
+                .map(|(altitude, vertical)| altitude + vertical),
+            miscellaneous_indicators: MiscellaneousIndicators::default()
+                .with_air_ground_state(AirGroundState::Airborne)
+                .with_track_heading_type(track_heading_type),
+            horizontal_velocity: self.ground_speed,
+            vertical_velocity: self.climb_rate,
+            track_heading: self.track.unwrap_or_default(),
+            emitter_category: self.aircraft_type.into(),
+            ..TrafficReport::default()
+        }
+    }
+
+    /// Fields after the sentence type
+    pub(crate) fn from_fields(fields: &[&str]) -> GDL90Result<Self> {
+        Ok(Self {
+            alarm_level: parse_field::<u8>(fields, 0)?.unwrap_or_default().into(),
+            relative_north: required(parse_field::<f64>(fields, 1)?, 1)?.meters(),
+            relative_east: required(parse_field::<f64>(fields, 2)?, 2)?.meters(),
+            relative_vertical: parse_field::<f64>(fields, 3)?.map(IntoUom::meters),
+            id_type: parse_field::<u8>(fields, 4)?.unwrap_or_default().into(),
+            id: required(parse_address_field(fields, 5)?, 5)?,
+            track: parse_field::<f64>(fields, 6)?.map(IntoUom::degrees),
+            ground_speed: parse_field::<f64>(fields, 8)?.map(IntoUom::meters_per_second),
+            climb_rate: parse_field::<f64>(fields, 9)?.map(IntoUom::meters_per_second),
+            aircraft_type: parse_hex_field(fields, 10)?
+                .and_then(|aircraft_type| u8::try_from(aircraft_type).ok())
+                .unwrap_or_default()
+                .into(),
+        })
+    }
+
+    /// Horizontal distance to ownship
+    #[must_use]
+    pub fn distance(&self) -> Length {
+        self.relative_north.hypot(self.relative_east)
+    }
+
+    /// Meters and m/s are rounded to integers, the climb rate to 0.1 m/s, the turn rate is left empty
+    #[must_use]
+    pub fn to_sentence(&self) -> String {
+        let meters = |length: Length| -> i32 { length.meters().round().clamp_into() };
+
+        nmea_sentence(&format!(
+            "PFLAA,{},{},{},{},{},{:06X},{},,{},{},{:X}",
+            u8::from(self.alarm_level),
+            meters(self.relative_north),
+            meters(self.relative_east),
+            optional_field(self.relative_vertical.map(meters)),
+            u8::from(self.id_type),
+            self.id,
+            optional_field(self.track.map(|track| -> u16 {
+                let degrees: u16 = track.degrees().rem_euclid(360.0).round().clamp_into();
+                degrees % 360
+            })),
+            optional_field(
+                self.ground_speed
+                    .map(|speed| -> u16 { speed.meters_per_second().round().clamp_into() })
+            ),
+            optional_field(
+                self.climb_rate
+                    .map(|climb| format!("{:.1}", climb.meters_per_second()))
+            ),
+            u8::from(self.aircraft_type),
+        ))
+    }
+}
+
+impl FromStr for Pflaa {
+    type Err = GDL90Error;
+
+    /// A `$PFLAA` sentence, the turn rate is ignored
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match nmea_fields(s)? {
+            ("PFLAA", fields) => Self::from_fields(&fields),
+            (sentence_type, _) => Err(GDL90Error::InvalidNmea(format!(
+                "expected PFLAA, got {sentence_type}"
+            ))),
+        }
+    }
+}
+
+/// North and east ground speed in m/s, `None` without a valid track
+fn velocity(report: &TrafficReport) -> Option<(f64, f64)> {
+    if report.miscellaneous_indicators.track_heading_type == TrackHeadingType::NotValid {
+        return None;
+    }
+    let speed = report.horizontal_velocity?.meters_per_second();
+    let track = report.track_heading.radians();
+    Some((speed * track.cos(), speed * track.sin()))
+}
+
+/// Alarm level of a target at `north`, `east` and `vertical` meters from ownship
+fn closest_approach_alarm(
+    north: f64,
+    east: f64,
+    vertical: f64,
+    ownship: &TrafficReport,
+    target: &TrafficReport,
+) -> AlarmLevel {
+    let (own_north, own_east) = velocity(ownship).unwrap_or_default();
+    let (target_north, target_east) = velocity(target).unwrap_or_default();
+    let (closing_north, closing_east) = (target_north - own_north, target_east - own_east);
+
+    let closing_squared = closing_north.powi(2) + closing_east.powi(2);
+    if closing_squared < f64::EPSILON {
+        return AlarmLevel::NoAlarm;
+    }
+
+    let time = -(north * closing_north + east * closing_east) / closing_squared;
+    let horizontal_miss = (north + closing_north * time).hypot(east + closing_east * time);
+    let climb = |report: &TrafficReport| {
+        report
+            .vertical_velocity
+            .map_or(0.0, |climb| climb.meters_per_second())
+    };
+    let vertical_miss = vertical + (climb(target) - climb(ownship)) * time;
+
+    if horizontal_miss > PROTECTED_RADIUS_M || vertical_miss.abs() > PROTECTED_HEIGHT_M {
+        return AlarmLevel::NoAlarm;
+    }
+    AlarmLevel::from_time_to_impact(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flarm::fixtures::{glider, ownship};
+
+    #[test]
+    fn head_on() {
+        let pflaa = Pflaa::from_reports(&ownship(), &glider()).unwrap();
+        // 1000 m at 51.4 m/s closing is 19.4 s to impact, 500 m is 9.7 s
+        assert_eq!(pflaa.alarm_level, AlarmLevel::NoAlarm);
+        assert_eq_f!(pflaa.distance(), 1000.0.meters(), 1.0);
+        assert_eq!(
+            pflaa.to_sentence(),
+            nmea_sentence("PFLAA,0,1000,0,0,1,3D1234,180,,26,0.7,1")
+        );
+
+        let closer = glider().with_latitude((45.0 + 500.0 / 111_195.0).degrees());
+        let pflaa = Pflaa::from_reports(&ownship(), &closer).unwrap();
+        assert_eq!(pflaa.alarm_level, AlarmLevel::Important);
+
+        // passing 300 m abeam
+        let abeam = closer.with_longitude((-122.0 + 300.0 / 78_630.0).degrees());
+        let pflaa = Pflaa::from_reports(&ownship(), &abeam).unwrap();
+        assert_eq!(pflaa.alarm_level, AlarmLevel::NoAlarm);
+
+        let alert = abeam.with_traffic_alert_status(TrafficAlertStatus::TrafficAlert);
+        let pflaa = Pflaa::from_reports(&ownship(), &alert).unwrap();
+        assert_eq!(pflaa.alarm_level, AlarmLevel::Low);
+    }
+
+    #[test]
+    fn unknown_values() {
+        let target = TrafficReport {
+            altitude: None,
+            horizontal_velocity: None,
+            vertical_velocity: None,
+            ..glider().with_miscellaneous_indicators(MiscellaneousIndicators::default())
+        };
+        let pflaa = Pflaa::from_reports(&ownship(), &target).unwrap();
+        assert_eq!(pflaa.alarm_level, AlarmLevel::NoAlarm);
+        assert_eq!(
+            pflaa.to_sentence(),
+            nmea_sentence("PFLAA,0,1000,0,,1,3D1234,,,,,1")
+        );
+
+        let no_position = ownship()
+            .with_latitude(0.0.degrees())
+            .with_longitude(0.0.degrees());
+        assert!(Pflaa::from_reports(&no_position, &glider()).is_none());
+    }
+
+    #[test]
+    fn parse() {
+        let pflaa = "$PFLAA,2,-1234,567,-89,1,3D1234,271,,26,-1.5,1*31\r\n"
+            .parse::<Pflaa>()
+            .unwrap();
+        assert_eq!(pflaa.alarm_level, AlarmLevel::Important);
+        assert_eq_f!(pflaa.relative_north, (-1234.0).meters(), 1e-9);
+        assert_eq_f!(pflaa.relative_vertical.unwrap(), (-89.0).meters(), 1e-9);
+        assert_eq!(pflaa.id_type, FlarmIdType::Icao);
+        assert_eq!(pflaa.id, 0x3D1234);
+        assert_eq!(pflaa.aircraft_type, FlarmAircraftType::Glider);
+        assert_eq!(
+            pflaa.to_sentence(),
+            "$PFLAA,2,-1234,567,-89,1,3D1234,271,,26,-1.5,1*31\r\n"
+        );
+
+        // older devices leave out the ID type and aircraft type
+        let pflaa = nmea_sentence("PFLAA,0,100,200,,,DDA85C,,,,")
+            .parse::<Pflaa>()
+            .unwrap();
+        assert_eq!(pflaa.id_type, FlarmIdType::Random);
+        assert_eq!(pflaa.aircraft_type, FlarmAircraftType::Unknown);
+        assert_eq!(pflaa.track, None);
+
+        assert!(
+            nmea_sentence("PFLAA,0,,200,,,DDA85C,,,,")
+                .parse::<Pflaa>()
+                .is_err()
+        );
+        assert!(
+            nmea_sentence("PFLAU,0,1,2,1,0,,0,,,")
+                .parse::<Pflaa>()
+                .is_err()
+        );
+        assert!(matches!(
+            nmea_sentence("PFLAA,0,100,200,,,1234ABCD,,,,").parse::<Pflaa>(),
+            Err(GDL90Error::InvalidNmea(_))
+        ));
+    }
+
+    #[test]
+    fn report() {
+        let pflaa = Pflaa::from_reports(&ownship(), &glider()).unwrap();
+        let report = pflaa.to_report(&ownship());
+
+        assert_eq!(report.target_identity, glider().target_identity);
+        assert_eq!(report.emitter_category, EmitterCategory::GliderSailplane);
+        assert_eq!(report.traffic_alert_status, TrafficAlertStatus::NoAlert);
+        assert_eq_f!(report.latitude, glider().latitude, 1e-9);
+        assert_eq_f!(report.longitude, glider().longitude, 1e-9);
+        assert_eq_f!(report.altitude.unwrap(), 3000.0.feet(), 1e-9);
+        assert_eq_f!(report.track_heading, 180.0.degrees(), 1e-9);
+        assert_eq_f!(report.horizontal_velocity.unwrap(), 50.0.knots(), 1e-9);
+    }
+}
@@ -0,0 +1,243 @@
+use std::str::FromStr;
+
+use crate::{
+    flarm::{optional_field, parse_address_field, parse_field},
+    prelude::*,
+};
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, num_enum::FromPrimitive, num_enum::IntoPrimitive,
+)]
+#[repr(u8)]
+/// `GPS` of `$PFLAU`
+pub enum FlarmGpsStatus {
+    #[default]
+    NoFix = 0,
+    OnGround = 1,
+    Airborne = 2,
+}
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, num_enum::FromPrimitive, num_enum::IntoPrimitive,
+)]
+#[repr(u8)]
+/// `AlarmType` of `$PFLAU`
+pub enum FlarmAlarmType {
+    /// No alarm, the target fields are traffic information
+    #[default]
+    NoAlarm = 0,
+    Aircraft = 2,
+    /// Obstacle or alert zone
+    Obstacle = 3,
+    TrafficAdvisory = 4,
+}
+
+/// `$PFLAU`, device status and the most relevant target
+#[derive(Debug, Clone, Copy, PartialEq, Builder)]
+pub struct Pflau {
+    /// Number of targets received, at most 99
+    pub rx: u8,
+
+    pub tx: bool,
+
+    pub gps: FlarmGpsStatus,
+
+    pub power: bool,
+
+    pub alarm_level: AlarmLevel,
+
+    /// -180 to 180 degrees, relative to the ownship track
+    pub relative_bearing: Option<Angle>,
+
+    pub alarm_type: FlarmAlarmType,
+
+    /// Positive if the target is above ownship
+    pub relative_vertical: Option<Length>,
+
+    pub relative_distance: Option<Length>,
+
+    /// 24 bit address
+    pub id: Option<u32>,
+}
+
+impl Pflau {
+    /// Status for `targets`, reporting the one with the highest alarm level, the nearest on a tie.
+    ///
+    /// The GPS status is `NoFix` without an ownship position.
+    /// The bearing is relative to true north if the ownship track isn't valid.
+    #[must_use]
+    pub fn from_targets(ownship: Option<&TrafficReport>, targets: &[Pflaa]) -> Self {
+        let gps = match ownship {
+            Some(report) if report.has_position() => {
+                match report.miscellaneous_indicators.air_ground_state {
+                    AirGroundState::Airborne => FlarmGpsStatus::Airborne,
+                    AirGroundState::OnGround => FlarmGpsStatus::OnGround,
+                }
+            }
+            _ => FlarmGpsStatus::NoFix,
+        };
+        let ownship_track = ownship
+            .filter(|report| {
+                report.miscellaneous_indicators.track_heading_type != TrackHeadingType::NotValid
+            })
+            .map_or(0.0, |report| report.track_heading.degrees());
+
+        let target = targets.iter().max_by(|a, b| {
+            a.alarm_level
+                .cmp(&b.alarm_level)
+                .then_with(|| b.distance().value.total_cmp(&a.distance().value))
+        });
+        let alarm_level = target.map_or(AlarmLevel::NoAlarm, |target| target.alarm_level);
+
+        Self {
+            rx: u8::try_from(targets.len()).unwrap_or(u8::MAX).min(99),
+            tx: true,
+            gps,
+            power: true,
+            alarm_level,
+            relative_bearing: target.map(|target| {
+                let bearing = target
+                    .relative_east
+                    .value
+                    .atan2(target.relative_north.value)
+                    .to_degrees();
+                ((bearing - ownship_track + 540.0).rem_euclid(360.0) - 180.0).degrees()
+            }),
+            alarm_type: if alarm_level == AlarmLevel::NoAlarm {
+                FlarmAlarmType::NoAlarm
+            } else {
+                FlarmAlarmType::Aircraft
+            },
+            relative_vertical: target.and_then(|target| target.relative_vertical),
+            relative_distance: target.map(Pflaa::distance),
+            id: target.map(|target| target.id),
+        }
+    }
+
+    /// Fields after the sentence type
+    pub(crate) fn from_fields(fields: &[&str]) -> GDL90Result<Self> {
+        Ok(Self {
+            rx: parse_field(fields, 0)?.unwrap_or_default(),
+            tx: parse_field::<u8>(fields, 1)? == Some(1),
+            gps: parse_field::<u8>(fields, 2)?.unwrap_or_default().into(),
+            power: parse_field::<u8>(fields, 3)? == Some(1),
+            alarm_level: parse_field::<u8>(fields, 4)?.unwrap_or_default().into(),
+            relative_bearing: parse_field::<f64>(fields, 5)?.map(IntoUom::degrees),
+            alarm_type: parse_field::<u8>(fields, 6)?.unwrap_or_default().into(),
+            relative_vertical: parse_field::<f64>(fields, 7)?.map(IntoUom::meters),
+            relative_distance: parse_field::<f64>(fields, 8)?.map(IntoUom::meters),
+            id: parse_address_field(fields, 9)?,
+        })
+    }
+
+    #[must_use]
+    pub fn to_sentence(&self) -> String {
+        let meters = |length: Length| -> i32 { length.meters().round().clamp_into() };
+
+        nmea_sentence(&format!(
+            "PFLAU,{},{},{},{},{},{},{},{},{},{}",
+            self.rx,
+            u8::from(self.tx),
+            u8::from(self.gps),
+            u8::from(self.power),
+            u8::from(self.alarm_level),
+            optional_field(
+                self.relative_bearing
+                    .map(|bearing| -> i16 { bearing.degrees().round().clamp_into() })
+            ),
+            u8::from(self.alarm_type),
+            optional_field(self.relative_vertical.map(meters)),
+            optional_field(self.relative_distance.map(meters)),
+            optional_field(self.id.map(|id| format!("{id:06X}"))),
+        ))
+    }
+}
+
+impl FromStr for Pflau {
+    type Err = GDL90Error;
+
+    /// A `$PFLAU` sentence, the ID is optional as older devices don't send it
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match nmea_fields(s)? {
+            ("PFLAU", fields) => Self::from_fields(&fields),
+            (sentence_type, _) => Err(GDL90Error::InvalidNmea(format!(
+                "expected PFLAU, got {sentence_type}"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flarm::fixtures::{glider, ownship};
+
+    #[test]
+    fn most_relevant_target() {
+        let far = glider()
+            .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, 0x3D0001))
+            .with_latitude(45.1.degrees());
+        let targets = [&far, &glider()]
+            .into_iter()
+            .filter_map(|target| Pflaa::from_reports(&ownship(), target))
+            .collect::<Vec<_>>();
+
+        let pflau = Pflau::from_targets(Some(&ownship()), &targets);
+        assert_eq!(pflau.alarm_level, AlarmLevel::NoAlarm);
+        assert_eq!(
+            pflau.to_sentence(),
+            nmea_sentence("PFLAU,2,1,2,1,0,0,0,0,1000,3D1234")
+        );
+
+        // the alarm wins over the nearer target, 90° right of the ownship track
+        let alarm = far
+            .with_latitude(45.0.degrees())
+            .with_longitude((-122.0 + 2000.0 / 78_630.0).degrees())
+            .with_traffic_alert_status(TrafficAlertStatus::TrafficAlert);
+        let targets = [
+            Pflaa::from_reports(&ownship(), &glider()).unwrap(),
+            Pflaa::from_reports(&ownship(), &alarm).unwrap(),
+        ];
+        let pflau = Pflau::from_targets(Some(&ownship()), &targets);
+        assert_eq!(pflau.alarm_level, AlarmLevel::Low);
+        assert_eq!(pflau.alarm_type, FlarmAlarmType::Aircraft);
+        assert_eq!(
+            pflau.to_sentence(),
+            nmea_sentence("PFLAU,2,1,2,1,1,90,2,0,2000,3D0001")
+        );
+
+        assert_eq!(
+            Pflau::from_targets(None, &[]).to_sentence(),
+            nmea_sentence("PFLAU,0,1,0,1,0,,0,,,")
+        );
+    }
+
+    #[test]
+    fn parse() {
+        let sentence = nmea_sentence("PFLAU,3,1,2,1,2,-30,2,-46,355,3D1234");
+        let pflau = sentence.parse::<Pflau>().unwrap();
+        assert_eq!(pflau.rx, 3);
+        assert_eq!(pflau.gps, FlarmGpsStatus::Airborne);
+        assert_eq!(pflau.alarm_level, AlarmLevel::Important);
+        assert_eq!(pflau.alarm_type, FlarmAlarmType::Aircraft);
+        assert_eq!(pflau.id, Some(0x3D1234));
+        assert_eq!(pflau.to_sentence(), sentence);
+
+        let pflau = nmea_sentence("PFLAU,0,1,1,1,0,,0,,")
+            .parse::<Pflau>()
+            .unwrap();
+        assert_eq!(pflau.gps, FlarmGpsStatus::OnGround);
+        assert_eq!(pflau.relative_bearing, None);
+        assert_eq!(pflau.id, None);
+
+        assert!(
+            nmea_sentence("PFLAU,x,1,1,1,0,,0,,")
+                .parse::<Pflau>()
+                .is_err()
+        );
+        assert!(matches!(
+            nmea_sentence("PFLAU,3,1,2,1,2,-30,2,-46,355,1234ABCD").parse::<Pflau>(),
+            Err(GDL90Error::InvalidNmea(_))
+        ));
+    }
+}
@@ -7,18 +7,28 @@
 #[macro_use]
 extern crate utilities_derive;
 
+pub mod capture;
 pub mod error;
+pub mod export;
+pub mod flarm;
 pub mod message;
 pub mod message_types;
+#[cfg(feature = "net")]
+pub mod net;
 pub mod util;
 
 #[cfg(any(feature = "cxx", feature = "swift"))]
 mod ffi;
 
 pub mod prelude {
+    pub use crate::capture::*;
     pub use crate::error::*;
+    pub use crate::export::*;
+    pub use crate::flarm::*;
     pub use crate::message::*;
     pub use crate::message_types::*;
+    #[cfg(feature = "net")]
+    pub use crate::net::*;
     pub use crate::util::*;
 
     #[cfg(test)]
@@ -0,0 +1,177 @@
+use crate::{
+    message::r#impl::{ESCAPE, ESCAPE_XOR, FLAG},
+    prelude::*,
+};
+
+/// Incremental frame decoder for byte streams (serial, TCP), where frames can be split across reads.
+///
+/// Bytes are buffered until the closing flag byte arrives, complete frames are returned
+/// and the rest is kept for the next call.
+///
+/// ```ignore
+/// let mut decoder = FrameDecoder::default();
+/// assert!(decoder.push(&[0x7E, 0x00, 0x81]).is_empty());
+/// let messages = decoder.push(&[0x41, 0xDB, 0xD0, 0x08, 0x02, 0xB3, 0x8B, 0x7E]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrameDecoder {
+    buf: Vec<u8>,
+    max_frame_len: usize,
+    dropped: u64,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_FRAME_LEN)
+    }
+}
+
+impl FrameDecoder {
+    /// Uplink Data is the longest message, 436 bytes + CRC, twice that if every byte is escaped
+    pub const DEFAULT_MAX_FRAME_LEN: usize = 1024;
+
+    /// Frames longer than `max_frame_len` are dropped, so a lost flag byte can't grow the buffer forever
+    #[must_use]
+    pub fn new(max_frame_len: usize) -> Self {
+        Self {
+            buf: Vec::with_capacity(max_frame_len),
+            max_frame_len,
+            dropped: 0,
+        }
+    }
+
+    /// Feed bytes, returns the complete frames including their flag bytes
+    pub fn push_frames(&mut self, bytes: impl AsRef<[u8]>) -> Vec<Vec<u8>> {
+        let mut frames = vec![];
+
+        for &byte in bytes.as_ref() {
+            if byte != FLAG {
+                // bytes before the first flag are not part of any frame
+                if !self.buf.is_empty() {
+                    self.buf.push(byte);
+                }
+                if self.buf.len() > self.max_frame_len {
+                    self.buf.clear();
+                    self.dropped += 1;
+                }
+                continue;
+            }
+
+            match self.buf.len() {
+                // opening flag
+                0 => self.buf.push(FLAG),
+                // two flags in a row, the second one opens the frame
+                1 => {}
+                // closing flag, it may also open the next frame
+                _ => {
+                    self.buf.push(FLAG);
+                    frames.push(std::mem::replace(
+                        &mut self.buf,
+                        Vec::with_capacity(self.max_frame_len),
+                    ));
+                    self.buf.push(FLAG);
+                }
+            }
+        }
+
+        frames
+    }
+
+    /// Feed bytes, returns the decoded messages of all complete frames
+    pub fn push(&mut self, bytes: impl AsRef<[u8]>) -> Vec<GDL90Result<Message>> {
+        self.push_frames(bytes)
+            .into_iter()
+            .flat_map(Message::from_gdl90_bytes)
+            .collect()
+    }
+
+    /// Bytes of the incomplete frame, without the opening flag
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.buf.len().saturating_sub(1)
+    }
+
+    /// Frames dropped for exceeding `max_frame_len`
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Discard the incomplete frame, e.g. after reconnecting
+    pub fn reset(&mut self) {
+        self.buf.clear();
+    }
+}
+
+/// Message ID of a frame, without decoding it or checking the CRC
+#[must_use]
+pub fn frame_message_id(frame: impl AsRef<[u8]>) -> Option<u8> {
+    let mut bytes = frame.as_ref().iter().skip_while(|&&b| b == FLAG);
+    match *bytes.next()? {
+        ESCAPE => bytes.next().map(|b| b ^ ESCAPE_XOR),
+        id => Some(id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heartbeat() -> Heartbeat {
+        Heartbeat::default().with_utc_ok().with_timestamp(1234)
+    }
+
+    #[test]
+    fn split_frames() {
+        let frame = heartbeat().into_gdl90_bytes().unwrap();
+        let mut stream = vec![0x01, 0x02];
+        stream.extend(&frame);
+        stream.extend(&frame);
+
+        for split in 0..stream.len() {
+            let mut decoder = FrameDecoder::default();
+            let mut frames = decoder.push_frames(&stream[..split]);
+            frames.extend(decoder.push_frames(&stream[split..]));
+            assert_eq!(frames, vec![frame.clone(), frame.clone()]);
+            assert_eq!(decoder.pending(), 0);
+        }
+    }
+
+    #[test]
+    fn shared_flags() {
+        let frame = heartbeat().into_gdl90_bytes().unwrap();
+        let mut stream = frame.clone();
+        stream.extend(&frame[1..]);
+
+        let mut decoder = FrameDecoder::default();
+        let messages = decoder.push(&stream);
+        assert_eq!(messages.len(), 2);
+        assert!(
+            messages
+                .iter()
+                .all(|m| m.as_ref().unwrap() == &Message::from(heartbeat()))
+        );
+    }
+
+    #[test]
+    fn oversized_frames_are_dropped() {
+        let frame = heartbeat().into_gdl90_bytes().unwrap();
+        let mut decoder = FrameDecoder::new(16);
+
+        let mut stream = vec![FLAG];
+        stream.extend([0xAA; 32]);
+        stream.extend(&frame);
+
+        assert_eq!(decoder.push_frames(&stream), vec![frame]);
+        assert_eq!(decoder.dropped(), 1);
+    }
+
+    #[test]
+    fn message_id() {
+        let frame = heartbeat().into_gdl90_bytes().unwrap();
+        assert_eq!(frame_message_id(&frame), Some(Message::ID_HEARTBEAT));
+        assert_eq!(frame_message_id([FLAG, 0xC9, 0x7F, FLAG]), Some(0xC9));
+        assert_eq!(frame_message_id([FLAG, ESCAPE, 0x5E, FLAG]), Some(FLAG));
+        assert_eq!(frame_message_id([FLAG, FLAG]), None);
+    }
+}
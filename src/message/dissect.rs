@@ -0,0 +1,1164 @@
+use std::{fmt, ops::Range};
+
+use crate::{
+    message::{
+        crc::crc_calc,
+        r#impl::{ESCAPE, ESCAPE_XOR, FLAG},
+    },
+    prelude::*,
+};
+
+/// Raw value of a `DissectedField`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RawValue {
+    /// Fields of up to 64 bits as an unsigned integer, little endian fields already swapped
+    Bits(u64),
+    /// Longer fields as they are in the message
+    Bytes(Vec<u8>),
+}
+
+/// One field of a dissected message, like a line in a Wireshark tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DissectedField {
+    pub name: String,
+    /// Bits in the unescaped message, bit 0 is the MSB of the message ID
+    pub bits: Range<usize>,
+    pub raw: RawValue,
+    /// Decoded meaning, empty for spare bits or if the payload doesn't decode
+    pub meaning: String,
+    /// Sub-fields, e.g. the address type and address of a target identity
+    pub children: Vec<DissectedField>,
+}
+
+/// An escape sequence in a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Escape {
+    /// Offset of the escape byte (0x7D) in the frame
+    pub offset: usize,
+    /// The byte after the escape byte
+    pub escaped: u8,
+    /// `None` for an invalid escape sequence
+    pub unescaped: Option<u8>,
+}
+
+/// Received and computed CRC of a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrcCheck {
+    pub received: u16,
+    pub computed: u16,
+}
+
+impl CrcCheck {
+    #[must_use]
+    pub fn ok(&self) -> bool {
+        self.received == self.computed
+    }
+}
+
+/// A frame taken apart field by field, see `dissect()`.
+///
+/// `Display` prints the tree, one field per line with raw value, meaning and bit range.
+#[derive(Debug, Clone)]
+pub struct Dissection {
+    /// Offset of the opening flag in the dissected bytes
+    pub offset: usize,
+    /// Escaped frame, including both flags
+    pub frame: Vec<u8>,
+    pub escapes: Vec<Escape>,
+    /// Unescaped message ID, payload and CRC
+    pub message: Vec<u8>,
+    /// Message ID, sub-ID, payload fields and CRC
+    pub fields: Vec<DissectedField>,
+    /// `None` if the message is too short to have a CRC
+    pub crc: Option<CrcCheck>,
+    /// What `Message::from_gdl90_bytes()` makes of the frame
+    pub result: GDL90Result<Message>,
+}
+
+/// Take every frame in `bytes` apart, for reading hex dumps against the ICD.
+///
+/// Fields are laid out even if the CRC doesn't match, meanings are filled in
+/// wherever the payload still decodes.
+///
+/// ```ignore
+/// for dissection in dissect(hex_dump) {
+///     println!("{dissection}");
+/// }
+/// ```
+#[must_use]
+pub fn dissect(bytes: impl AsRef<[u8]>) -> Vec<Dissection> {
+    let bytes = bytes.as_ref();
+    let flags = bytes
+        .iter()
+        .enumerate()
+        .filter(|&(_, &b)| b == FLAG)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    flags
+        .windows(2)
+        .filter(|w| w[1] > w[0] + 1)
+        .map(|w| dissect_frame(w[0], &bytes[w[0]..=w[1]]))
+        .collect()
+}
+
+fn dissect_frame(offset: usize, frame: &[u8]) -> Dissection {
+    let mut escapes = Vec::new();
+    let mut message = Vec::with_capacity(frame.len());
+    let content = &frame[1..frame.len() - 1];
+    let mut i = 0;
+    while i < content.len() {
+        if content[i] == ESCAPE
+            && let Some(&escaped) = content.get(i + 1)
+        {
+            let unescaped = escaped ^ ESCAPE_XOR;
+            let unescaped = [FLAG, ESCAPE].contains(&unescaped).then_some(unescaped);
+            escapes.push(Escape {
+                offset: i + 1,
+                escaped,
+                unescaped,
+            });
+            message.push(unescaped.unwrap_or(escaped));
+            i += 2;
+        } else {
+            message.push(content[i]);
+            i += 1;
+        }
+    }
+
+    let result = Message::from_gdl90_bytes(frame)
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| Err(GDL90Error::MessageTooShort(message.len())));
+
+    let (fields, crc) = if message.len() < 3 {
+        (Vec::new(), None)
+    } else {
+        let crc_at = message.len() - 2;
+        let crc = CrcCheck {
+            received: u16::from_le_bytes([message[crc_at], message[crc_at + 1]]),
+            computed: crc_calc(&message[..crc_at]),
+        };
+
+        let mut layout = Layout::new(&message[..crc_at]);
+        message_fields(&mut layout, &message[..crc_at]);
+        layout.rest();
+
+        let mut fields = layout.fields;
+        fields.push(DissectedField {
+            name: "crc".to_string(),
+            bits: crc_at * 8..message.len() * 8,
+            raw: RawValue::Bits(crc.received.into()),
+            meaning: if crc.ok() {
+                "ok".to_string()
+            } else {
+                format!("mismatch, computed {:#06X}", crc.computed)
+            },
+            children: Vec::new(),
+        });
+        (fields, Some(crc))
+    };
+
+    Dissection {
+        offset,
+        frame: frame.to_vec(),
+        escapes,
+        message,
+        fields,
+        crc,
+        result,
+    }
+}
+
+fn decoded<'a, T: DekuContainerRead<'a>>(payload: &'a [u8]) -> Option<T> {
+    T::from_bytes((payload, 0)).ok().map(|(_, value)| value)
+}
+
+/// Rounded to 9 decimals, so unit conversions show `5000 ft` and not `4999.999999999999 ft`
+fn quantity(value: Option<f64>, unit: &str) -> String {
+    value.map_or_else(
+        || "unavailable".to_string(),
+        |v| format!("{} {unit}", (v * 1e9).round() / 1e9),
+    )
+}
+
+fn debug(value: impl fmt::Debug) -> String {
+    format!("{value:?}")
+}
+
+/// Message ID, sub-ID and payload of `message` (without CRC)
+fn message_fields(l: &mut Layout, message: &[u8]) {
+    let id = message[0];
+    let sub_id = message.get(1).copied();
+    let payload = &message[1..];
+    let extension = sub_id
+        .map(|sub_id| ExtensionKey::with_sub_id(id, sub_id))
+        .into_iter()
+        .chain([ExtensionKey::new(id)])
+        .find(|&key| is_extension_registered(key));
+
+    if let Some(key) = extension {
+        l.field("message_id", 8, "Extension");
+        if key.sub_id.is_some() {
+            l.field("sub_id", 8, "");
+        }
+        l.rest_named("extension payload");
+        return;
+    }
+
+    match id {
+        Message::ID_HEARTBEAT => {
+            l.field("message_id", 8, "Heartbeat");
+            heartbeat(l, decoded(payload));
+        }
+        Message::ID_INITIALIZATION => {
+            l.field("message_id", 8, "Initialization");
+            initialization(l, decoded(payload));
+        }
+        Message::ID_UPLINK_DATA => {
+            l.field("message_id", 8, "UplinkData");
+            uplink_data(l, decoded(payload));
+        }
+        Message::ID_HEIGHT_ABOVE_TERRAIN => {
+            l.field("message_id", 8, "HeightAboveTerrain");
+            let hat = decoded::<HeightAboveTerrain>(payload);
+            l.field(
+                "height_above_terrain",
+                16,
+                hat.map(|hat| quantity(hat.height_above_terrain.map(FromUom::feet), "ft")),
+            );
+        }
+        Message::ID_OWNSHIP | Message::ID_TRAFFIC => {
+            let name = if id == Message::ID_OWNSHIP {
+                "Ownship"
+            } else {
+                "Traffic"
+            };
+            l.field("message_id", 8, name);
+            traffic_report(l, decoded(payload));
+        }
+        Message::ID_OWNSHIP_GEOMETRIC_ALTITUDE => {
+            l.field("message_id", 8, "OwnshipGeometricAltitude");
+            ownship_geometric_altitude(l, decoded(payload));
+        }
+        Message::ID_BASIC_REPORT => {
+            l.field("message_id", 8, "BasicReport");
+            l.rest_named("uat_payload");
+        }
+        Message::ID_LONG_REPORT => {
+            l.field("message_id", 8, "LongReport");
+            l.rest_named("uat_payload");
+        }
+        Message::ID_FORE_FLIGHT => {
+            l.field("message_id", 8, "ForeFlight");
+            let payload = &message[message.len().min(2)..];
+            match sub_id {
+                Some(0) => {
+                    l.field("sub_id", 8, "ID");
+                    foreflight_id(l, decoded(payload));
+                }
+                Some(1) => {
+                    l.field("sub_id", 8, "AHRS");
+                    foreflight_ahrs(l, decoded(payload));
+                }
+                _ => l.field("sub_id", 8, "unknown"),
+            }
+        }
+        Message::ID_CUSTOM => {
+            l.field("message_id", 8, "Custom");
+            let payload = &message[message.len().min(2)..];
+            match sub_id {
+                Some(0) => {
+                    l.field("sub_id", 8, "PreciseOwnship");
+                    precise_ownship(l, decoded(payload));
+                }
+                Some(1) => {
+                    l.field("sub_id", 8, "PreciseOwnshipReport");
+                    precise_ownship_report(l, decoded(payload));
+                }
+                Some(2) => {
+                    l.field("sub_id", 8, "PreciseTraffic");
+                    precise_traffic(l, decoded(payload));
+                }
+                Some(3) => {
+                    l.field("sub_id", 8, "Ping");
+                    ping(l, decoded(payload));
+                }
+                Some(4) => {
+                    l.field("sub_id", 8, "Pong");
+                    pong(l, decoded(payload));
+                }
+                _ => l.field("sub_id", 8, "unknown"),
+            }
+        }
+        _ => l.field("message_id", 8, "unknown"),
+    }
+}
+
+fn heartbeat(l: &mut Layout, hb: Option<Heartbeat>) {
+    let flag = |f: fn(&Heartbeat) -> bool| hb.as_ref().map(|hb| f(hb).to_string());
+
+    l.field("gps_pos_valid", 1, flag(|hb| hb.gps_pos_valid));
+    l.field("maint_reqd", 1, flag(|hb| hb.maint_reqd));
+    l.field("ident", 1, flag(|hb| hb.ident));
+    l.field("addr_type", 1, flag(|hb| hb.addr_type));
+    l.field("gps_batt_low", 1, flag(|hb| hb.gps_batt_low));
+    l.field("ratcs", 1, flag(|hb| hb.ratcs));
+    l.spare(1);
+    l.field("uat_initialized", 1, flag(|hb| hb.uat_initialized));
+    l.field(
+        "timestamp_msb",
+        1,
+        hb.map(|hb| format!("timestamp {} s", hb.timestamp())),
+    );
+    l.field("csa_requested", 1, flag(|hb| hb.csa_requested));
+    l.field("csa_not_available", 1, flag(|hb| hb.csa_not_available));
+    l.spare(4);
+    l.field("utc_ok", 1, flag(|hb| hb.utc_ok));
+    l.field_le("timestamp", 16, "seconds since 0000Z, bits 0-15");
+    l.group(
+        "message_counts",
+        16,
+        hb.map(|hb| {
+            format!(
+                "{} uplink, {} basic/long",
+                hb.uplink_count(),
+                hb.basic_long_count()
+            )
+        }),
+        |l| {
+            l.field("uplink", 5, hb.map(|hb| hb.uplink_count().to_string()));
+            l.spare(1);
+            l.field(
+                "basic_long",
+                10,
+                hb.map(|hb| hb.basic_long_count().to_string()),
+            );
+        },
+    );
+}
+
+fn initialization(l: &mut Layout, init: Option<Initialization>) {
+    let flag = |f: fn(&Initialization) -> bool| init.as_ref().map(|init| f(init).to_string());
+
+    l.spare(1);
+    l.field("audio_test", 1, flag(|init| init.audio_test));
+    l.spare(4);
+    l.field("audio_inhibit", 1, flag(|init| init.audio_inhibit));
+    l.field("cdti_ok", 1, flag(|init| init.cdti_ok));
+    l.spare(6);
+    l.field("csa_audio_disable", 1, flag(|init| init.csa_audio_disable));
+    l.field("csa_disable", 1, flag(|init| init.csa_disable));
+}
+
+fn uplink_data(l: &mut Layout, uplink: Option<UplinkData>) {
+    l.field_le(
+        "time_of_reception",
+        24,
+        uplink.map(|uplink| {
+            uplink
+                .time_of_reception
+                .map_or_else(|| "invalid".to_string(), |tor| format!("{tor:?}"))
+        }),
+    );
+    l.rest_named("uplink_payload");
+}
+
+fn traffic_report(l: &mut Layout, report: Option<TrafficReport>) {
+    let r = report.as_ref();
+
+    l.field(
+        "traffic_alert_status",
+        4,
+        r.map(|r| debug(r.traffic_alert_status)),
+    );
+    l.group(
+        "target_identity",
+        28,
+        r.map(|r| debug(r.target_identity.address_type)),
+        |l| {
+            l.field(
+                "address_type",
+                4,
+                r.map(|r| debug(r.target_identity.address_type)),
+            );
+            l.field(
+                "participant_address",
+                24,
+                r.map(|r| format!("{:06X}", r.target_identity.participant_address)),
+            );
+        },
+    );
+    l.field(
+        "latitude",
+        24,
+        r.map(|r| quantity(Some(r.latitude.degrees()), "deg")),
+    );
+    l.field(
+        "longitude",
+        24,
+        r.map(|r| quantity(Some(r.longitude.degrees()), "deg")),
+    );
+    l.field(
+        "altitude",
+        12,
+        r.map(|r| quantity(r.altitude.map(FromUom::feet), "ft")),
+    );
+    l.group("miscellaneous_indicators", 4, "", |l| {
+        let m = r.map(|r| r.miscellaneous_indicators);
+        l.field("air_ground_state", 1, m.map(|m| debug(m.air_ground_state)));
+        l.field("report_type", 1, m.map(|m| debug(m.report_type)));
+        l.field(
+            "track_heading_type",
+            2,
+            m.map(|m| debug(m.track_heading_type)),
+        );
+    });
+    l.field("nic", 4, r.map(|r| debug(r.nic)));
+    l.field("nacp", 4, r.map(|r| debug(r.nacp)));
+    l.field(
+        "horizontal_velocity",
+        12,
+        r.map(|r| quantity(r.horizontal_velocity.map(FromUom::knots), "kt")),
+    );
+    l.field(
+        "vertical_velocity",
+        12,
+        r.map(|r| quantity(r.vertical_velocity.map(FromUom::feet_per_minute), "fpm")),
+    );
+    l.field(
+        "track_heading",
+        8,
+        r.map(|r| quantity(Some(r.track_heading.degrees()), "deg")),
+    );
+    l.field("emitter_category", 8, r.map(|r| debug(r.emitter_category)));
+    l.field("callsign", 64, r.map(|r| debug(&r.callsign)));
+    l.field(
+        "emergency_priority_code",
+        4,
+        r.map(|r| debug(r.emergency_priority_code)),
+    );
+    l.spare(4);
+}
+
+fn ownship_geometric_altitude(l: &mut Layout, oga: Option<OwnshipGeometricAltitude>) {
+    l.field(
+        "ownship_geo_altitude",
+        16,
+        oga.map(|oga| quantity(Some(oga.ownship_geo_altitude.feet()), "ft")),
+    );
+    l.group("vertical_metrics", 16, "", |l| {
+        let vm = oga.map(|oga| oga.vertical_metrics);
+        l.field(
+            "vertical_warning_indicator",
+            1,
+            vm.map(|vm| vm.vertical_warning_indicator.to_string()),
+        );
+        l.field(
+            "vfom",
+            15,
+            vm.map(|vm| quantity(vm.vfom.map(FromUom::meters), "m")),
+        );
+    });
+}
+
+fn foreflight_id(l: &mut Layout, id: Option<ForeFlightID>) {
+    let id = id.as_ref();
+
+    l.field("version", 8, id.map(|id| id.version.to_string()));
+    l.field(
+        "device_serial_number",
+        64,
+        id.map(|id| id.device_serial_number.to_string()),
+    );
+    l.field("device_name", 64, id.map(|id| debug(&id.device_name)));
+    l.field(
+        "device_long_name",
+        128,
+        id.map(|id| debug(&id.device_long_name)),
+    );
+    l.group("capabilities", 32, "", |l| {
+        let c = id.map(|id| id.capabilities);
+        l.field("reserved", 29, "");
+        l.field(
+            "internet_policy",
+            2,
+            c.map(|c| debug(c.foreflight_internet_policy())),
+        );
+        l.field(
+            "geometric_altitude_datum",
+            1,
+            c.map(|c| debug(c.geometric_altitude_datum())),
+        );
+    });
+}
+
+fn foreflight_ahrs(l: &mut Layout, ahrs: Option<ForeFlightAHRS>) {
+    let degrees = |f: fn(&ForeFlightAHRS) -> Option<Angle>| {
+        ahrs.as_ref()
+            .map(|ahrs| quantity(f(ahrs).map(FromUom::degrees), "deg"))
+    };
+    let knots = |f: fn(&ForeFlightAHRS) -> Option<Velocity>| {
+        ahrs.as_ref()
+            .map(|ahrs| quantity(f(ahrs).map(FromUom::knots), "kt"))
+    };
+
+    l.field("roll", 16, degrees(|ahrs| ahrs.roll));
+    l.field("pitch", 16, degrees(|ahrs| ahrs.pitch));
+    l.field("heading_type", 1, ahrs.map(|ahrs| debug(ahrs.heading_type)));
+    l.field("heading", 15, degrees(|ahrs| ahrs.heading));
+    l.field(
+        "indicated_airspeed",
+        16,
+        knots(|ahrs| ahrs.indicated_airspeed),
+    );
+    l.field("true_airspeed", 16, knots(|ahrs| ahrs.true_airspeed));
+}
+
+fn precise_ownship(l: &mut Layout, ownship: Option<PreciseOwnship>) {
+    l.field(
+        "latitude",
+        64,
+        ownship.map(|o| quantity(Some(o.latitude.degrees()), "deg")),
+    );
+    l.field(
+        "longitude",
+        64,
+        ownship.map(|o| quantity(Some(o.longitude.degrees()), "deg")),
+    );
+    l.field(
+        "altitude",
+        64,
+        ownship.map(|o| quantity(Some(o.altitude.feet()), "ft")),
+    );
+    l.field(
+        "ground_speed",
+        64,
+        ownship.map(|o| quantity(Some(o.ground_speed.knots()), "kt")),
+    );
+}
+
+fn precise_ownship_report(l: &mut Layout, report: Option<PreciseOwnshipReport>) {
+    l.field("version", 8, report.map(|r| r.version.to_string()));
+    l.field("timestamp", 64, report.map(|r| r.timestamp.to_rfc3339()));
+    l.field(
+        "latitude",
+        64,
+        report.map(|r| quantity(Some(r.latitude.degrees()), "deg")),
+    );
+    l.field(
+        "longitude",
+        64,
+        report.map(|r| quantity(Some(r.longitude.degrees()), "deg")),
+    );
+    l.field(
+        "altitude",
+        64,
+        report.map(|r| quantity(Some(r.altitude.feet()), "ft")),
+    );
+    l.field(
+        "ground_speed",
+        64,
+        report.map(|r| quantity(Some(r.ground_speed.knots()), "kt")),
+    );
+    l.field(
+        "track",
+        64,
+        report.map(|r| quantity(Some(r.track.degrees()), "deg")),
+    );
+    l.field(
+        "vertical_speed",
+        64,
+        report.map(|r| quantity(Some(r.vertical_speed.feet_per_minute()), "fpm")),
+    );
+    l.field(
+        "horizontal_accuracy",
+        64,
+        report.map(|r| quantity(r.horizontal_accuracy.map(FromUom::meters), "m")),
+    );
+    l.field(
+        "vertical_accuracy",
+        64,
+        report.map(|r| quantity(r.vertical_accuracy.map(FromUom::meters), "m")),
+    );
+}
+
+fn precise_traffic(l: &mut Layout, traffic: Option<PreciseTraffic>) {
+    let t = traffic.as_ref();
+
+    l.field("version", 8, t.map(|t| t.version.to_string()));
+    l.field("timestamp", 64, t.map(|t| t.timestamp.to_rfc3339()));
+    l.spare(4);
+    l.group(
+        "target_identity",
+        28,
+        t.map(|t| debug(t.target_identity.address_type)),
+        |l| {
+            l.field(
+                "address_type",
+                4,
+                t.map(|t| debug(t.target_identity.address_type)),
+            );
+            l.field(
+                "participant_address",
+                24,
+                t.map(|t| format!("{:06X}", t.target_identity.participant_address)),
+            );
+        },
+    );
+    l.field(
+        "latitude",
+        64,
+        t.map(|t| quantity(Some(t.latitude.degrees()), "deg")),
+    );
+    l.field(
+        "longitude",
+        64,
+        t.map(|t| quantity(Some(t.longitude.degrees()), "deg")),
+    );
+    l.field(
+        "altitude",
+        64,
+        t.map(|t| quantity(Some(t.altitude.feet()), "ft")),
+    );
+    l.field(
+        "ground_speed",
+        64,
+        t.map(|t| quantity(Some(t.ground_speed.knots()), "kt")),
+    );
+    l.field(
+        "track",
+        64,
+        t.map(|t| quantity(Some(t.track.degrees()), "deg")),
+    );
+    l.field(
+        "vertical_speed",
+        64,
+        t.map(|t| quantity(Some(t.vertical_speed.feet_per_minute()), "fpm")),
+    );
+    l.field("emitter_category", 8, t.map(|t| debug(t.emitter_category)));
+    l.field("callsign", 64, t.map(|t| debug(&t.callsign)));
+}
+
+fn ping(l: &mut Layout, ping: Option<Ping>) {
+    l.field("version", 8, ping.map(|p| p.version.to_string()));
+    l.field("sequence", 32, ping.map(|p| p.sequence.to_string()));
+    l.field("sent_at", 64, ping.map(|p| p.sent_at.to_rfc3339()));
+}
+
+fn pong(l: &mut Layout, pong: Option<Pong>) {
+    l.field("version", 8, pong.map(|p| p.version.to_string()));
+    l.field("sequence", 32, pong.map(|p| p.sequence.to_string()));
+    l.field(
+        "ping_sent_at",
+        64,
+        pong.map(|p| p.ping_sent_at.to_rfc3339()),
+    );
+    l.field("sent_at", 64, pong.map(|p| p.sent_at.to_rfc3339()));
+}
+
+/// Lays fields out one after the other over the message bytes
+struct Layout<'a> {
+    message: &'a [u8],
+    offset: usize,
+    fields: Vec<DissectedField>,
+}
+
+impl<'a> Layout<'a> {
+    fn new(message: &'a [u8]) -> Self {
+        Self {
+            message,
+            offset: 0,
+            fields: Vec::new(),
+        }
+    }
+
+    fn len_bits(&self) -> usize {
+        self.message.len() * 8
+    }
+
+    /// MSB first
+    fn read(&self, bits: Range<usize>) -> u64 {
+        bits.fold(0, |acc, bit| {
+            (acc << 1) | u64::from((self.message[bit / 8] >> (7 - bit % 8)) & 1)
+        })
+    }
+
+    /// Take the next `bits`, `None` (and a `truncated` field) if the message ends before
+    fn take(&mut self, bits: usize) -> Option<Range<usize>> {
+        if self.offset + bits > self.len_bits() {
+            self.rest_named("truncated");
+            return None;
+        }
+        let range = self.offset..self.offset + bits;
+        self.offset += bits;
+        Some(range)
+    }
+
+    fn push(&mut self, name: &str, bits: Range<usize>, raw: RawValue, meaning: impl Meaning) {
+        self.fields.push(DissectedField {
+            name: name.to_string(),
+            bits,
+            raw,
+            meaning: meaning.into_meaning(),
+            children: Vec::new(),
+        });
+    }
+
+    fn raw(&self, bits: Range<usize>) -> RawValue {
+        if bits.len() <= 64 {
+            RawValue::Bits(self.read(bits))
+        } else {
+            RawValue::Bytes(self.message[bits.start / 8..bits.end.div_ceil(8)].to_vec())
+        }
+    }
+
+    fn field(&mut self, name: &str, bits: usize, meaning: impl Meaning) {
+        if let Some(bits) = self.take(bits) {
+            let raw = self.raw(bits.clone());
+            self.push(name, bits, raw, meaning);
+        }
+    }
+
+    /// Whole bytes, least significant byte first
+    fn field_le(&mut self, name: &str, bits: usize, meaning: impl Meaning) {
+        if let Some(bits) = self.take(bits) {
+            let raw = self.message[bits.start / 8..bits.end / 8]
+                .iter()
+                .rev()
+                .fold(0, |acc, &b| (acc << 8) | u64::from(b));
+            self.push(name, bits, RawValue::Bits(raw), meaning);
+        }
+    }
+
+    fn spare(&mut self, bits: usize) {
+        self.field("spare", bits, "");
+    }
+
+    /// A field made of sub-fields laid out by `f`
+    fn group(&mut self, name: &str, bits: usize, meaning: impl Meaning, f: impl FnOnce(&mut Self)) {
+        let start = self.offset;
+        if self.offset + bits > self.len_bits() {
+            self.rest_named("truncated");
+            return;
+        }
+
+        let outer = std::mem::take(&mut self.fields);
+        f(self);
+        let children = std::mem::replace(&mut self.fields, outer);
+        let bits = start..self.offset;
+        let raw = self.raw(bits.clone());
+        self.fields.push(DissectedField {
+            name: name.to_string(),
+            bits,
+            raw,
+            meaning: meaning.into_meaning(),
+            children,
+        });
+    }
+
+    /// Everything not laid out yet as one field
+    fn rest_named(&mut self, name: &str) {
+        if self.offset < self.len_bits() {
+            let bits = self.offset..self.len_bits();
+            self.offset = self.len_bits();
+            let raw = RawValue::Bytes(self.message[bits.start / 8..].to_vec());
+            self.push(name, bits, raw, "");
+        }
+    }
+
+    /// Bytes after the last field of the layout
+    fn rest(&mut self) {
+        self.rest_named("unexpected bytes");
+    }
+}
+
+/// Meanings can be given as text or `Option<String>`, `None` when the payload didn't decode
+trait Meaning {
+    fn into_meaning(self) -> String;
+}
+
+impl Meaning for &str {
+    fn into_meaning(self) -> String {
+        self.to_string()
+    }
+}
+
+impl Meaning for Option<String> {
+    fn into_meaning(self) -> String {
+        self.unwrap_or_default()
+    }
+}
+
+impl fmt::Display for RawValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bits(value) => write!(f, "{value:#X}"),
+            Self::Bytes(bytes) => {
+                let hex = bytes.iter().take(16).map(|b| format!("{b:02X}"));
+                write!(f, "{}", hex.collect::<Vec<_>>().join(" "))?;
+                if bytes.len() > 16 {
+                    write!(f, " ... ({} bytes)", bytes.len())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl DissectedField {
+    fn write_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = "    ".repeat(depth);
+        let bits = format!("[bits {}-{}]", self.bits.start, self.bits.end - 1);
+        write!(f, "{indent}{}: {}", self.name, self.raw)?;
+        if !self.meaning.is_empty() {
+            write!(f, " = {}", self.meaning)?;
+        }
+        writeln!(f, " {bits}")?;
+
+        for child in &self.children {
+            child.write_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Dissection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = self.frame.iter().map(|b| format!("{b:02X}"));
+        writeln!(
+            f,
+            "Frame at byte {}, {} bytes: {}",
+            self.offset,
+            self.frame.len(),
+            hex.collect::<Vec<_>>().join(" ")
+        )?;
+
+        writeln!(f, "    flag: {FLAG:#04X} [frame byte 0]")?;
+        for escape in &self.escapes {
+            match escape.unescaped {
+                Some(b) => writeln!(
+                    f,
+                    "    escape: 7D {:02X} -> {b:#04X} [frame bytes {}-{}]",
+                    escape.escaped,
+                    escape.offset,
+                    escape.offset + 1
+                )?,
+                None => writeln!(
+                    f,
+                    "    escape: 7D {:02X} -> invalid [frame bytes {}-{}]",
+                    escape.escaped,
+                    escape.offset,
+                    escape.offset + 1
+                )?,
+            }
+        }
+        for field in &self.fields {
+            field.write_tree(f, 1)?;
+        }
+        writeln!(
+            f,
+            "    flag: {FLAG:#04X} [frame byte {}]",
+            self.frame.len() - 1
+        )?;
+
+        match &self.result {
+            Ok(message) => write!(f, "    => {}", message.type_name()),
+            Err(err) => write!(f, "    => error: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEARTBEAT: [u8; 11] = [
+        0x7E, 0x00, 0x81, 0x41, 0xDB, 0xD0, 0x08, 0x02, 0xB3, 0x8B, 0x7E,
+    ];
+
+    /// Escape `message` (ID, payload and CRC) and add the flags
+    fn frame(message: &[u8]) -> Vec<u8> {
+        let mut frame = vec![FLAG];
+        for &b in message {
+            if b == FLAG || b == ESCAPE {
+                frame.extend([ESCAPE, b ^ ESCAPE_XOR]);
+            } else {
+                frame.push(b);
+            }
+        }
+        frame.push(FLAG);
+        frame
+    }
+
+    fn find<'a>(fields: &'a [DissectedField], name: &str) -> &'a DissectedField {
+        fields.iter().find(|f| f.name == name).unwrap()
+    }
+
+    #[test]
+    fn heartbeat() {
+        let mut bytes = vec![0x00, 0x01];
+        bytes.extend(HEARTBEAT);
+        let dissections = dissect(&bytes);
+        assert_eq!(dissections.len(), 1);
+
+        let d = &dissections[0];
+        assert_eq!(d.offset, 2);
+        assert!(d.escapes.is_empty());
+        assert!(d.crc.unwrap().ok());
+        assert!(d.result.is_ok());
+
+        let id = find(&d.fields, "message_id");
+        assert_eq!(id.raw, RawValue::Bits(0));
+        assert_eq!(id.meaning, "Heartbeat");
+
+        let gps = find(&d.fields, "gps_pos_valid");
+        assert_eq!(gps.bits, 8..9);
+        assert_eq!(gps.raw, RawValue::Bits(1));
+        assert_eq!(gps.meaning, "true");
+
+        let spares = d.fields.iter().filter(|f| f.name == "spare").count();
+        assert_eq!(spares, 2);
+
+        let timestamp = find(&d.fields, "timestamp");
+        assert_eq!(timestamp.bits, 24..40);
+        assert_eq!(timestamp.raw, RawValue::Bits(0xD0DB));
+
+        let counts = find(&d.fields, "message_counts");
+        assert_eq!(counts.children.len(), 3);
+        assert_eq!(counts.children[0].raw, RawValue::Bits(1));
+        assert_eq!(counts.children[2].raw, RawValue::Bits(2));
+
+        let crc = find(&d.fields, "crc");
+        assert_eq!(crc.bits, 56..72);
+        assert_eq!(crc.meaning, "ok");
+
+        let text = d.to_string();
+        assert!(
+            text.contains("    gps_pos_valid: 0x1 = true [bits 8-8]"),
+            "{text}"
+        );
+        assert!(
+            text.contains("        uplink: 0x1 = 1 [bits 40-44]"),
+            "{text}"
+        );
+        assert!(text.ends_with("=> Heartbeat"), "{text}");
+    }
+
+    #[test]
+    fn escapes_and_crc() {
+        // 0x7E7D ft needs both bytes escaped
+        let hat = HeightAboveTerrain::default().with_height_above_terrain(0x7E7D.feet());
+        let d = &dissect(hat.into_gdl90_bytes().unwrap())[0];
+        assert_eq!(d.escapes[0].offset, 2);
+        assert_eq!(d.escapes[0].unescaped, Some(0x7E));
+        assert_eq!(d.escapes[1].offset, 4);
+        assert_eq!(d.escapes[1].unescaped, Some(0x7D));
+        assert_eq!(d.message[1..3], [0x7E, 0x7D]);
+        let height = find(&d.fields, "height_above_terrain");
+        assert_eq!(height.bits, 8..24);
+        assert_eq!(height.raw, RawValue::Bits(0x7E7D));
+        assert_eq!(height.meaning, "32381 ft");
+
+        // corrupt the CRC, the fields are still laid out
+        let mut message = d.message.clone();
+        *message.last_mut().unwrap() ^= 0x01;
+        let d = &dissect(frame(&message))[0];
+        assert!(!d.crc.unwrap().ok());
+        assert!(find(&d.fields, "crc").meaning.starts_with("mismatch"));
+        assert_eq!(find(&d.fields, "height_above_terrain").meaning, "32381 ft");
+        assert!(matches!(d.result, Err(GDL90Error::CrcMismatch { .. })));
+
+        let d = &dissect([FLAG, 0x09, ESCAPE, 0x00, 0x00, 0x00, 0x00, FLAG])[0];
+        assert_eq!(d.escapes[0].unescaped, None);
+        assert!(d.to_string().contains("escape: 7D 00 -> invalid"));
+        assert!(matches!(
+            d.result,
+            Err(GDL90Error::InvalidEscapeSequence(_))
+        ));
+    }
+
+    /// Leaves of `fields`, checking that each level covers `bits` exactly once, in order
+    fn leaves<'a>(fields: &'a [DissectedField], bits: Range<usize>) -> Vec<&'a DissectedField> {
+        let mut offset = bits.start;
+        let mut found = vec![];
+        for field in fields {
+            assert_eq!(
+                field.bits.start, offset,
+                "{} overlaps or leaves a gap",
+                field.name
+            );
+            offset = field.bits.end;
+            if field.children.is_empty() {
+                found.push(field);
+            } else {
+                found.extend(leaves_of(field));
+            }
+        }
+        assert_eq!(offset, bits.end);
+        found
+    }
+
+    fn leaves_of(field: &DissectedField) -> Vec<&DissectedField> {
+        leaves(&field.children, field.bits.clone())
+    }
+
+    #[test]
+    fn every_message() {
+        let timestamp = chrono::DateTime::from_timestamp_micros(1_760_000_000_123_456).unwrap();
+        let report = TrafficReport::default()
+            .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, 0xAB4549))
+            .with_altitude(5000.0.feet())
+            .with_nacp(NACp::NACp9_HFOM_30M_VFOM_45M)
+            .with_callsign("N825V".to_string());
+        let ownship_fields = [
+            ("participant_address", "AB4549"),
+            ("altitude", "5000 ft"),
+            ("nacp", "NACp9_HFOM_30M_VFOM_45M"),
+            ("callsign", "\"N825V\""),
+        ];
+
+        let messages: Vec<(Message, &[(&str, &str)])> = vec![
+            (
+                Heartbeat::default()
+                    .with_gps_pos_valid()
+                    .with_timestamp(53467)
+                    .into(),
+                &[
+                    ("gps_pos_valid", "true"),
+                    ("timestamp_msb", "timestamp 53467 s"),
+                ],
+            ),
+            (
+                Initialization::default().with_cdti_ok().into(),
+                &[("cdti_ok", "true"), ("audio_test", "false")],
+            ),
+            (
+                UplinkData::default()
+                    .with_time_of_reception(std::time::Duration::from_micros(500))
+                    .with_uplink_payload([0x7E, 0x7D, 1, 2, 3])
+                    .into(),
+                &[("time_of_reception", "500µs")],
+            ),
+            (
+                HeightAboveTerrain::default()
+                    .with_height_above_terrain(1234.0.feet())
+                    .into(),
+                &[("height_above_terrain", "1234 ft")],
+            ),
+            (report.clone().ownship().into(), &ownship_fields),
+            (report.traffic().into(), &ownship_fields),
+            (
+                OwnshipGeometricAltitude::default()
+                    .with_ownship_geo_altitude(5125.0.feet())
+                    .into(),
+                &[("ownship_geo_altitude", "5125 ft")],
+            ),
+            (Message::BasicReport, &[]),
+            (Message::LongReport, &[]),
+            (
+                ForeFlightID::default()
+                    .with_device_serial_number(1234)
+                    .with_device_name("Name.")
+                    .into(),
+                &[
+                    ("device_serial_number", "1234"),
+                    ("device_name", "\"Name.\""),
+                ],
+            ),
+            (
+                ForeFlightAHRS::default()
+                    .with_heading_type(AHRSHeadingType::Magnetic)
+                    .into(),
+                &[("heading_type", "Magnetic")],
+            ),
+            (
+                PreciseOwnship::default()
+                    .with_altitude(31_000.5.feet())
+                    .into(),
+                &[("altitude", "31000.5 ft")],
+            ),
+            (
+                PreciseOwnshipReport::default()
+                    .with_timestamp(timestamp)
+                    .into(),
+                &[("version", "1")],
+            ),
+            (
+                PreciseTraffic::default()
+                    .with_target_identity(TargetIdentity::new(AddressType::TisbIcao, 0x123456))
+                    .with_callsign("SWR123".to_string())
+                    .into(),
+                &[
+                    ("address_type", "TisbIcao"),
+                    ("participant_address", "123456"),
+                    ("callsign", "\"SWR123\""),
+                ],
+            ),
+            (Ping::new(7, timestamp).into(), &[("sequence", "7")]),
+            (
+                Pong::new(7, timestamp, timestamp).into(),
+                &[("sequence", "7")],
+            ),
+        ];
+
+        for (message, expected) in messages {
+            let name = message.type_name();
+            let bytes = message.into_gdl90_bytes().unwrap();
+            let dissections = dissect(&bytes);
+            assert_eq!(dissections.len(), 1, "{name}");
+            let d = &dissections[0];
+
+            let decoded = d.result.clone().unwrap();
+            assert_eq!(decoded.type_name(), name);
+            assert_eq!(decoded.into_gdl90_bytes().unwrap(), bytes, "{name}");
+
+            let fields = leaves(&d.fields, 0..d.message.len() * 8);
+            let ids = fields
+                .iter()
+                .filter(|f| f.name == "message_id" || f.name == "sub_id")
+                .map(|f| f.meaning.as_str())
+                .collect::<String>();
+            // custom message type names leave out the message ID
+            assert!(
+                ids == name || ids == format!("Custom{name}"),
+                "{name}: {ids}"
+            );
+
+            for leaf in &fields {
+                assert!(
+                    !["truncated", "unexpected bytes"].contains(&leaf.name.as_str()),
+                    "{name}\n{d}"
+                );
+                if !["spare", "reserved", "uplink_payload"].contains(&leaf.name.as_str()) {
+                    assert!(
+                        !leaf.meaning.is_empty(),
+                        "{name}: {} has no meaning",
+                        leaf.name
+                    );
+                }
+            }
+            assert_eq!(find(&d.fields, "crc").meaning, "ok");
+
+            for (field, meaning) in expected {
+                let leaf = fields.iter().find(|f| f.name == *field).unwrap();
+                assert_eq!(leaf.meaning, *meaning, "{name}: {field}");
+            }
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        let mut message = vec![Message::ID_OWNSHIP, 0x00, 0xAB];
+        message.extend(crc_calc(&message).to_le_bytes());
+
+        let d = &dissect(frame(&message))[0];
+        assert!(d.crc.unwrap().ok());
+        let names = d.fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["message_id", "traffic_alert_status", "truncated", "crc"]
+        );
+        assert_eq!(d.fields[1].meaning, "");
+    }
+}
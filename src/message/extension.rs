@@ -0,0 +1,256 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::{LazyLock, PoisonError, RwLock},
+};
+
+use crate::prelude::*;
+
+/// Message ID, and optionally sub-ID, an extension is registered for.
+///
+/// A key without a sub-ID claims the whole message ID, the payload starts right after the ID byte.
+/// A key with a sub-ID only claims messages whose second byte matches, like `ForeFlightMessage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ExtensionKey {
+    pub message_id: u8,
+    pub sub_id: Option<u8>,
+}
+
+impl ExtensionKey {
+    #[must_use]
+    pub const fn new(message_id: u8) -> Self {
+        Self {
+            message_id,
+            sub_id: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_sub_id(message_id: u8, sub_id: u8) -> Self {
+        Self {
+            message_id,
+            sub_id: Some(sub_id),
+        }
+    }
+
+    /// Number of header bytes (ID + sub-ID) in front of the payload
+    #[must_use]
+    pub const fn header_len(&self) -> usize {
+        if self.sub_id.is_some() { 2 } else { 1 }
+    }
+}
+
+/// A message type defined outside of this crate.
+///
+/// Register it with [`register_extension`] and it is decoded by `Message::from_gdl90_bytes()`
+/// into `Message::Extension`, from where it can be downcast again with [`ExtensionMessage::downcast_ref`].
+///
+/// ```ignore
+/// #[derive(Debug, Clone, PartialEq, DekuRead, DekuWrite)]
+/// struct Battery { percent: u8 }
+///
+/// impl Extension for Battery {
+///     const KEY: ExtensionKey = ExtensionKey::with_sub_id(0xC9, 0x10);
+///
+///     fn decode(payload: &[u8]) -> GDL90Result<Self> {
+///         Ok(Self::from_bytes((payload, 0))?.1)
+///     }
+///
+///     fn encode(&self) -> GDL90Result<Vec<u8>> {
+///         Ok(self.to_bytes()?)
+///     }
+/// }
+///
+/// register_extension::<Battery>();
+/// ```
+pub trait Extension: std::fmt::Debug + Clone + PartialEq + Send + Sync + 'static {
+    const KEY: ExtensionKey;
+
+    /// Parse the payload, without the message ID, sub-ID and CRC.
+    ///
+    /// # Errors
+    ///
+    /// If the payload is not a valid message of this type.
+    fn decode(payload: &[u8]) -> GDL90Result<Self>;
+
+    /// Serialize the payload, without the message ID, sub-ID and CRC.
+    ///
+    /// # Errors
+    ///
+    /// If the message can't be serialized.
+    fn encode(&self) -> GDL90Result<Vec<u8>>;
+}
+
+/// Object safe counterpart of `Extension`, so different extensions fit into one `Message` variant
+trait DynExtension: std::fmt::Debug + Send + Sync {
+    fn key(&self) -> ExtensionKey;
+    fn encode(&self) -> GDL90Result<Vec<u8>>;
+    fn clone_box(&self) -> Box<dyn DynExtension>;
+    fn eq_dyn(&self, other: &dyn DynExtension) -> bool;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Extension> DynExtension for T {
+    fn key(&self) -> ExtensionKey {
+        T::KEY
+    }
+
+    fn encode(&self) -> GDL90Result<Vec<u8>> {
+        Extension::encode(self)
+    }
+
+    fn clone_box(&self) -> Box<dyn DynExtension> {
+        Box::new(self.clone())
+    }
+
+    fn eq_dyn(&self, other: &dyn DynExtension) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A decoded extension message, see [`Extension`]
+#[derive(Debug)]
+pub struct ExtensionMessage(Box<dyn DynExtension>);
+
+impl ExtensionMessage {
+    #[must_use]
+    pub fn new<T: Extension>(value: T) -> Self {
+        Self(Box::new(value))
+    }
+
+    #[must_use]
+    pub fn key(&self) -> ExtensionKey {
+        self.0.key()
+    }
+
+    #[must_use]
+    pub fn is<T: Extension>(&self) -> bool {
+        self.0.as_any().is::<T>()
+    }
+
+    #[must_use]
+    pub fn downcast_ref<T: Extension>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref::<T>()
+    }
+
+    /// Message ID, sub-ID (if any) and payload, without CRC
+    pub(crate) fn to_message_bytes(&self) -> GDL90Result<Vec<u8>> {
+        let key = self.key();
+        let payload = self.0.encode()?;
+
+        let mut bytes = Vec::with_capacity(payload.len() + key.header_len());
+        bytes.push(key.message_id);
+        bytes.extend(key.sub_id);
+        bytes.extend(payload);
+        Ok(bytes)
+    }
+}
+
+impl Clone for ExtensionMessage {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl PartialEq for ExtensionMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_dyn(other.0.as_ref())
+    }
+}
+
+impl<T: Extension> From<T> for ExtensionMessage {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Serialized as the hex encoded message bytes, deserializing needs the extension to be registered
+#[cfg(feature = "serde")]
+impl serde::Serialize for ExtensionMessage {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes = self
+            .to_message_bytes()
+            .map_err(<S::Error as serde::ser::Error>::custom)?;
+        crate::util::serde_fields::hex::serialize(bytes, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ExtensionMessage {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let hex = <String as serde::Deserialize>::deserialize(deserializer)?;
+        let bytes = crate::util::serde_fields::hex::decode(&hex).map_err(D::Error::custom)?;
+        match decode_extension(&bytes) {
+            Some(result) => result.map_err(D::Error::custom),
+            None => Err(D::Error::custom(format!(
+                "no extension registered for {hex}"
+            ))),
+        }
+    }
+}
+
+type Decoder = fn(&[u8]) -> GDL90Result<ExtensionMessage>;
+
+static REGISTRY: LazyLock<RwLock<HashMap<ExtensionKey, Decoder>>> = LazyLock::new(RwLock::default);
+
+fn decode_as<T: Extension>(payload: &[u8]) -> GDL90Result<ExtensionMessage> {
+    T::decode(payload).map(ExtensionMessage::new)
+}
+
+/// Register `T` so it is decoded from incoming packets.
+///
+/// Registered extensions take precedence over the built-in messages with the same ID.
+/// Registering a second extension for the same key replaces the first one.
+pub fn register_extension<T: Extension>() {
+    REGISTRY
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(T::KEY, decode_as::<T>);
+}
+
+/// Remove whatever extension is registered for `key`.
+///
+/// Returns `true` if there was one.
+pub fn unregister_extension(key: ExtensionKey) -> bool {
+    REGISTRY
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(&key)
+        .is_some()
+}
+
+#[must_use]
+pub fn is_extension_registered(key: ExtensionKey) -> bool {
+    REGISTRY
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains_key(&key)
+}
+
+/// Try the registered extensions on unescaped message bytes (ID + payload, no CRC).
+///
+/// `(ID, sub-ID)` registrations are checked before plain `ID` ones.
+/// `None` if no extension claims the message.
+pub(crate) fn decode_extension(bytes: &[u8]) -> Option<GDL90Result<ExtensionMessage>> {
+    let &message_id = bytes.first()?;
+    let registry = REGISTRY.read().unwrap_or_else(PoisonError::into_inner);
+
+    let sub_key = bytes
+        .get(1)
+        .map(|&sub_id| ExtensionKey::with_sub_id(message_id, sub_id));
+
+    [sub_key, Some(ExtensionKey::new(message_id))]
+        .into_iter()
+        .flatten()
+        .find_map(|key| {
+            registry
+                .get(&key)
+                .map(|decode| decode(&bytes[key.header_len()..]))
+        })
+}
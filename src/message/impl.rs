@@ -1,6 +1,6 @@
 use crate::{
     bail,
-    message::{GDL90Encode, Message, crc::crc_calc},
+    message::{GDL90Encode, Message, crc::crc_calc, extension::decode_extension},
     prelude::*,
 };
 
@@ -38,65 +38,212 @@ impl_message_from! {
     Traffic(TrafficMessage),
     ForeFlight(ForeFlightMessage),
     Custom(CustomMessage),
+    Extension(ExtensionMessage),
 }
 
-impl From<ForeFlightID> for Message {
-    fn from(value: ForeFlightID) -> Self {
-        Self::ForeFlight(ForeFlightMessage::ID(value))
+impl<T: Extension> From<T> for Message {
+    fn from(value: T) -> Self {
+        Self::Extension(value.into())
     }
 }
 
-impl From<ForeFlightAHRS> for Message {
-    fn from(value: ForeFlightAHRS) -> Self {
-        Self::ForeFlight(ForeFlightMessage::AHRS(value))
-    }
+/// `From<$ty> for Message`, `Message::is_$fn()` and `Message::$fn()` for messages behind a sub-ID
+macro_rules! impl_sub_message {
+    ($(($fn:ident, $variant:ident($sub:ident::$sub_variant:ident), $ty:ty)),* $(,)?) => {
+        $(
+            impl From<$ty> for Message {
+                fn from(value: $ty) -> Self {
+                    Self::$variant($sub::$sub_variant(value))
+                }
+            }
+        )*
+
+        impl Message {
+            pastey::paste! {$(
+                #[must_use]
+                pub fn [<is_ $fn>](&self) -> bool {
+                    matches!(self, Self::$variant($sub::$sub_variant(_)))
+                }
+                #[must_use]
+                pub fn $fn(&self) -> Option<&$ty> {
+                    if let Self::$variant($sub::$sub_variant(value)) = self {
+                        Some(value)
+                    } else {
+                        None
+                    }
+                }
+            )*}
+        }
+    };
 }
 
-impl From<PreciseOwnship> for Message {
-    fn from(value: PreciseOwnship) -> Self {
-        Self::Custom(CustomMessage::PreciseOwnship(value))
-    }
+impl_sub_message! {
+    (fore_flight_id, ForeFlight(ForeFlightMessage::ID), ForeFlightID),
+    (fore_flight_ahrs, ForeFlight(ForeFlightMessage::AHRS), ForeFlightAHRS),
+    (precise_ownship, Custom(CustomMessage::PreciseOwnship), PreciseOwnship),
+    (precise_ownship_report, Custom(CustomMessage::PreciseOwnshipReport), PreciseOwnshipReport),
+    (precise_traffic, Custom(CustomMessage::PreciseTraffic), PreciseTraffic),
+    (ping, Custom(CustomMessage::Ping), Ping),
+    (pong, Custom(CustomMessage::Pong), Pong),
 }
 
+// Message IDs
+
 impl Message {
+    pub const ID_HEARTBEAT: u8 = 0;
+    pub const ID_INITIALIZATION: u8 = 2;
+    pub const ID_UPLINK_DATA: u8 = 7;
+    pub const ID_HEIGHT_ABOVE_TERRAIN: u8 = 9;
+    pub const ID_OWNSHIP: u8 = 10;
+    pub const ID_OWNSHIP_GEOMETRIC_ALTITUDE: u8 = 11;
+    pub const ID_TRAFFIC: u8 = 20;
+    pub const ID_BASIC_REPORT: u8 = 30;
+    pub const ID_LONG_REPORT: u8 = 31;
+    pub const ID_FORE_FLIGHT: u8 = 0x65;
+    pub const ID_CUSTOM: u8 = 0xC9;
+
+    /// Message IDs decoded without a registered extension
+    pub const BUILT_IN_IDS: [u8; 11] = [
+        Self::ID_HEARTBEAT,
+        Self::ID_INITIALIZATION,
+        Self::ID_UPLINK_DATA,
+        Self::ID_HEIGHT_ABOVE_TERRAIN,
+        Self::ID_OWNSHIP,
+        Self::ID_OWNSHIP_GEOMETRIC_ALTITUDE,
+        Self::ID_TRAFFIC,
+        Self::ID_BASIC_REPORT,
+        Self::ID_LONG_REPORT,
+        Self::ID_FORE_FLIGHT,
+        Self::ID_CUSTOM,
+    ];
+
+    /// The message ID this message is sent with
     #[must_use]
-    pub fn is_fore_flight_id(&self) -> bool {
-        matches!(self, Self::ForeFlight(ForeFlightMessage::ID(_)))
-    }
-    #[must_use]
-    pub fn is_fore_flight_ahrs(&self) -> bool {
-        matches!(self, Self::ForeFlight(ForeFlightMessage::AHRS(_)))
-    }
-    #[must_use]
-    pub fn is_precise_ownship(&self) -> bool {
-        matches!(self, Self::Custom(CustomMessage::PreciseOwnship(_)))
+    pub fn message_id(&self) -> u8 {
+        match self {
+            Self::Heartbeat(_) => Self::ID_HEARTBEAT,
+            Self::Initialization(_) => Self::ID_INITIALIZATION,
+            Self::UplinkData(_) => Self::ID_UPLINK_DATA,
+            Self::HeightAboveTerrain(_) => Self::ID_HEIGHT_ABOVE_TERRAIN,
+            Self::Ownship(_) => Self::ID_OWNSHIP,
+            Self::OwnshipGeometricAltitude(_) => Self::ID_OWNSHIP_GEOMETRIC_ALTITUDE,
+            Self::Traffic(_) => Self::ID_TRAFFIC,
+            Self::BasicReport => Self::ID_BASIC_REPORT,
+            Self::LongReport => Self::ID_LONG_REPORT,
+            Self::ForeFlight(_) => Self::ID_FORE_FLIGHT,
+            Self::Custom(_) => Self::ID_CUSTOM,
+            Self::Extension(ext) => ext.key().message_id,
+        }
     }
+
+    /// The sub-ID following the message ID, for messages that have one
     #[must_use]
-    pub fn fore_flight_id(&self) -> Option<&ForeFlightID> {
-        if let Self::ForeFlight(ForeFlightMessage::ID(id)) = self {
-            Some(id)
-        } else {
-            None
+    pub fn sub_id(&self) -> Option<u8> {
+        match self {
+            Self::ForeFlight(ForeFlightMessage::ID(_)) => Some(0),
+            Self::ForeFlight(ForeFlightMessage::AHRS(_)) => Some(1),
+            Self::Custom(CustomMessage::PreciseOwnship(_)) => Some(0),
+            Self::Custom(CustomMessage::PreciseOwnshipReport(_)) => Some(1),
+            Self::Custom(CustomMessage::PreciseTraffic(_)) => Some(2),
+            Self::Custom(CustomMessage::Ping(_)) => Some(3),
+            Self::Custom(CustomMessage::Pong(_)) => Some(4),
+            Self::Extension(ext) => ext.key().sub_id,
+            _ => None,
         }
     }
+
+    /// Name of the message type, sub-messages are named after their inner type
     #[must_use]
-    pub fn fore_flight_ahrs(&self) -> Option<&ForeFlightAHRS> {
-        if let Self::ForeFlight(ForeFlightMessage::AHRS(ahrs)) = self {
-            Some(ahrs)
-        } else {
-            None
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Heartbeat(_) => "Heartbeat",
+            Self::Initialization(_) => "Initialization",
+            Self::UplinkData(_) => "UplinkData",
+            Self::HeightAboveTerrain(_) => "HeightAboveTerrain",
+            Self::Ownship(_) => "Ownship",
+            Self::OwnshipGeometricAltitude(_) => "OwnshipGeometricAltitude",
+            Self::Traffic(_) => "Traffic",
+            Self::BasicReport => "BasicReport",
+            Self::LongReport => "LongReport",
+            Self::ForeFlight(ForeFlightMessage::ID(_)) => "ForeFlightID",
+            Self::ForeFlight(ForeFlightMessage::AHRS(_)) => "ForeFlightAHRS",
+            Self::Custom(CustomMessage::PreciseOwnship(_)) => "PreciseOwnship",
+            Self::Custom(CustomMessage::PreciseOwnshipReport(_)) => "PreciseOwnshipReport",
+            Self::Custom(CustomMessage::PreciseTraffic(_)) => "PreciseTraffic",
+            Self::Custom(CustomMessage::Ping(_)) => "Ping",
+            Self::Custom(CustomMessage::Pong(_)) => "Pong",
+            Self::Extension(_) => "Extension",
         }
     }
-    #[must_use]
-    pub fn precise_ownship(&self) -> Option<&PreciseOwnship> {
-        if let Self::Custom(CustomMessage::PreciseOwnship(ownship)) = self {
-            Some(ownship)
-        } else {
-            None
+}
+
+impl DekuReader<'_> for Message {
+    /// Built-in messages only, extensions are decoded in `MessageWrapper::from_unescaped_bytes`
+    fn from_reader_with_ctx<R: std::io::Read + std::io::Seek>(
+        reader: &mut deku::reader::Reader<R>,
+        (): (),
+    ) -> Result<Self, DekuError> {
+        let id = u8::from_reader_with_ctx(reader, ())?;
+        Ok(match id {
+            Self::ID_HEARTBEAT => Self::Heartbeat(Heartbeat::from_reader_with_ctx(reader, ())?),
+            Self::ID_INITIALIZATION => {
+                Self::Initialization(Initialization::from_reader_with_ctx(reader, ())?)
+            }
+            Self::ID_UPLINK_DATA => Self::UplinkData(UplinkData::from_reader_with_ctx(reader, ())?),
+            Self::ID_HEIGHT_ABOVE_TERRAIN => {
+                Self::HeightAboveTerrain(HeightAboveTerrain::from_reader_with_ctx(reader, ())?)
+            }
+            Self::ID_OWNSHIP => Self::Ownship(OwnshipMessage::from_reader_with_ctx(reader, ())?),
+            Self::ID_OWNSHIP_GEOMETRIC_ALTITUDE => Self::OwnshipGeometricAltitude(
+                OwnshipGeometricAltitude::from_reader_with_ctx(reader, ())?,
+            ),
+            Self::ID_TRAFFIC => Self::Traffic(TrafficMessage::from_reader_with_ctx(reader, ())?),
+            Self::ID_BASIC_REPORT => Self::BasicReport,
+            Self::ID_LONG_REPORT => Self::LongReport,
+            Self::ID_FORE_FLIGHT => {
+                Self::ForeFlight(ForeFlightMessage::from_reader_with_ctx(reader, ())?)
+            }
+            Self::ID_CUSTOM => Self::Custom(CustomMessage::from_reader_with_ctx(reader, ())?),
+            _ => {
+                bail!(DekuError::Parse(
+                    format!("Could not match enum variant id = {id} on enum `Message`").into()
+                ));
+            }
+        })
+    }
+}
+
+impl DekuWriter for Message {
+    fn to_writer<W: std::io::Write + std::io::Seek>(
+        &self,
+        writer: &mut Writer<W>,
+        (): (),
+    ) -> Result<(), DekuError> {
+        if let Self::Extension(ext) = self {
+            let bytes = ext
+                .to_message_bytes()
+                .map_err(|e| DekuError::Parse(e.to_string().into()))?;
+            return writer.write_bytes(&bytes);
+        }
+
+        self.message_id().to_writer(writer, ())?;
+        match self {
+            Self::Heartbeat(m) => m.to_writer(writer, ()),
+            Self::Initialization(m) => m.to_writer(writer, ()),
+            Self::UplinkData(m) => m.to_writer(writer, ()),
+            Self::HeightAboveTerrain(m) => m.to_writer(writer, ()),
+            Self::Ownship(m) => m.to_writer(writer, ()),
+            Self::OwnshipGeometricAltitude(m) => m.to_writer(writer, ()),
+            Self::Traffic(m) => m.to_writer(writer, ()),
+            Self::ForeFlight(m) => m.to_writer(writer, ()),
+            Self::Custom(m) => m.to_writer(writer, ()),
+            Self::BasicReport | Self::LongReport | Self::Extension(_) => Ok(()),
         }
     }
 }
 
+impl DekuContainerWrite for Message {}
+
 // Encoding & Decoding
 
 impl Message {
@@ -188,7 +335,9 @@ impl MessageWrapper {
         Ok(Self { message, crc })
     }
 
-    /// For decoding. Validates length & crc and parses the message data
+    /// For decoding. Validates length & crc and parses the message data.
+    ///
+    /// Registered extensions are tried first, then the built-in messages.
     fn from_unescaped_bytes(bytes: impl AsRef<[u8]>) -> GDL90Result<Self> {
         let bytes = bytes.as_ref();
         let len = bytes.len();
@@ -196,17 +345,30 @@ impl MessageWrapper {
             bail!(GDL90Error::MessageTooShort(len));
         }
 
-        let wrapper = Self::try_from(bytes)?;
+        let crc = u16::from_le_bytes([bytes[len - 2], bytes[len - 1]]);
         let crc_actual = crc_calc(&bytes[..len - 2]);
 
-        if crc_actual != wrapper.crc {
+        if crc_actual != crc {
             bail!(GDL90Error::CrcMismatch {
-                expected: wrapper.crc,
+                expected: crc,
                 got: crc_actual
             });
         }
 
-        Ok(wrapper)
+        if let Some(ext) = decode_extension(&bytes[..len - 2]) {
+            let message = Message::Extension(ext?);
+            return Ok(Self { message, crc });
+        }
+
+        if !Message::BUILT_IN_IDS.contains(&bytes[0]) {
+            bail!(GDL90Error::UnknownMessageId(bytes[0]));
+        }
+
+        if let [Message::ID_CUSTOM, sub_id, version, ..] = *bytes {
+            CustomMessage::check_version(sub_id, version)?;
+        }
+
+        Ok(Self::try_from(bytes)?)
     }
 
     /// Assumes crc is already set. wrapper -> escaped
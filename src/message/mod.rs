@@ -1,8 +1,13 @@
 use crate::prelude::*;
 
 mod crc;
+pub mod decoder;
+pub mod dissect;
+pub mod extension;
 mod r#impl;
 
+pub use self::{decoder::*, dissect::*, extension::*};
+
 pub trait GDL90Encode {
     /// Encode into a GDL90 byte vector, ready to be sent.
     ///
@@ -43,43 +48,43 @@ impl Message {
 /// `GDL90Encode::into_gdl90_bytes()` is implemented for `T: Into<Message>`, which is implemented for every variant's inner type.
 /// It's not necessary to wrap message data in `Message`, as `into_gdl90_bytes()` can be used on the inner type directly.
 ///
+/// Message types from other crates can be plugged in with `register_extension()`,
+/// they are decoded into `Message::Extension`.
+///
 /// <https://www.faa.gov/sites/faa.gov/files/air_traffic/technology/adsb/archival/GDL90_Public_ICD_RevA.PDF>
-#[derive(Debug, Clone, PartialEq, DekuRead, DekuWrite, EnumGet)]
-#[deku(id_type = "u8")]
+///
+/// `DekuRead` and `DekuWrite` are implemented by hand, the message IDs are the `Message::ID_*` constants.
+///
+/// With the `serde` feature every message implements `Serialize` and `Deserialize`,
+/// quantities are plain numbers with the unit in the field name, see `util::serde_fields`.
+#[derive(Debug, Clone, PartialEq, EnumGet)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(clippy::large_enum_variant)] // TODO: Fix later
 pub enum Message {
-    #[deku(id = 0)]
     Heartbeat(Heartbeat),
 
-    #[deku(id = 2)]
     Initialization(Initialization),
 
-    #[deku(id = 7)]
     UplinkData(UplinkData),
 
-    #[deku(id = 9)]
     HeightAboveTerrain(HeightAboveTerrain),
 
-    #[deku(id = 10)]
     Ownship(OwnshipMessage),
 
-    #[deku(id = 11)]
     OwnshipGeometricAltitude(OwnshipGeometricAltitude),
 
-    #[deku(id = 20)]
     Traffic(TrafficMessage),
 
-    #[deku(id = 30)]
     BasicReport,
 
-    #[deku(id = 31)]
     LongReport,
 
-    #[deku(id = 0x65)]
     ForeFlight(ForeFlightMessage),
 
-    #[deku(id = 0xC9)]
     Custom(CustomMessage),
+
+    /// Registered extension, see `Extension`
+    Extension(ExtensionMessage),
 }
 
 /// ForeFlight Messages (extended spec)
@@ -91,6 +96,7 @@ pub enum Message {
 ///
 /// <https://www.foreflight.com/connect/spec>
 #[derive(Debug, Clone, PartialEq, DekuRead, DekuWrite, EnumGet)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(id_type = "u8")]
 pub enum ForeFlightMessage {
     #[deku(id = 0)]
@@ -100,11 +106,27 @@ pub enum ForeFlightMessage {
     AHRS(ForeFlightAHRS),
 }
 
+/// Custom Messages (not part of any spec)
+///
+/// Sub-IDs 1 and up start with a version byte, see `CustomMessage::check_version()`.
 #[derive(Debug, Clone, PartialEq, DekuRead, DekuWrite, EnumGet)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(id_type = "u8")]
 pub enum CustomMessage {
     #[deku(id = 0)]
     PreciseOwnship(PreciseOwnship),
+
+    #[deku(id = 1)]
+    PreciseOwnshipReport(PreciseOwnshipReport),
+
+    #[deku(id = 2)]
+    PreciseTraffic(PreciseTraffic),
+
+    #[deku(id = 3)]
+    Ping(Ping),
+
+    #[deku(id = 4)]
+    Pong(Pong),
 }
 
 #[cfg(test)]
@@ -154,3 +154,181 @@ fn multiple_messages_encode_decode() {
 
     assert_eq!(bytes, bytes2);
 }
+
+#[derive(Debug, Clone, PartialEq)]
+struct Battery {
+    percent: u8,
+}
+
+impl Extension for Battery {
+    const KEY: ExtensionKey = ExtensionKey::with_sub_id(0xC9, 0x7F);
+
+    fn decode(payload: &[u8]) -> GDL90Result<Self> {
+        match payload {
+            [percent] => Ok(Self { percent: *percent }),
+            _ => Err(GDL90Error::Extension(format!(
+                "bad length {}",
+                payload.len()
+            ))),
+        }
+    }
+
+    fn encode(&self) -> GDL90Result<Vec<u8>> {
+        Ok(vec![self.percent])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Vendor(Vec<u8>);
+
+impl Extension for Vendor {
+    const KEY: ExtensionKey = ExtensionKey::new(0x70);
+
+    fn decode(payload: &[u8]) -> GDL90Result<Self> {
+        Ok(Self(payload.to_vec()))
+    }
+
+    fn encode(&self) -> GDL90Result<Vec<u8>> {
+        Ok(self.0.clone())
+    }
+}
+
+#[test]
+fn extension_sub_id_encode_decode() {
+    register_extension::<Battery>();
+    assert!(is_extension_registered(Battery::KEY));
+
+    let bytes = Battery { percent: 42 }.into_gdl90_bytes().unwrap();
+    assert_eq!(bytes[..4], [0x7E, 0xC9, 0x7F, 42]);
+
+    let mut packet = bytes.clone();
+    packet.extend_from_slice(&new_heartbeat().into_gdl90_bytes().unwrap());
+    let messages = Message::from_gdl90_bytes(packet);
+    assert_eq!(messages.len(), 2);
+
+    let ext = messages[0].as_ref().unwrap().extension().unwrap();
+    assert_eq!(ext.key(), Battery::KEY);
+    assert!(ext.is::<Battery>());
+    assert!(!ext.is::<Vendor>());
+    assert_eq!(
+        ext.downcast_ref::<Battery>(),
+        Some(&Battery { percent: 42 })
+    );
+    assert!(messages[1].as_ref().unwrap().is_heartbeat());
+
+    let reencoded = messages[0]
+        .as_ref()
+        .unwrap()
+        .clone()
+        .into_gdl90_bytes()
+        .unwrap();
+    assert_eq!(reencoded, bytes);
+
+    // other sub-IDs of 0xC9 still go to the built-in custom messages
+    let precise = PreciseOwnship::default().into_gdl90_bytes().unwrap();
+    let precise = Message::from_gdl90_bytes(precise);
+    assert!(precise[0].as_ref().unwrap().is_precise_ownship());
+
+    let too_long = Message::from_gdl90_bytes([0x7E, 0xC9, 0x7F, 1, 2, 0xB6, 0x22, 0x7E]);
+    assert!(matches!(too_long[0], Err(GDL90Error::Extension(_))));
+}
+
+#[test]
+fn extension_message_id_encode_decode() {
+    let vendor = Vendor(vec![1, 2, 3, 0x7E]);
+
+    let bytes = vendor.clone().into_gdl90_bytes().unwrap();
+    assert!(Message::from_gdl90_bytes(&bytes)[0].is_err());
+
+    register_extension::<Vendor>();
+    let decoded = Message::from_gdl90_bytes(&bytes);
+    let decoded = decoded[0].as_ref().unwrap();
+    assert_eq!(decoded.message_id(), 0x70);
+    assert_eq!(decoded, &Message::from(vendor.clone()));
+    assert_eq!(
+        decoded.extension().unwrap().downcast_ref::<Vendor>(),
+        Some(&vendor)
+    );
+
+    assert!(unregister_extension(Vendor::KEY));
+    assert!(!unregister_extension(Vendor::KEY));
+    assert!(Message::from_gdl90_bytes(&bytes)[0].is_err());
+}
+
+#[test]
+fn unknown_message_id() {
+    let mut frame = vec![0x33, 0x01, 0x02];
+    frame.extend(crc::crc_calc(&frame).to_le_bytes());
+    frame.insert(0, 0x7E);
+    frame.push(0x7E);
+
+    let decoded = Message::from_gdl90_bytes(&frame);
+    assert!(matches!(
+        decoded[0],
+        Err(GDL90Error::UnknownMessageId(0x33))
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    register_extension::<Battery>();
+    let timestamp = chrono::DateTime::from_timestamp_micros(1_760_000_000_123_456).unwrap();
+
+    let messages: Vec<Message> = vec![
+        new_heartbeat().into(),
+        Initialization::default().with_cdti_ok().into(),
+        UplinkData::default()
+            .with_time_of_reception(std::time::Duration::from_micros(500))
+            .with_uplink_payload([0x7E, 0x7D, 1, 2, 3])
+            .into(),
+        HeightAboveTerrain::default()
+            .with_height_above_terrain(1234.feet())
+            .into(),
+        OwnshipGeometricAltitude::default()
+            .with_ownship_geo_altitude(5125.feet())
+            .into(),
+        new_ownship().into(),
+        TrafficReport::from(new_ownship()).traffic().into(),
+        new_ahrs().into(),
+        ForeFlightID::default()
+            .with_device_serial_number(1234)
+            .with_device_name("Name.")
+            .into(),
+        PreciseOwnshipReport::default()
+            .with_timestamp(timestamp)
+            .with_latitude(47.464_722_123.degrees())
+            .with_altitude(31_000.5.feet())
+            .with_vertical_speed((-1250.5).feet_per_minute())
+            .with_horizontal_accuracy(2.5.meters())
+            .into(),
+        PreciseTraffic::default()
+            .with_timestamp(timestamp)
+            .with_longitude(8.549_167_456.degrees())
+            .with_callsign("SWR123")
+            .into(),
+        Ping::new(7, timestamp).into(),
+        Battery { percent: 42 }.into(),
+    ];
+
+    for message in messages {
+        // what a receiver would have decoded
+        let bytes = message.into_gdl90_bytes().unwrap();
+        let decoded = Message::from_gdl90_bytes(&bytes).remove(0).unwrap();
+
+        let json = serde_json::to_string(&decoded).unwrap();
+        let restored: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, decoded, "{json}");
+        assert_eq!(restored.into_gdl90_bytes().unwrap(), bytes, "{json}");
+    }
+
+    let json = serde_json::to_value(Message::from(new_ownship())).unwrap();
+    let report = &json["Ownship"];
+    assert_eq!(report["altitude_ft"], 5000.0);
+    assert!(report["horizontal_velocity_kt"].is_f64());
+    assert!(report.get("horizontal_velocity").is_none());
+    assert_eq!(report["nacp"], "NACp9_HFOM_30M_VFOM_45M");
+
+    let json = serde_json::to_value(Message::from(new_heartbeat())).unwrap();
+    assert_eq!(json["Heartbeat"]["timestamp_s"], 53467);
+}
@@ -1,73 +1,631 @@
-use crate::prelude::*;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    message_types::traffic_report::r#impl::{callsign_read, callsign_write},
+    prelude::*,
+};
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(bit_order = "msb", endian = "big")]
 /// Custom message I made up for some testing purposes, this is not real
 /// Lat, Lon, Alt, GS each sent with 64bit precision
+///
+/// Predates the versioned sub-messages, so it has no version byte.
 pub struct PreciseOwnship {
     #[deku(
-        reader = "Self::angle_read(deku::reader)",
-        writer = "Self::angle_write(deku::writer, self.latitude)"
+        reader = "angle_read(deku::reader)",
+        writer = "angle_write(deku::writer, self.latitude)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "latitude_deg", with = "crate::util::serde_fields::degrees")
     )]
     pub latitude: Angle,
 
     #[deku(
-        reader = "Self::angle_read(deku::reader)",
-        writer = "Self::angle_write(deku::writer, self.longitude)"
+        reader = "angle_read(deku::reader)",
+        writer = "angle_write(deku::writer, self.longitude)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "longitude_deg", with = "crate::util::serde_fields::degrees")
     )]
     pub longitude: Angle,
 
     /// Altitude in feet
     #[deku(
-        reader = "Self::length_read(deku::reader)",
-        writer = "Self::length_write(deku::writer, self.altitude)"
+        reader = "length_read(deku::reader)",
+        writer = "length_write(deku::writer, self.altitude)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "altitude_ft", with = "crate::util::serde_fields::feet")
     )]
     pub altitude: Length,
 
     /// Ground Speed in knots
     #[deku(
-        reader = "Self::velocity_read(deku::reader)",
-        writer = "Self::velocity_write(deku::writer, self.ground_speed)"
+        reader = "velocity_read(deku::reader)",
+        writer = "velocity_write(deku::writer, self.ground_speed)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "ground_speed_kt", with = "crate::util::serde_fields::knots")
     )]
     pub ground_speed: Velocity,
 }
 
-impl PreciseOwnship {
-    const CTX: (Endian, ByteSize) = (Endian::Big, ByteSize(8));
+#[derive(Debug, Clone, Copy, PartialEq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[deku(bit_order = "msb", endian = "big")]
+/// Precise ownship with time, track, vertical speed and accuracy, everything with 64bit precision
+pub struct PreciseOwnshipReport {
+    #[deku(bytes = 1)]
+    #[builder(skip(ctor), default = 1)]
+    /// Sub-message version, see `CustomMessage::check_version()`
+    pub version: u8,
+
+    /// UTC time of the position, microsecond resolution
+    #[deku(
+        reader = "timestamp_read(deku::reader)",
+        writer = "timestamp_write(deku::writer, self.timestamp)"
+    )]
+    pub timestamp: DateTime<Utc>,
+
+    #[deku(
+        reader = "angle_read(deku::reader)",
+        writer = "angle_write(deku::writer, self.latitude)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "latitude_deg", with = "crate::util::serde_fields::degrees")
+    )]
+    pub latitude: Angle,
+
+    #[deku(
+        reader = "angle_read(deku::reader)",
+        writer = "angle_write(deku::writer, self.longitude)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "longitude_deg", with = "crate::util::serde_fields::degrees")
+    )]
+    pub longitude: Angle,
+
+    /// Altitude in feet
+    #[deku(
+        reader = "length_read(deku::reader)",
+        writer = "length_write(deku::writer, self.altitude)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "altitude_ft", with = "crate::util::serde_fields::feet")
+    )]
+    pub altitude: Length,
+
+    /// Ground Speed in knots
+    #[deku(
+        reader = "velocity_read(deku::reader)",
+        writer = "velocity_write(deku::writer, self.ground_speed)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "ground_speed_kt", with = "crate::util::serde_fields::knots")
+    )]
+    pub ground_speed: Velocity,
+
+    /// True track in degrees
+    #[deku(
+        reader = "angle_read(deku::reader)",
+        writer = "angle_write(deku::writer, self.track)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "track_deg", with = "crate::util::serde_fields::degrees")
+    )]
+    pub track: Angle,
+
+    /// Vertical speed in feet per minute
+    #[deku(
+        reader = "vertical_speed_read(deku::reader)",
+        writer = "vertical_speed_write(deku::writer, self.vertical_speed)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "vertical_speed_fpm",
+            with = "crate::util::serde_fields::feet_per_minute"
+        )
+    )]
+    pub vertical_speed: Velocity,
+
+    /// Horizontal position accuracy (95%) in meters
+    ///
+    /// None = unavailable
+    #[deku(
+        reader = "accuracy_read(deku::reader)",
+        writer = "accuracy_write(deku::writer, self.horizontal_accuracy)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "horizontal_accuracy_m",
+            with = "crate::util::serde_fields::option_meters"
+        )
+    )]
+    pub horizontal_accuracy: Option<Length>,
+
+    /// Vertical position accuracy (95%) in meters
+    ///
+    /// None = unavailable
+    #[deku(
+        reader = "accuracy_read(deku::reader)",
+        writer = "accuracy_write(deku::writer, self.vertical_accuracy)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "vertical_accuracy_m",
+            with = "crate::util::serde_fields::option_meters"
+        )
+    )]
+    pub vertical_accuracy: Option<Length>,
+}
+
+#[derive(Debug, Clone, PartialEq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[deku(bit_order = "msb", endian = "big")]
+/// Precise traffic report, keyed by the target's address like `TrafficReport`
+pub struct PreciseTraffic {
+    #[deku(bytes = 1)]
+    #[builder(skip(ctor), default = 1)]
+    /// Sub-message version, see `CustomMessage::check_version()`
+    pub version: u8,
+
+    /// UTC time of the position, microsecond resolution
+    #[deku(
+        reader = "timestamp_read(deku::reader)",
+        writer = "timestamp_write(deku::writer, self.timestamp)"
+    )]
+    pub timestamp: DateTime<Utc>,
+
+    /// Address Type & Participant (ICAO) Address
+    #[deku(pad_bits_before = "4")]
+    pub target_identity: TargetIdentity,
+
+    #[deku(
+        reader = "angle_read(deku::reader)",
+        writer = "angle_write(deku::writer, self.latitude)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "latitude_deg", with = "crate::util::serde_fields::degrees")
+    )]
+    pub latitude: Angle,
+
+    #[deku(
+        reader = "angle_read(deku::reader)",
+        writer = "angle_write(deku::writer, self.longitude)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "longitude_deg", with = "crate::util::serde_fields::degrees")
+    )]
+    pub longitude: Angle,
+
+    /// Altitude in feet
+    #[deku(
+        reader = "length_read(deku::reader)",
+        writer = "length_write(deku::writer, self.altitude)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "altitude_ft", with = "crate::util::serde_fields::feet")
+    )]
+    pub altitude: Length,
+
+    /// Ground Speed in knots
+    #[deku(
+        reader = "velocity_read(deku::reader)",
+        writer = "velocity_write(deku::writer, self.ground_speed)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "ground_speed_kt", with = "crate::util::serde_fields::knots")
+    )]
+    pub ground_speed: Velocity,
+
+    /// True track in degrees
+    #[deku(
+        reader = "angle_read(deku::reader)",
+        writer = "angle_write(deku::writer, self.track)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "track_deg", with = "crate::util::serde_fields::degrees")
+    )]
+    pub track: Angle,
+
+    /// Vertical speed in feet per minute
+    #[deku(
+        reader = "vertical_speed_read(deku::reader)",
+        writer = "vertical_speed_write(deku::writer, self.vertical_speed)"
+    )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "vertical_speed_fpm",
+            with = "crate::util::serde_fields::feet_per_minute"
+        )
+    )]
+    pub vertical_speed: Velocity,
+
+    pub emitter_category: EmitterCategory,
+
+    /// Call Sign: 8 ASCII characters, padded with spaces, same as `TrafficReport`
+    #[deku(
+        reader = "callsign_read(deku::reader)",
+        writer = "callsign_write(deku::writer, &self.callsign)"
+    )]
+    pub callsign: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[deku(bit_order = "msb", endian = "big")]
+/// Latency measurement request, answer with `Pong::reply_to()`
+pub struct Ping {
+    #[deku(bytes = 1)]
+    #[builder(skip(ctor), default = 1)]
+    /// Sub-message version, see `CustomMessage::check_version()`
+    pub version: u8,
+
+    /// Chosen by the sender, echoed in the `Pong`
+    #[deku(bytes = 4)]
+    pub sequence: u32,
+
+    /// UTC time the ping was sent, microsecond resolution
+    #[deku(
+        reader = "timestamp_read(deku::reader)",
+        writer = "timestamp_write(deku::writer, self.sent_at)"
+    )]
+    pub sent_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[deku(bit_order = "msb", endian = "big")]
+/// Latency measurement response to a `Ping`
+pub struct Pong {
+    #[deku(bytes = 1)]
+    #[builder(skip(ctor), default = 1)]
+    /// Sub-message version, see `CustomMessage::check_version()`
+    pub version: u8,
+
+    /// Sequence of the `Ping` this answers
+    #[deku(bytes = 4)]
+    pub sequence: u32,
+
+    /// `Ping::sent_at` echoed back, in the pinging side's clock
+    #[deku(
+        reader = "timestamp_read(deku::reader)",
+        writer = "timestamp_write(deku::writer, self.ping_sent_at)"
+    )]
+    pub ping_sent_at: DateTime<Utc>,
+
+    /// UTC time the pong was sent, in the responding side's clock
+    #[deku(
+        reader = "timestamp_read(deku::reader)",
+        writer = "timestamp_write(deku::writer, self.sent_at)"
+    )]
+    pub sent_at: DateTime<Utc>,
+}
 
-    fn angle_read<R: std::io::Read + std::io::Seek>(
-        reader: &mut deku::reader::Reader<R>,
-    ) -> Result<Angle, DekuError> {
-        Ok(f64::from_reader_with_ctx(reader, Self::CTX)?.degrees())
+impl CustomMessage {
+    /// Newest version of each versioned sub-message this crate can read, by sub-ID
+    const VERSIONS: [(u8, u8); 4] = [
+        (1, PreciseOwnshipReport::VERSION),
+        (2, PreciseTraffic::VERSION),
+        (3, Ping::VERSION),
+        (4, Pong::VERSION),
+    ];
+
+    /// Versioned sub-messages start with a version byte right after the sub-ID.
+    /// Anything newer than what this crate knows is rejected before parsing,
+    /// so a newer sender can't be misread and readers can skip it by the error.
+    pub(crate) fn check_version(sub_id: u8, version: u8) -> GDL90Result<()> {
+        let supported = Self::VERSIONS
+            .iter()
+            .find_map(|&(id, v)| (id == sub_id).then_some(v));
+
+        match supported {
+            Some(supported) if version > supported => Err(GDL90Error::UnsupportedVersion {
+                message_id: Message::ID_CUSTOM,
+                sub_id,
+                version,
+                supported,
+            }),
+            _ => Ok(()),
+        }
     }
-    fn angle_write<W: std::io::Write + std::io::Seek>(
-        writer: &mut Writer<W>,
-        deg: Angle,
-    ) -> Result<(), DekuError> {
-        deg.degrees().to_writer(writer, Self::CTX)
+}
+
+impl PreciseOwnshipReport {
+    pub const VERSION: u8 = 1;
+}
+
+impl Default for PreciseOwnshipReport {
+    fn default() -> Self {
+        Self {
+            version: Self::VERSION,
+            timestamp: DateTime::default(),
+            latitude: Angle::default(),
+            longitude: Angle::default(),
+            altitude: Length::default(),
+            ground_speed: Velocity::default(),
+            track: Angle::default(),
+            vertical_speed: Velocity::default(),
+            horizontal_accuracy: None,
+            vertical_accuracy: None,
+        }
     }
+}
+
+impl PreciseTraffic {
+    pub const VERSION: u8 = 1;
+}
 
-    fn length_read<R: std::io::Read + std::io::Seek>(
-        reader: &mut deku::reader::Reader<R>,
-    ) -> Result<Length, DekuError> {
-        Ok(f64::from_reader_with_ctx(reader, Self::CTX)?.feet())
+impl Default for PreciseTraffic {
+    fn default() -> Self {
+        Self {
+            version: Self::VERSION,
+            timestamp: DateTime::default(),
+            target_identity: TargetIdentity::default(),
+            latitude: Angle::default(),
+            longitude: Angle::default(),
+            altitude: Length::default(),
+            ground_speed: Velocity::default(),
+            track: Angle::default(),
+            vertical_speed: Velocity::default(),
+            emitter_category: EmitterCategory::default(),
+            callsign: String::new(),
+        }
     }
-    fn length_write<W: std::io::Write + std::io::Seek>(
-        writer: &mut Writer<W>,
-        len: Length,
-    ) -> Result<(), DekuError> {
-        len.feet().to_writer(writer, Self::CTX)
+}
+
+impl Ping {
+    pub const VERSION: u8 = 1;
+
+    /// Ping sent now
+    #[must_use]
+    pub fn now(sequence: u32) -> Self {
+        Self::new(sequence, Utc::now())
+    }
+}
+
+impl Default for Ping {
+    fn default() -> Self {
+        Self {
+            version: Self::VERSION,
+            sequence: 0,
+            sent_at: DateTime::default(),
+        }
+    }
+}
+
+impl Pong {
+    pub const VERSION: u8 = 1;
+
+    /// Answer `ping`, sent now
+    #[must_use]
+    pub fn reply_to(ping: &Ping) -> Self {
+        Self::new(ping.sequence, ping.sent_at, Utc::now())
     }
 
-    fn velocity_read<R: std::io::Read + std::io::Seek>(
-        reader: &mut deku::reader::Reader<R>,
-    ) -> Result<Velocity, DekuError> {
-        Ok(f64::from_reader_with_ctx(reader, Self::CTX)?.knots())
+    /// Time from sending the `Ping` until `received_at`, in the pinging side's clock.
+    ///
+    /// None if `received_at` is before the ping was sent.
+    #[must_use]
+    pub fn round_trip_time(&self, received_at: DateTime<Utc>) -> Option<Duration> {
+        (received_at - self.ping_sent_at).to_std().ok()
     }
-    fn velocity_write<W: std::io::Write + std::io::Seek>(
-        writer: &mut Writer<W>,
-        vel: Velocity,
-    ) -> Result<(), DekuError> {
-        vel.knots().to_writer(writer, Self::CTX)
+}
+
+impl Default for Pong {
+    fn default() -> Self {
+        Self {
+            version: Self::VERSION,
+            sequence: 0,
+            ping_sent_at: DateTime::default(),
+            sent_at: DateTime::default(),
+        }
+    }
+}
+
+const CTX: (Endian, ByteSize) = (Endian::Big, ByteSize(8));
+
+fn angle_read<R: std::io::Read + std::io::Seek>(
+    reader: &mut deku::reader::Reader<R>,
+) -> Result<Angle, DekuError> {
+    Ok(f64::from_reader_with_ctx(reader, CTX)?.degrees())
+}
+fn angle_write<W: std::io::Write + std::io::Seek>(
+    writer: &mut Writer<W>,
+    deg: Angle,
+) -> Result<(), DekuError> {
+    deg.degrees().to_writer(writer, CTX)
+}
+
+fn length_read<R: std::io::Read + std::io::Seek>(
+    reader: &mut deku::reader::Reader<R>,
+) -> Result<Length, DekuError> {
+    Ok(f64::from_reader_with_ctx(reader, CTX)?.feet())
+}
+fn length_write<W: std::io::Write + std::io::Seek>(
+    writer: &mut Writer<W>,
+    len: Length,
+) -> Result<(), DekuError> {
+    len.feet().to_writer(writer, CTX)
+}
+
+fn velocity_read<R: std::io::Read + std::io::Seek>(
+    reader: &mut deku::reader::Reader<R>,
+) -> Result<Velocity, DekuError> {
+    Ok(f64::from_reader_with_ctx(reader, CTX)?.knots())
+}
+fn velocity_write<W: std::io::Write + std::io::Seek>(
+    writer: &mut Writer<W>,
+    vel: Velocity,
+) -> Result<(), DekuError> {
+    vel.knots().to_writer(writer, CTX)
+}
+
+fn vertical_speed_read<R: std::io::Read + std::io::Seek>(
+    reader: &mut deku::reader::Reader<R>,
+) -> Result<Velocity, DekuError> {
+    Ok(f64::from_reader_with_ctx(reader, CTX)?.feet_per_minute())
+}
+fn vertical_speed_write<W: std::io::Write + std::io::Seek>(
+    writer: &mut Writer<W>,
+    vs: Velocity,
+) -> Result<(), DekuError> {
+    vs.feet_per_minute().to_writer(writer, CTX)
+}
+
+// Meters, NaN = unavailable
+fn accuracy_read<R: std::io::Read + std::io::Seek>(
+    reader: &mut deku::reader::Reader<R>,
+) -> Result<Option<Length>, DekuError> {
+    let m = f64::from_reader_with_ctx(reader, CTX)?;
+    Ok(if m.is_nan() { None } else { Some(m.meters()) })
+}
+fn accuracy_write<W: std::io::Write + std::io::Seek>(
+    writer: &mut Writer<W>,
+    accuracy: Option<Length>,
+) -> Result<(), DekuError> {
+    accuracy
+        .map_or(f64::NAN, |a| a.meters())
+        .to_writer(writer, CTX)
+}
+
+// Microseconds since the unix epoch, 64-bit signed integer
+fn timestamp_read<R: std::io::Read + std::io::Seek>(
+    reader: &mut deku::reader::Reader<R>,
+) -> Result<DateTime<Utc>, DekuError> {
+    let us = i64::from_reader_with_ctx(reader, CTX)?;
+    DateTime::from_timestamp_micros(us)
+        .ok_or_else(|| DekuError::Parse(format!("timestamp out of range: {us}us").into()))
+}
+fn timestamp_write<W: std::io::Write + std::io::Seek>(
+    writer: &mut Writer<W>,
+    timestamp: DateTime<Utc>,
+) -> Result<(), DekuError> {
+    timestamp.timestamp_micros().to_writer(writer, CTX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp() -> DateTime<Utc> {
+        DateTime::from_timestamp_micros(1_760_000_000_123_456).unwrap()
+    }
+
+    #[test]
+    fn precise_ownship_report_encode_decode() {
+        let report = PreciseOwnshipReport::default()
+            .with_timestamp(timestamp())
+            .with_latitude(47.464_722_123.degrees())
+            .with_longitude(8.549_167_456.degrees())
+            .with_altitude(31_000.5.feet())
+            .with_ground_speed(450.25.knots())
+            .with_track(214.75.degrees())
+            .with_vertical_speed((-1250.5).feet_per_minute())
+            .with_horizontal_accuracy(2.5.meters());
+
+        let bytes = report.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 1 + 8 * 9);
+        assert_eq!(bytes[0], PreciseOwnshipReport::VERSION);
+        assert_eq!(bytes[1..9], 1_760_000_000_123_456i64.to_be_bytes());
+
+        let decoded = PreciseOwnshipReport::from_bytes((&bytes, 0)).unwrap().1;
+        assert_eq!(decoded, report);
+        assert_eq!(decoded.vertical_accuracy, None);
+    }
+
+    #[test]
+    fn precise_traffic_encode_decode() {
+        let traffic = PreciseTraffic::default()
+            .with_timestamp(timestamp())
+            .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, 0xAB_CD_EF))
+            .with_latitude((-33.946_111).degrees())
+            .with_longitude(151.177_222.degrees())
+            .with_altitude(1200.feet())
+            .with_ground_speed(140.knots())
+            .with_track(340.degrees())
+            .with_vertical_speed(700.feet_per_minute())
+            .with_emitter_category(EmitterCategory::Large)
+            .with_callsign("QFA12");
+
+        let bytes = traffic.to_bytes().unwrap();
+        assert_eq!(bytes[9..13], [0x00, 0xAB, 0xCD, 0xEF]);
+
+        let decoded = PreciseTraffic::from_bytes((&bytes, 0)).unwrap().1;
+        assert_eq!(decoded, traffic);
+    }
+
+    #[test]
+    fn ping_pong() {
+        let ping = Ping::new(7, timestamp());
+        assert_eq!(ping.version, Ping::VERSION);
+
+        let bytes = ping.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 1 + 4 + 8);
+        assert_eq!(Ping::from_bytes((&bytes, 0)).unwrap().1, ping);
+
+        let pong = Pong::reply_to(&ping);
+        assert_eq!(pong.sequence, 7);
+        assert_eq!(pong.ping_sent_at, ping.sent_at);
+
+        let bytes = pong.to_bytes().unwrap();
+        assert_eq!(Pong::from_bytes((&bytes, 0)).unwrap().1, pong);
+
+        let rtt = pong.round_trip_time(timestamp() + chrono::Duration::milliseconds(12));
+        assert_eq!(rtt, Some(Duration::from_millis(12)));
+        assert_eq!(
+            pong.round_trip_time(timestamp() - chrono::Duration::seconds(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        // Ping version 2, sequence 1
+        const PING_V2: [u8; 19] = [
+            0x7E, 0xC9, 0x03, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x06, 0x40, 0xB5, 0xEE, 0xCF,
+            0xE2, 0x40, 0x4C, 0xF3, 0x7E,
+        ];
+
+        let decoded = Message::from_gdl90_bytes(PING_V2);
+        assert!(matches!(
+            decoded[0],
+            Err(GDL90Error::UnsupportedVersion {
+                message_id: 0xC9,
+                sub_id: 3,
+                version: 2,
+                supported: 1,
+            })
+        ));
+
+        let ping = Ping::new(1, timestamp());
+        let decoded = Message::from_gdl90_bytes(ping.into_gdl90_bytes().unwrap());
+        assert_eq!(decoded[0].as_ref().unwrap().ping(), Some(&ping));
+
+        assert!(CustomMessage::check_version(0, 0xFF).is_ok());
+        assert!(CustomMessage::check_version(1, 1).is_ok());
+        assert!(CustomMessage::check_version(1, 2).is_err());
     }
 }
@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(bit_order = "msb", endian = "big")]
 /// # ForeFlight AHRS Message
 pub struct ForeFlightAHRS {
@@ -8,12 +9,26 @@ pub struct ForeFlightAHRS {
         reader = "ForeFlightAHRS::roll_pitch_read(deku::reader)",
         writer = "ForeFlightAHRS::roll_pitch_write(deku::writer, self.roll)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "roll_deg",
+            with = "crate::util::serde_fields::option_degrees"
+        )
+    )]
     pub roll: Option<Angle>,
 
     #[deku(
         reader = "ForeFlightAHRS::roll_pitch_read(deku::reader)",
         writer = "ForeFlightAHRS::roll_pitch_write(deku::writer, self.pitch)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "pitch_deg",
+            with = "crate::util::serde_fields::option_degrees"
+        )
+    )]
     pub pitch: Option<Angle>,
 
     pub heading_type: AHRSHeadingType,
@@ -22,22 +37,44 @@ pub struct ForeFlightAHRS {
         reader = "ForeFlightAHRS::hdg_read(deku::reader)",
         writer = "ForeFlightAHRS::hdg_write(deku::writer, self.heading)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "heading_deg",
+            with = "crate::util::serde_fields::option_degrees"
+        )
+    )]
     pub heading: Option<Angle>,
 
     #[deku(
         reader = "ForeFlightAHRS::ias_tas_read(deku::reader)",
         writer = "ForeFlightAHRS::ias_tas_write(deku::writer, self.indicated_airspeed)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "indicated_airspeed_kt",
+            with = "crate::util::serde_fields::option_knots"
+        )
+    )]
     pub indicated_airspeed: Option<Velocity>,
 
     #[deku(
         reader = "ForeFlightAHRS::ias_tas_read(deku::reader)",
         writer = "ForeFlightAHRS::ias_tas_write(deku::writer, self.true_airspeed)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "true_airspeed_kt",
+            with = "crate::util::serde_fields::option_knots"
+        )
+    )]
     pub true_airspeed: Option<Velocity>,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, EnumGet)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(
     id_type = "u8",
     bits = 1,
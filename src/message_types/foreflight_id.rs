@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
 #[derive(Debug, Clone, PartialEq, Eq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(bit_order = "msb", endian = "big")]
 /// # ForeFlight ID Message
 pub struct ForeFlightID {
@@ -24,36 +25,151 @@ pub struct ForeFlightID {
     )]
     pub device_long_name: String,
 
-    #[deku(pad_bits_before = "29")]
     pub capabilities: Capabilities,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, Builder)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(ctx = "_: deku::ctx::Endian, _: deku::ctx::Order")]
-/// 4 bytes, 3 bits used (at the end)
+/// # Capabilities Mask
+///
+/// 4 bytes, big endian. Bit 0 is the LSB of the last byte.
+///
+/// | Bits | Meaning                                                        |
+/// | ---- | -------------------------------------------------------------- |
+/// | 0    | Geometric altitude datum of the Ownship Geometric Altitude msg |
+/// | 1-2  | Internet policy                                                |
+/// | 3-31 | Reserved                                                       |
+///
+/// The whole mask is kept, so reserved bits set by a sender survive a decode/encode round-trip.
 pub struct Capabilities {
-    pub foreflight_internet_policy: ForeFlightInternetPolicy,
-    pub geometric_altitude_datum: GeometricAltitudeDatum, // this one goes last since LSB?
+    #[deku(bytes = 4, endian = "big")]
+    mask: u32,
+}
+
+impl Capabilities {
+    /// Bit 0: `GeometricAltitudeDatum`
+    pub const GEOMETRIC_ALTITUDE_DATUM: u32 = 1;
+    /// Bits 1-2: `ForeFlightInternetPolicy`
+    pub const INTERNET_POLICY: u32 = 0b11 << Self::INTERNET_POLICY_SHIFT;
+    /// Bits 3-31: not defined by the spec
+    pub const RESERVED: u32 = !(Self::GEOMETRIC_ALTITUDE_DATUM | Self::INTERNET_POLICY);
+
+    const INTERNET_POLICY_SHIFT: u32 = 1;
+
+    #[must_use]
+    pub fn new(
+        foreflight_internet_policy: ForeFlightInternetPolicy,
+        geometric_altitude_datum: GeometricAltitudeDatum,
+    ) -> Self {
+        Self::default()
+            .with_foreflight_internet_policy(foreflight_internet_policy)
+            .with_geometric_altitude_datum(geometric_altitude_datum)
+    }
+
+    #[must_use]
+    pub const fn from_mask(mask: u32) -> Self {
+        Self { mask }
+    }
+
+    /// The raw mask, including reserved bits
+    #[must_use]
+    pub const fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// Only the bits not defined by the spec
+    #[must_use]
+    pub const fn reserved_bits(&self) -> u32 {
+        self.mask & Self::RESERVED
+    }
+
+    #[must_use]
+    pub fn geometric_altitude_datum(&self) -> GeometricAltitudeDatum {
+        if self.mask & Self::GEOMETRIC_ALTITUDE_DATUM == 0 {
+            GeometricAltitudeDatum::WGS84
+        } else {
+            GeometricAltitudeDatum::MSL
+        }
+    }
+
+    pub fn set_geometric_altitude_datum(&mut self, datum: GeometricAltitudeDatum) {
+        self.mask &= !Self::GEOMETRIC_ALTITUDE_DATUM;
+        self.mask |= u32::from(datum as u8) & Self::GEOMETRIC_ALTITUDE_DATUM;
+    }
+
+    #[must_use]
+    pub fn with_geometric_altitude_datum(mut self, datum: GeometricAltitudeDatum) -> Self {
+        self.set_geometric_altitude_datum(datum);
+        self
+    }
+
+    #[must_use]
+    pub fn foreflight_internet_policy(&self) -> ForeFlightInternetPolicy {
+        match (self.mask & Self::INTERNET_POLICY) >> Self::INTERNET_POLICY_SHIFT {
+            0 => ForeFlightInternetPolicy::Unrestricted,
+            1 => ForeFlightInternetPolicy::Expensive,
+            2 => ForeFlightInternetPolicy::Disallowed,
+            _ => ForeFlightInternetPolicy::Reserved,
+        }
+    }
+
+    pub fn set_foreflight_internet_policy(&mut self, policy: ForeFlightInternetPolicy) {
+        self.mask &= !Self::INTERNET_POLICY;
+        self.mask |=
+            (u32::from(policy as u8) << Self::INTERNET_POLICY_SHIFT) & Self::INTERNET_POLICY;
+    }
+
+    #[must_use]
+    pub fn with_foreflight_internet_policy(mut self, policy: ForeFlightInternetPolicy) -> Self {
+        self.set_foreflight_internet_policy(policy);
+        self
+    }
+}
+
+impl From<u32> for Capabilities {
+    fn from(mask: u32) -> Self {
+        Self::from_mask(mask)
+    }
+}
+
+impl From<Capabilities> for u32 {
+    fn from(capabilities: Capabilities) -> Self {
+        capabilities.mask
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, EnumGet)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(id_type = "u8", bits = 1)]
 #[repr(u8)]
+/// Capabilities bit 0.
+///
+/// Must match what is sent in `OwnshipGeometricAltitude`,
+/// otherwise ForeFlight shows the geometric altitude off by the geoid separation.
 pub enum GeometricAltitudeDatum {
+    /// Height above the WGS-84 ellipsoid
     #[default]
     WGS84 = 0,
+    /// Height above mean sea level
     MSL = 1,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, EnumGet)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(id_type = "u8", bits = 2)]
 #[repr(u8)]
+/// Capabilities bits 1-2, value = `(mask >> 1) & 0b11`
 pub enum ForeFlightInternetPolicy {
-    // not sure if the bit order is correct here
+    /// No restriction on internet use
     #[default]
     Unrestricted = 0,
+    /// Internet access is expensive, ForeFlight limits its use
     Expensive = 1,
+    /// ForeFlight must not use the internet
     Disallowed = 2,
+    /// Value 3, not defined by the spec
+    Reserved = 3,
 }
 
 impl Default for ForeFlightID {
@@ -76,7 +192,8 @@ impl ForeFlightID {
         mut self,
         geometric_altitude_datum: GeometricAltitudeDatum,
     ) -> Self {
-        self.capabilities.geometric_altitude_datum = geometric_altitude_datum;
+        self.capabilities
+            .set_geometric_altitude_datum(geometric_altitude_datum);
         self
     }
 
@@ -85,7 +202,8 @@ impl ForeFlightID {
         mut self,
         foreflight_internet_policy: ForeFlightInternetPolicy,
     ) -> Self {
-        self.capabilities.foreflight_internet_policy = foreflight_internet_policy;
+        self.capabilities
+            .set_foreflight_internet_policy(foreflight_internet_policy);
         self
     }
 }
@@ -177,4 +295,56 @@ mod tests {
         let bytes = id.to_bytes().unwrap();
         assert_eq!(bytes, BYTES);
     }
+
+    #[test]
+    fn capability_bits() {
+        let caps = Capabilities::default();
+        assert_eq!(caps.mask(), 0);
+        assert_eq!(
+            caps.geometric_altitude_datum(),
+            GeometricAltitudeDatum::WGS84
+        );
+        assert_eq!(
+            caps.foreflight_internet_policy(),
+            ForeFlightInternetPolicy::Unrestricted
+        );
+
+        let caps = caps.with_geometric_altitude_datum(GeometricAltitudeDatum::MSL);
+        assert_eq!(caps.mask(), 0b001);
+
+        for (policy, mask) in [
+            (ForeFlightInternetPolicy::Unrestricted, 0b001),
+            (ForeFlightInternetPolicy::Expensive, 0b011),
+            (ForeFlightInternetPolicy::Disallowed, 0b101),
+            (ForeFlightInternetPolicy::Reserved, 0b111),
+        ] {
+            let caps = caps.with_foreflight_internet_policy(policy);
+            assert_eq!(caps.mask(), mask);
+            assert_eq!(caps.foreflight_internet_policy(), policy);
+            assert_eq!(caps.geometric_altitude_datum(), GeometricAltitudeDatum::MSL);
+        }
+    }
+
+    #[test]
+    fn reserved_bits_round_trip() {
+        let mut bytes = BYTES;
+        bytes[33..].copy_from_slice(&[0x80, 0x01, 0x00, 0x0C]);
+
+        let mut id = ForeFlightID::from_bytes((&bytes, 0)).unwrap().1;
+        assert_eq!(id.capabilities.mask(), 0x8001_000C);
+        assert_eq!(id.capabilities.reserved_bits(), 0x8001_0008);
+        assert_eq!(
+            id.capabilities.geometric_altitude_datum(),
+            GeometricAltitudeDatum::WGS84
+        );
+        assert_eq!(
+            id.capabilities.foreflight_internet_policy(),
+            ForeFlightInternetPolicy::Disallowed
+        );
+        assert_eq!(id.to_bytes().unwrap(), bytes);
+
+        id = id.with_geometric_altitude_datum(GeometricAltitudeDatum::MSL);
+        assert_eq!(id.capabilities.mask(), 0x8001_000D);
+        assert_eq!(id.to_bytes().unwrap()[33..], [0x80, 0x01, 0x00, 0x0D]);
+    }
 }
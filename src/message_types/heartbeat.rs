@@ -4,6 +4,11 @@ use crate::prelude::*;
 
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "HeartbeatFields", into = "HeartbeatFields")
+)]
 #[deku(bit_order = "msb", endian = "big")]
 /// # 3.1. Heartbeat Message
 ///
@@ -96,11 +101,83 @@ impl Heartbeat {
         self
     }
 
+    /// Uplink messages received in the previous second, `message_counts` bits 15-11
+    #[must_use]
+    pub fn uplink_count(&self) -> u8 {
+        (self.message_counts >> 11) as u8
+    }
+
+    /// Basic and Long reports received in the previous second, `message_counts` bits 9-0
+    #[must_use]
+    pub fn basic_long_count(&self) -> u16 {
+        self.message_counts & 0x3FF
+    }
+
     fn now() -> u32 {
         chrono::Utc::now().num_seconds_from_midnight()
     }
 }
 
+/// Serialized form of `Heartbeat`, the split timestamp bits become `timestamp_s`
+#[cfg(feature = "serde")]
+#[allow(clippy::struct_excessive_bools)]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct HeartbeatFields {
+    gps_pos_valid: bool,
+    maint_reqd: bool,
+    ident: bool,
+    addr_type: bool,
+    gps_batt_low: bool,
+    ratcs: bool,
+    uat_initialized: bool,
+    csa_requested: bool,
+    csa_not_available: bool,
+    utc_ok: bool,
+    timestamp_s: u32,
+    message_counts: u16,
+}
+
+#[cfg(feature = "serde")]
+impl From<Heartbeat> for HeartbeatFields {
+    fn from(hb: Heartbeat) -> Self {
+        Self {
+            gps_pos_valid: hb.gps_pos_valid,
+            maint_reqd: hb.maint_reqd,
+            ident: hb.ident,
+            addr_type: hb.addr_type,
+            gps_batt_low: hb.gps_batt_low,
+            ratcs: hb.ratcs,
+            uat_initialized: hb.uat_initialized,
+            csa_requested: hb.csa_requested,
+            csa_not_available: hb.csa_not_available,
+            utc_ok: hb.utc_ok,
+            timestamp_s: hb.timestamp(),
+            message_counts: hb.message_counts,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<HeartbeatFields> for Heartbeat {
+    fn from(fields: HeartbeatFields) -> Self {
+        Self {
+            gps_pos_valid: fields.gps_pos_valid,
+            maint_reqd: fields.maint_reqd,
+            ident: fields.ident,
+            addr_type: fields.addr_type,
+            gps_batt_low: fields.gps_batt_low,
+            ratcs: fields.ratcs,
+            uat_initialized: fields.uat_initialized,
+            csa_requested: fields.csa_requested,
+            csa_not_available: fields.csa_not_available,
+            utc_ok: fields.utc_ok,
+            message_counts: fields.message_counts,
+            ..Default::default()
+        }
+        .with_timestamp(fields.timestamp_s)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,6 +205,8 @@ mod tests {
         assert_eq!(hb.timestamp(), 0x0_D0_DB);
 
         assert_eq!(hb.message_counts, 0x0802);
+        assert_eq!(hb.uplink_count(), 1);
+        assert_eq!(hb.basic_long_count(), 2);
     }
 
     #[test]
@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(bit_order = "msb", endian = "big")]
 /// # 3.7. Height Above Terrain
 pub struct HeightAboveTerrain {
@@ -11,6 +12,13 @@ pub struct HeightAboveTerrain {
         reader = "HeightAboveTerrain::hat_read(deku::reader)",
         writer = "HeightAboveTerrain::hat_write(deku::writer, self.height_above_terrain)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "height_above_terrain_ft",
+            with = "crate::util::serde_fields::option_feet"
+        )
+    )]
     pub height_above_terrain: Option<Length>,
 }
 
@@ -2,6 +2,7 @@ use crate::prelude::*;
 
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(bit_order = "msb", endian = "big")]
 /// # 3.2. Initialization Message
 pub struct Initialization {
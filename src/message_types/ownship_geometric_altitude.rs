@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(bit_order = "msb", endian = "big")]
 /// # 3.8. Ownship Geometric Altitude
 pub struct OwnshipGeometricAltitude {
@@ -9,12 +10,20 @@ pub struct OwnshipGeometricAltitude {
         reader = "OwnshipGeometricAltitude::oga_read(deku::reader)",
         writer = "OwnshipGeometricAltitude::oga_write(deku::writer, self.ownship_geo_altitude)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "ownship_geo_altitude_ft",
+            with = "crate::util::serde_fields::feet"
+        )
+    )]
     pub ownship_geo_altitude: Length,
 
     pub vertical_metrics: VerticalMetrics,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(ctx = "_: deku::ctx::Endian, _: deku::ctx::Order")]
 pub struct VerticalMetrics {
     #[deku(bits = 1)]
@@ -28,6 +37,10 @@ pub struct VerticalMetrics {
     ///
     /// `None` = unavailable
     /// `VerticalMetrics::VFOM_MAX` = 32766m or more
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "vfom_m", with = "crate::util::serde_fields::option_meters")
+    )]
     pub vfom: Option<Length>,
 }
 
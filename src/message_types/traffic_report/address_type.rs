@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, Builder)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(ctx = "_: deku::ctx::Endian, _: deku::ctx::Order")]
 /// # 3.5.1.2 Target Identity
 ///
@@ -18,7 +19,8 @@ pub struct TargetIdentity {
     pub participant_address: u32,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, EnumGet)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, DekuRead, DekuWrite, EnumGet)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(id_type = "u8", bits = 4)]
 #[repr(u8)]
 pub enum AddressType {
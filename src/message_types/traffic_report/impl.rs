@@ -121,7 +121,7 @@ pub(super) fn heading_write<W: std::io::Write + std::io::Seek>(
 }
 
 // 8 ASCII characters, '0' through '9' and 'A' through 'Z'.
-pub(super) fn callsign_read<R: std::io::Read + std::io::Seek>(
+pub(crate) fn callsign_read<R: std::io::Read + std::io::Seek>(
     reader: &mut deku::reader::Reader<R>,
 ) -> Result<String, DekuError> {
     let value = <[u8; 8]>::from_reader_with_ctx(reader, ())?;
@@ -130,7 +130,7 @@ pub(super) fn callsign_read<R: std::io::Read + std::io::Seek>(
         .map(|s| s.trim().to_string())
 }
 
-pub(super) fn callsign_write<W: std::io::Write + std::io::Seek>(
+pub(crate) fn callsign_write<W: std::io::Write + std::io::Seek>(
     writer: &mut Writer<W>,
     callsign: &str,
 ) -> Result<(), DekuError> {
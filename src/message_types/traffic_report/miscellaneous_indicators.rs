@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(ctx = "_: deku::ctx::Endian, _: deku::ctx::Order")]
 /// # 3.5.1.5 Miscellaneous Indicators
 pub struct MiscellaneousIndicators {
@@ -10,6 +11,7 @@ pub struct MiscellaneousIndicators {
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, EnumGet)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(id_type = "u8", bits = 1)]
 #[repr(u8)]
 pub enum AirGroundState {
@@ -19,6 +21,7 @@ pub enum AirGroundState {
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, EnumGet)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(id_type = "u8", bits = 1)]
 #[repr(u8)]
 pub enum ReportType {
@@ -28,6 +31,7 @@ pub enum ReportType {
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, EnumGet)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(id_type = "u8", bits = 2)]
 #[repr(u8)]
 pub enum TrackHeadingType {
@@ -6,7 +6,7 @@ pub mod nacp;
 pub mod nic;
 pub mod traffic_alert_status;
 
-mod r#impl;
+pub(crate) mod r#impl;
 
 pub use self::{
     address_type::*, emergency_priority_code::*, emitter_category::*, miscellaneous_indicators::*,
@@ -25,6 +25,7 @@ use crate::prelude::*;
 /// The GDL 90 will always output an Ownship Report message once per second. The message
 /// uses the same format as the Traffic Report, with the Message ID set to the value 10.
 #[derive(Debug, Default, Clone, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OwnshipMessage(pub TrafficReport);
 
 impl From<TrafficReport> for OwnshipMessage {
@@ -35,6 +36,7 @@ impl From<TrafficReport> for OwnshipMessage {
 
 /// # 3.5. Traffic Report Message
 #[derive(Debug, Default, Clone, PartialEq, DekuRead, DekuWrite)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TrafficMessage(pub TrafficReport);
 
 impl From<TrafficReport> for TrafficMessage {
@@ -67,6 +69,7 @@ impl From<&TrafficMessage> for TrafficReport {
 
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Default, Clone, PartialEq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(bit_order = "msb", endian = "big")]
 /// # 3.5.1 Traffic and Ownship Report Data Format
 pub struct TrafficReport {
@@ -84,6 +87,10 @@ pub struct TrafficReport {
         reader = "coord_read(deku::reader)",
         writer = "coord_write(deku::writer, self.latitude)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "latitude_deg", with = "crate::util::serde_fields::degrees")
+    )]
     pub latitude: Angle,
 
     /// `nn nn nn`
@@ -92,6 +99,10 @@ pub struct TrafficReport {
         reader = "coord_read(deku::reader)",
         writer = "coord_write(deku::writer, self.longitude)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "longitude_deg", with = "crate::util::serde_fields::degrees")
+    )]
     pub longitude: Angle,
 
     /// `ddd`
@@ -100,6 +111,13 @@ pub struct TrafficReport {
         reader = "altitude_read(deku::reader)",
         writer = "altitude_write(deku::writer, self.altitude)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "altitude_ft",
+            with = "crate::util::serde_fields::option_feet"
+        )
+    )]
     pub altitude: Option<Length>,
 
     /// `m`
@@ -122,6 +140,13 @@ pub struct TrafficReport {
         reader = "hv_read(deku::reader)",
         writer = "hv_write(deku::writer, self.horizontal_velocity)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "horizontal_velocity_kt",
+            with = "crate::util::serde_fields::option_knots"
+        )
+    )]
     pub horizontal_velocity: Option<Velocity>,
 
     /// `vvv`
@@ -132,6 +157,13 @@ pub struct TrafficReport {
         reader = "vv_read(deku::reader)",
         writer = "vv_write(deku::writer, self.vertical_velocity)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "vertical_velocity_fpm",
+            with = "crate::util::serde_fields::option_feet_per_minute"
+        )
+    )]
     pub vertical_velocity: Option<Velocity>,
 
     /// `tt`
@@ -141,6 +173,13 @@ pub struct TrafficReport {
         reader = "heading_read(deku::reader)",
         writer = "heading_write(deku::writer, self.track_heading)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "track_heading_deg",
+            with = "crate::util::serde_fields::degrees"
+        )
+    )]
     pub track_heading: Angle,
 
     /// `ee`
@@ -174,6 +213,12 @@ impl TrafficReport {
     pub fn traffic(self) -> TrafficMessage {
         self.into()
     }
+
+    /// `false` if latitude and longitude are both 0, which is sent for an invalid position
+    #[must_use]
+    pub fn has_position(&self) -> bool {
+        self.latitude.value != 0.0 || self.longitude.value != 0.0
+    }
 }
 
 #[cfg(test)]
@@ -332,4 +377,14 @@ mod tests {
             assert_eq_f!(tr_dec.track_heading, hdg, RES);
         }
     }
+
+    #[test]
+    fn has_position() {
+        assert!(!TrafficReport::default().has_position());
+        assert!(
+            TrafficReport::default()
+                .with_longitude(8.5.degrees())
+                .has_position()
+        );
+    }
 }
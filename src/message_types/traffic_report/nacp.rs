@@ -13,6 +13,7 @@ use crate::prelude::*;
     num_enum::FromPrimitive,
     num_enum::IntoPrimitive,
 )]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(
     ctx = "_: deku::ctx::Endian, _: deku::ctx::Order",
     id_type = "u8",
@@ -13,6 +13,7 @@ use crate::prelude::*;
     num_enum::FromPrimitive,
     num_enum::IntoPrimitive,
 )]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(
     ctx = "_: deku::ctx::Endian, _: deku::ctx::Order",
     id_type = "u8",
@@ -53,3 +54,35 @@ pub enum NIC {
     NIC11_HPL_7_5M_VPL_11M = 11,
     // 12..=15 => unused
 }
+
+impl NIC {
+    /// Horizontal containment radius (HPL)
+    #[must_use]
+    pub fn containment_radius(&self) -> Option<Length> {
+        match self {
+            NIC::NIC0_Unknown => None,
+            NIC::NIC1_20NM => Some(20.nautical_miles()),
+            NIC::NIC2_8NM => Some(8.nautical_miles()),
+            NIC::NIC3_4NM => Some(4.nautical_miles()),
+            NIC::NIC4_2NM => Some(2.nautical_miles()),
+            NIC::NIC5_1NM => Some(1.nautical_miles()),
+            NIC::NIC6_0_6NM => Some(0.6.nautical_miles()),
+            NIC::NIC7_0_2NM => Some(0.2.nautical_miles()),
+            NIC::NIC8_0_1NM => Some(0.1.nautical_miles()),
+            NIC::NIC9_HPL_75M_VPL_112M => Some(75.meters()),
+            NIC::NIC10_HPL_25M_VPL_37_5M => Some(25.meters()),
+            NIC::NIC11_HPL_7_5M_VPL_11M => Some(7.5.meters()),
+        }
+    }
+
+    /// Vertical protection limit (VPL)
+    #[must_use]
+    pub fn vertical_protection_limit(&self) -> Option<Length> {
+        match self {
+            NIC::NIC9_HPL_75M_VPL_112M => Some(112.meters()),
+            NIC::NIC10_HPL_25M_VPL_37_5M => Some(37.5.meters()),
+            NIC::NIC11_HPL_7_5M_VPL_11M => Some(11.meters()),
+            _ => None,
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, EnumGet)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(
     ctx = "_: deku::ctx::Endian, _: deku::ctx::Order",
     id_type = "u8",
@@ -5,6 +5,7 @@ use crate::prelude::*;
 const PAYLOAD_LEN: usize = 432;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, DekuRead, DekuWrite, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[deku(bit_order = "msb", endian = "big")]
 /// # 3.3. Uplink Data Message
 pub struct UplinkData {
@@ -15,10 +16,18 @@ pub struct UplinkData {
         reader = "UplinkData::tor_read(deku::reader)",
         writer = "UplinkData::tor_write(deku::writer, self.time_of_reception)"
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "time_of_reception_ns",
+            with = "crate::util::serde_fields::option_nanoseconds"
+        )
+    )]
     pub time_of_reception: Option<Duration>,
 
     /// 3.3.2. Uplink Payload
     #[builder(default = [0;432], setter = |data: impl AsRef<[u8]>| { self.uplink_payload[..data.as_ref().len().min(PAYLOAD_LEN)].copy_from_slice(&data.as_ref()[..data.as_ref().len().min(PAYLOAD_LEN)]); })]
+    #[cfg_attr(feature = "serde", serde(with = "crate::util::serde_fields::hex"))]
     pub uplink_payload: [u8; PAYLOAD_LEN],
 }
 
@@ -145,4 +154,23 @@ mod tests {
         let ud = UplinkData::default().with_uplink_payload([]);
         assert_eq!(ud.uplink_payload, [0; PAYLOAD_LEN]);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_fields() {
+        let ud = UplinkData::default()
+            .with_time_of_reception(Duration::from_micros(500))
+            .with_uplink_payload([0x7E, 0x01]);
+        let json = serde_json::to_value(ud).unwrap();
+
+        let fields = json.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(fields, ["time_of_reception_ns", "uplink_payload"]);
+        assert_eq!(json["time_of_reception_ns"], 500_000);
+        let payload = json["uplink_payload"].as_str().unwrap();
+        assert_eq!(payload.len(), PAYLOAD_LEN * 2);
+        assert!(payload.starts_with("7E0100"));
+
+        let json = serde_json::to_value(UplinkData::default()).unwrap();
+        assert!(json["time_of_reception_ns"].is_null());
+    }
 }
@@ -0,0 +1,202 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use tokio::task::JoinHandle;
+
+use crate::prelude::*;
+
+/// Default ATAK situational awareness multicast group, `239.2.3.1:6969`
+pub const COT_MULTICAST_GROUP: SocketAddr =
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(239, 2, 3, 1)), 6969);
+
+#[derive(Debug, Clone, Builder)]
+/// `CotOutput` settings, `CotOutputConfig::default()` sends to `COT_MULTICAST_GROUP`
+pub struct CotOutputConfig {
+    pub group: SocketAddr,
+
+    /// How long an event stays valid in the TAK client, see `CotEvent::stale`
+    pub stale_after: Duration,
+
+    /// Ownship altitudes older than this aren't used for `hae`
+    pub altitude_timeout: Duration,
+
+    pub sender: UdpSenderConfig,
+}
+
+impl Default for CotOutputConfig {
+    fn default() -> Self {
+        Self {
+            group: COT_MULTICAST_GROUP,
+            stale_after: Duration::from_secs(20),
+            altitude_timeout: Duration::from_secs(5),
+            sender: UdpSenderConfig::default(),
+        }
+    }
+}
+
+/// Sends traffic and ownship reports as Cursor-on-Target events, one event per datagram.
+///
+/// `hae` is the pressure altitude plus the difference between the last ownship geometric and
+/// pressure altitudes, see `CotEvent::from_report()`. It's unknown until both are received, and
+/// again when one of them is older than `altitude_timeout`. The multicast group is the initial target, more can be added through `targets()`,
+/// e.g. a TAK server's UDP input.
+///
+/// ```ignore
+/// let output = CotOutput::bind(CotOutputConfig::default()).await?;
+/// let task = output.spawn(receiver.spawn());
+/// ```
+#[derive(Debug)]
+pub struct CotOutput {
+    sender: UdpSender,
+    config: CotOutputConfig,
+    pressure_altitude: Option<(DateTime<Utc>, Length)>,
+    geometric_altitude: Option<(DateTime<Utc>, Length)>,
+}
+
+impl CotOutput {
+    /// # Errors
+    ///
+    /// See `UdpSender::bind()`.
+    pub async fn bind(config: CotOutputConfig) -> GDL90Result<Self> {
+        let sender = UdpSender::bind(config.sender.clone()).await?;
+        sender.targets().add(Target::Multicast(config.group));
+        Ok(Self {
+            sender,
+            config,
+            pressure_altitude: None,
+            geometric_altitude: None,
+        })
+    }
+
+    /// Shared target list, see `Targets`
+    #[must_use]
+    pub fn targets(&self) -> Targets {
+        self.sender.targets()
+    }
+
+    /// Send the event for a traffic or ownship report, keep the ownship altitudes.
+    ///
+    /// Returns the number of targets the event was sent to,
+    /// 0 for other messages and reports without a valid position.
+    pub async fn send(&mut self, message: &Message, time: DateTime<Utc>) -> usize {
+        let (report, ownship) = match message {
+            Message::Traffic(TrafficMessage(report)) => (report, false),
+            Message::Ownship(OwnshipMessage(report)) => {
+                self.pressure_altitude = report.altitude.map(|altitude| (time, altitude));
+                (report, true)
+            }
+            Message::OwnshipGeometricAltitude(altitude) => {
+                self.geometric_altitude = Some((time, altitude.ownship_geo_altitude));
+                return 0;
+            }
+            _ => return 0,
+        };
+        let Some(event) = CotEvent::from_report(
+            report,
+            ownship,
+            self.altitude_offset(time),
+            time,
+            self.config.stale_after,
+        ) else {
+            return 0;
+        };
+        self.sender.send(event.to_xml()).await
+    }
+
+    /// Ownship geometric minus pressure altitude, `None` if either is unknown or too old
+    fn altitude_offset(&self, time: DateTime<Utc>) -> Option<Length> {
+        let timeout = TimeDelta::from_std(self.config.altitude_timeout).unwrap_or(TimeDelta::MAX);
+        let current = |altitude: Option<(DateTime<Utc>, Length)>| {
+            altitude
+                .filter(|(received_at, _)| time.signed_duration_since(*received_at) <= timeout)
+                .map(|(_, altitude)| altitude)
+        };
+        Some(current(self.geometric_altitude)? - current(self.pressure_altitude)?)
+    }
+
+    /// Send the events for `messages` on the tokio runtime, until `messages` is closed
+    #[must_use]
+    pub fn spawn(mut self, mut messages: MessageStream) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(received) = messages.recv().await {
+                self.send(&received.message, received.received_at).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::UdpSocket;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn send_events() {
+        let receiver = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let config = CotOutputConfig::default().with_sender(UdpSenderConfig::loopback());
+        let mut output = CotOutput::bind(config).await.unwrap();
+        assert_eq!(
+            output.targets().list(),
+            [Target::Multicast(COT_MULTICAST_GROUP)]
+        );
+        output.targets().clear();
+        output
+            .targets()
+            .add(Target::Unicast(receiver.local_addr().unwrap()));
+
+        let report = TrafficReport::default()
+            .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, 0xAB4549))
+            .with_latitude(44.90708.degrees())
+            .with_longitude((-122.99488).degrees())
+            .with_altitude(5000.0.feet())
+            .with_emitter_category(EmitterCategory::Light);
+        let t0 = Utc::now();
+        let at = |s| t0 + TimeDelta::seconds(s);
+        assert_eq!(output.send(&Heartbeat::default().into(), at(0)).await, 0);
+        assert_eq!(
+            output.send(&report.clone().traffic().into(), at(0)).await,
+            1
+        );
+
+        let mut buf = [0; 1024];
+        let len = receiver.recv(&mut buf).await.unwrap();
+        let xml = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(
+            xml.contains(r#"uid="ICAO-AB4549" type="a-n-A-C-F""#),
+            "{xml}"
+        );
+        assert!(xml.contains(r#"hae="9999999.0""#), "{xml}");
+
+        // 5000 ft is 1524 m, so the offset is 76 m
+        let altitude =
+            OwnshipGeometricAltitude::default().with_ownship_geo_altitude(1600.0.meters());
+        assert_eq!(output.send(&altitude.into(), at(0)).await, 0);
+        assert_eq!(
+            output.send(&report.clone().ownship().into(), at(1)).await,
+            1
+        );
+        let len = receiver.recv(&mut buf).await.unwrap();
+        let xml = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(xml.contains(r#"type="a-f-A-C-F""#), "{xml}");
+        assert!(xml.contains(r#"hae="1600.0""#), "{xml}");
+
+        let traffic = report.with_altitude(4000.0.feet());
+        assert_eq!(
+            output.send(&traffic.clone().traffic().into(), at(2)).await,
+            1
+        );
+        let len = receiver.recv(&mut buf).await.unwrap();
+        let xml = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(xml.contains(r#"hae="1295.2""#), "{xml}");
+
+        // the geometric altitude is too old
+        assert_eq!(output.send(&traffic.traffic().into(), at(6)).await, 1);
+        let len = receiver.recv(&mut buf).await.unwrap();
+        let xml = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(xml.contains(r#"hae="9999999.0""#), "{xml}");
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::task::JoinHandle;
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Builder)]
+/// `DeviceEmulator` settings
+pub struct DeviceConfig {
+    /// `false` emulates a unit without CSA, `csa_not_available` is set whenever CSA is requested
+    pub csa_available: bool,
+
+    /// CDTI is considered failed if the display sends no Initialization for this long
+    pub cdti_timeout: Duration,
+}
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        Self {
+            csa_available: true,
+            cdti_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// What the display asked for in its Initialization messages
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceState {
+    pub audio_inhibit: bool,
+    pub cdti_ok: bool,
+    pub csa_audio_disable: bool,
+    pub csa_disable: bool,
+    /// Audio tests requested so far
+    pub audio_tests: u64,
+    pub last_initialization: Option<DateTime<Utc>>,
+}
+
+impl DeviceState {
+    /// Take over the display's settings, returns `true` if it asked for an audio test
+    pub fn apply(&mut self, init: &Initialization, at: DateTime<Utc>) -> bool {
+        self.audio_inhibit = init.audio_inhibit;
+        self.cdti_ok = init.cdti_ok;
+        self.csa_audio_disable = init.csa_audio_disable;
+        self.csa_disable = init.csa_disable;
+        self.last_initialization = Some(at);
+
+        if init.audio_test {
+            self.audio_tests += 1;
+        }
+        init.audio_test
+    }
+
+    /// Clear `cdti_ok` if the display went quiet
+    pub fn expire(&mut self, timeout: Duration, now: DateTime<Utc>) {
+        let timeout = chrono::Duration::from_std(timeout).unwrap_or(chrono::Duration::MAX);
+        if self
+            .last_initialization
+            .is_none_or(|last| now - last > timeout)
+        {
+            self.cdti_ok = false;
+        }
+    }
+
+    /// CSA runs while a CDTI is operating and hasn't disabled it
+    #[must_use]
+    pub fn csa_requested(&self) -> bool {
+        self.cdti_ok && !self.csa_disable
+    }
+
+    /// Whether traffic alerts are announced on the audio output
+    #[must_use]
+    pub fn traffic_audio(&self) -> bool {
+        !self.audio_inhibit && !self.csa_audio_disable
+    }
+
+    /// Set the CSA flags of `heartbeat`, CSA needs a valid GPS position
+    pub fn update_heartbeat(&self, heartbeat: &mut Heartbeat, csa_available: bool) {
+        heartbeat.csa_requested = self.csa_requested();
+        heartbeat.csa_not_available =
+            heartbeat.csa_requested && !(csa_available && heartbeat.gps_pos_valid);
+    }
+}
+
+/// Emulated GDL 90 that acts on the display's Initialization messages.
+///
+/// The resulting CSA flags end up in the Heartbeat of the `OutputServer`,
+/// the other flags are left to the application.
+/// Cloning is cheap, all clones share the same state.
+///
+/// ```ignore
+/// let server = OutputServer::new(sender, OutputConfig::default());
+/// let device = DeviceEmulator::new(server.clone(), DeviceConfig::default());
+/// let _output = server.spawn();
+/// let _device = device.spawn(from_display);
+///
+/// if device.state().cdti_ok { /* ... */ }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DeviceEmulator {
+    config: DeviceConfig,
+    server: OutputServer,
+    state: Arc<Mutex<DeviceState>>,
+}
+
+impl DeviceEmulator {
+    #[must_use]
+    pub fn new(server: OutputServer, config: DeviceConfig) -> Self {
+        let device = Self {
+            config,
+            server,
+            state: Arc::default(),
+        };
+        device.refresh(Utc::now());
+        device
+    }
+
+    fn lock(&self) -> MutexGuard<'_, DeviceState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Copy of the current state
+    #[must_use]
+    pub fn state(&self) -> DeviceState {
+        self.lock().clone()
+    }
+
+    /// Act on a message from the display, returns `true` if it asked for an audio test
+    pub fn handle(&self, received: &ReceivedMessage) -> bool {
+        let Message::Initialization(init) = &received.message else {
+            return false;
+        };
+        let audio_test = self.lock().apply(init, received.received_at);
+        self.refresh(received.received_at);
+        audio_test
+    }
+
+    /// Expire `cdti_ok` and update the Heartbeat flags
+    pub fn refresh(&self, now: DateTime<Utc>) {
+        let mut state = self.lock();
+        state.expire(self.config.cdti_timeout, now);
+        self.server.update_heartbeat(|heartbeat| {
+            state.update_heartbeat(heartbeat, self.config.csa_available)
+        });
+    }
+
+    /// Handle messages from the display on the tokio runtime.
+    ///
+    /// The task ends when the stream is closed.
+    #[must_use]
+    pub fn spawn(&self, mut from_display: MessageStream) -> JoinHandle<()> {
+        let device = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                tokio::select! {
+                    received = from_display.recv() => match received {
+                        Some(received) => {
+                            device.handle(&received);
+                        }
+                        None => return,
+                    },
+                    _ = interval.tick() => device.refresh(Utc::now()),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use super::*;
+
+    fn at(s: i64) -> DateTime<Utc> {
+        DateTime::UNIX_EPOCH + chrono::Duration::seconds(s)
+    }
+
+    fn from_display(init: Initialization, s: i64) -> ReceivedMessage {
+        ReceivedMessage {
+            message: init.into(),
+            source: Source::Udp(SocketAddr::from((Ipv4Addr::LOCALHOST, 4000))),
+            received_at: at(s),
+        }
+    }
+
+    #[test]
+    fn state_machine() {
+        let mut state = DeviceState::default();
+        assert!(!state.csa_requested());
+
+        let init = Initialization::default().with_cdti_ok().with_audio_test();
+        assert!(state.apply(&init, at(0)));
+        assert!(state.csa_requested());
+        assert!(state.traffic_audio());
+        assert_eq!(state.audio_tests, 1);
+
+        let init = Initialization::default()
+            .with_cdti_ok()
+            .with_csa_audio_disable();
+        assert!(!state.apply(&init, at(1)));
+        assert!(!state.traffic_audio());
+        assert_eq!(state.audio_tests, 1);
+
+        state.expire(Duration::from_secs(5), at(6));
+        assert!(state.cdti_ok);
+        state.expire(Duration::from_secs(5), at(7));
+        assert!(!state.cdti_ok);
+        assert!(!state.csa_requested());
+    }
+
+    #[tokio::test]
+    async fn heartbeat_flags() {
+        let sender = UdpSender::bind(UdpSenderConfig::loopback()).await.unwrap();
+        let server = OutputServer::new(sender, OutputConfig::default());
+        server.set_heartbeat(Heartbeat::default().with_uat_initialized());
+        let device = DeviceEmulator::new(server.clone(), DeviceConfig::default());
+
+        device.handle(&from_display(Initialization::default().with_cdti_ok(), 0));
+        let heartbeat = server.heartbeat();
+        assert!(heartbeat.uat_initialized);
+        assert!(heartbeat.csa_requested);
+        // no position yet
+        assert!(heartbeat.csa_not_available);
+
+        server.update_heartbeat(|heartbeat| heartbeat.gps_pos_valid = true);
+        device.refresh(at(1));
+        assert!(!server.heartbeat().csa_not_available);
+
+        let init = Initialization::default().with_cdti_ok().with_csa_disable();
+        device.handle(&from_display(init, 2));
+        assert!(!server.heartbeat().csa_requested);
+        assert!(device.state().csa_disable);
+
+        device.refresh(at(10));
+        assert!(!device.state().cdti_ok);
+    }
+}
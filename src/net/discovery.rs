@@ -0,0 +1,331 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::{net::UdpSocket, task::JoinHandle};
+
+use crate::prelude::*;
+
+/// EFBs broadcast `ForeFlightBroadcast` JSON to this port
+pub const FOREFLIGHT_BROADCAST_PORT: u16 = 63093;
+
+/// An EFB announcing itself with `ForeFlightBroadcast`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Efb {
+    pub app: String,
+    pub ip: IpAddr,
+    /// `GDL90.port` of the broadcast
+    pub gdl90_port: u16,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub broadcasts: u64,
+}
+
+impl Efb {
+    /// Where the EFB wants its GDL90
+    #[must_use]
+    pub fn target(&self) -> Target {
+        Target::Unicast(SocketAddr::new(self.ip, self.gdl90_port))
+    }
+}
+
+/// Live set of EFBs, keyed by their GDL90 target.
+///
+/// Cloning is cheap, all clones see the same EFBs.
+/// If `Targets` are attached, EFBs are added to and removed from them as they come and go.
+#[derive(Debug, Clone, Default)]
+pub struct EfbList {
+    efbs: Arc<Mutex<HashMap<Target, Efb>>>,
+    targets: Option<Targets>,
+}
+
+impl EfbList {
+    fn lock(&self) -> MutexGuard<'_, HashMap<Target, Efb>> {
+        self.efbs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Keep `targets` in sync with the EFB list, usually `UdpSender::targets()`
+    #[must_use]
+    pub fn with_targets(mut self, targets: Targets) -> Self {
+        for target in self.lock().keys() {
+            targets.add(*target);
+        }
+        self.targets = Some(targets);
+        self
+    }
+
+    /// Sorted by IP and port
+    #[must_use]
+    pub fn list(&self) -> Vec<Efb> {
+        let mut efbs = self.lock().values().cloned().collect::<Vec<_>>();
+        efbs.sort_by_key(Efb::target);
+        efbs
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Add or refresh the EFB that sent `broadcast` from `ip`, returns its updated entry.
+    ///
+    /// `Efb::broadcasts` is 1 if it's new.
+    pub fn update(&self, ip: IpAddr, broadcast: ForeFlightBroadcast, now: DateTime<Utc>) -> Efb {
+        let target = Target::Unicast(SocketAddr::new(ip, broadcast.gdl90.port));
+        let mut efbs = self.lock();
+
+        let efb = efbs
+            .entry(target)
+            .and_modify(|efb| {
+                efb.last_seen = now;
+                efb.broadcasts += 1;
+            })
+            .or_insert_with(|| Efb {
+                app: broadcast.app.clone(),
+                ip,
+                gdl90_port: broadcast.gdl90.port,
+                first_seen: now,
+                last_seen: now,
+                broadcasts: 0,
+            });
+        efb.app = broadcast.app;
+
+        if efb.broadcasts == 0 {
+            efb.broadcasts = 1;
+            if let Some(targets) = &self.targets {
+                targets.add(target);
+            }
+        }
+        efb.clone()
+    }
+
+    /// Remove EFBs not seen for longer than `timeout`, returns them
+    pub fn expire(&self, timeout: Duration, now: DateTime<Utc>) -> Vec<Efb> {
+        let timeout = chrono::Duration::from_std(timeout).unwrap_or(chrono::Duration::MAX);
+        let mut efbs = self.lock();
+
+        let expired = efbs
+            .iter()
+            .filter(|(_, efb)| now - efb.last_seen > timeout)
+            .map(|(target, _)| *target)
+            .collect::<Vec<_>>();
+
+        expired
+            .into_iter()
+            .filter_map(|target| {
+                if let Some(targets) = &self.targets {
+                    targets.remove(&target);
+                }
+                efbs.remove(&target)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Builder)]
+/// `Discovery` settings
+pub struct DiscoveryConfig {
+    pub bind_addr: SocketAddr,
+
+    /// EFBs are removed when they haven't broadcast for this long.
+    /// ForeFlight broadcasts every 5 seconds.
+    pub timeout: Duration,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from((Ipv4Addr::UNSPECIFIED, FOREFLIGHT_BROADCAST_PORT)),
+            timeout: Duration::from_secs(15),
+        }
+    }
+}
+
+/// Listens for `ForeFlightBroadcast`s and keeps an `EfbList` up to date.
+///
+/// ```ignore
+/// let sender = UdpSender::bind(UdpSenderConfig::default()).await?;
+/// let discovery = Discovery::bind(DiscoveryConfig::default())
+///     .await?
+///     .with_targets(sender.targets());
+/// let efbs = discovery.efbs();
+/// let _task = discovery.spawn();
+///
+/// // every EFB on the network now gets what `sender` sends
+/// ```
+#[derive(Debug)]
+pub struct Discovery {
+    socket: UdpSocket,
+    config: DiscoveryConfig,
+    efbs: EfbList,
+    /// Broadcasts that weren't valid JSON
+    invalid: u64,
+}
+
+impl Discovery {
+    /// # Errors
+    ///
+    /// If the socket can't be bound.
+    pub async fn bind(config: DiscoveryConfig) -> GDL90Result<Self> {
+        let socket = UdpSocket::bind(config.bind_addr).await?;
+        socket.set_broadcast(true)?;
+
+        Ok(Self {
+            socket,
+            config,
+            efbs: EfbList::default(),
+            invalid: 0,
+        })
+    }
+
+    /// Feed discovered EFBs into `targets`, see `EfbList::with_targets()`
+    #[must_use]
+    pub fn with_targets(mut self, targets: Targets) -> Self {
+        self.efbs = self.efbs.with_targets(targets);
+        self
+    }
+
+    /// # Errors
+    ///
+    /// If the socket has no local address.
+    pub fn local_addr(&self) -> GDL90Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    #[must_use]
+    pub fn efbs(&self) -> EfbList {
+        self.efbs.clone()
+    }
+
+    /// Broadcasts that couldn't be parsed
+    #[must_use]
+    pub fn invalid_broadcasts(&self) -> u64 {
+        self.invalid
+    }
+
+    /// Wait for the next broadcast and update the EFB list.
+    ///
+    /// # Errors
+    ///
+    /// If receiving failed or the broadcast isn't valid `ForeFlightBroadcast` JSON.
+    pub async fn recv(&mut self) -> GDL90Result<Efb> {
+        let mut buf = [0; 1024];
+        let (len, addr) = self.socket.recv_from(&mut buf).await?;
+
+        let broadcast = ForeFlightBroadcast::from_json(&buf[..len]).map_err(|err| {
+            self.invalid += 1;
+            GDL90Error::InvalidBroadcast(err.to_string())
+        })?;
+        Ok(self.efbs.update(addr.ip(), broadcast, Utc::now()))
+    }
+
+    /// Receive broadcasts and expire EFBs on the tokio runtime until the task is aborted
+    #[must_use]
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        let efbs = self.efbs();
+        let timeout = self.config.timeout;
+
+        tokio::spawn(async move {
+            let mut expiry = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                tokio::select! {
+                    _ = expiry.tick() => {
+                        efbs.expire(timeout, Utc::now());
+                    }
+                    _ = self.recv() => {}
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn broadcast(app: &str, port: u16) -> ForeFlightBroadcast {
+        ForeFlightBroadcast {
+            app: app.into(),
+            gdl90: Port { port },
+        }
+    }
+
+    #[test]
+    fn efbs_feed_targets() {
+        let targets = Targets::default();
+        let efbs = EfbList::default().with_targets(targets.clone());
+        let ip = IpAddr::from(Ipv4Addr::new(192, 168, 1, 20));
+        let t0 = Utc::now();
+
+        assert_eq!(
+            efbs.update(ip, broadcast("ForeFlight", 4000), t0)
+                .broadcasts,
+            1
+        );
+        assert_eq!(
+            efbs.update(ip, broadcast("ForeFlight", 4000), t0)
+                .broadcasts,
+            2
+        );
+        let later = t0 + chrono::Duration::seconds(10);
+        assert_eq!(
+            efbs.update(ip, broadcast("Other", 4001), later).broadcasts,
+            1
+        );
+
+        let list = efbs.list();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].broadcasts, 2);
+        assert_eq!(list[1].app, "Other");
+        assert_eq!(
+            targets.list(),
+            vec![
+                Target::Unicast(SocketAddr::new(ip, 4000)),
+                Target::Unicast(SocketAddr::new(ip, 4001)),
+            ]
+        );
+
+        let expired = efbs.expire(Duration::from_secs(15), t0 + chrono::Duration::seconds(20));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].gdl90_port, 4000);
+        assert_eq!(
+            targets.list(),
+            vec![Target::Unicast(SocketAddr::new(ip, 4001))]
+        );
+    }
+
+    #[tokio::test]
+    async fn receive_broadcast() {
+        let config =
+            DiscoveryConfig::default().with_bind_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)));
+        let mut discovery = Discovery::bind(config).await.unwrap();
+        let addr = discovery.local_addr().unwrap();
+
+        let efb = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        efb.send_to(b"not json", addr).await.unwrap();
+        efb.send_to(br#"{"App":"ForeFlight","GDL90":{"port":4000}}"#, addr)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            discovery.recv().await,
+            Err(GDL90Error::InvalidBroadcast(_))
+        ));
+        assert_eq!(discovery.invalid_broadcasts(), 1);
+
+        let found = discovery.recv().await.unwrap();
+        assert_eq!(found.app, "ForeFlight");
+        assert_eq!(found.ip, IpAddr::from(Ipv4Addr::LOCALHOST));
+        assert_eq!(found.gdl90_port, 4000);
+        assert_eq!(discovery.efbs().len(), 1);
+    }
+}
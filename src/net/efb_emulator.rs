@@ -0,0 +1,391 @@
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::{net::UdpSocket, task::JoinHandle};
+
+use crate::prelude::*;
+
+/// What arrived of one message type, see `Message::type_name()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeObservation {
+    pub count: u64,
+    pub first: DateTime<Utc>,
+    pub last: DateTime<Utc>,
+    /// Longest time between two consecutive messages, `None` until the second one arrives
+    pub max_gap: Option<chrono::Duration>,
+}
+
+impl TypeObservation {
+    /// Average messages per second between the first and the last one
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn rate(&self) -> Option<f64> {
+        let span = (self.last - self.first).as_seconds_f64();
+        (self.count > 1 && span > 0.).then(|| (self.count - 1) as f64 / span)
+    }
+}
+
+/// Something the GDL90 source does that an EFB doesn't expect
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ConformanceIssue {
+    #[error("no {0} received")]
+    Missing(&'static str),
+
+    #[error("{message} gap of {gap}, expected at most {expected:?}")]
+    Gap {
+        message: &'static str,
+        gap: chrono::Duration,
+        expected: Duration,
+    },
+
+    #[error("last {message} was {since} ago")]
+    Stale {
+        message: &'static str,
+        since: chrono::Duration,
+    },
+}
+
+#[derive(Debug, Clone, Builder)]
+/// What `Observations::check()` expects from a GDL90 source
+pub struct ConformanceRules {
+    /// Types that must have been received at least once
+    pub required: Vec<&'static str>,
+
+    /// Longest accepted time between two Heartbeats, the ICD asks for one every second
+    pub heartbeat_max_gap: Duration,
+}
+
+impl Default for ConformanceRules {
+    fn default() -> Self {
+        Self {
+            required: vec!["Heartbeat", "ForeFlightID"],
+            heartbeat_max_gap: Duration::from_millis(1200),
+        }
+    }
+}
+
+/// Message types seen by an `EfbEmulator`, shared so they can be inspected while it runs.
+///
+/// Cloning is cheap, all clones see the same observations.
+#[derive(Debug, Clone, Default)]
+pub struct Observations(Arc<Mutex<BTreeMap<&'static str, TypeObservation>>>);
+
+impl Observations {
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<&'static str, TypeObservation>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn observe(&self, message: &Message, at: DateTime<Utc>) {
+        self.lock()
+            .entry(message.type_name())
+            .and_modify(|o| {
+                let gap = at - o.last;
+                o.max_gap = Some(o.max_gap.map_or(gap, |max| max.max(gap)));
+                o.count += 1;
+                o.last = at;
+            })
+            .or_insert(TypeObservation {
+                count: 1,
+                first: at,
+                last: at,
+                max_gap: None,
+            });
+    }
+
+    #[must_use]
+    pub fn get(&self, type_name: &str) -> Option<TypeObservation> {
+        self.lock().get(type_name).cloned()
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> BTreeMap<&'static str, TypeObservation> {
+        self.lock().clone()
+    }
+
+    pub fn reset(&self) {
+        self.lock().clear();
+    }
+
+    /// Check what was received so far against `rules`, empty if everything conforms
+    #[must_use]
+    pub fn check(&self, rules: &ConformanceRules, now: DateTime<Utc>) -> Vec<ConformanceIssue> {
+        let observations = self.lock();
+        let mut issues = rules
+            .required
+            .iter()
+            .copied()
+            .filter(|name| !observations.contains_key(name))
+            .map(ConformanceIssue::Missing)
+            .collect::<Vec<_>>();
+
+        if let Some(heartbeat) = observations.get("Heartbeat") {
+            let expected = rules.heartbeat_max_gap;
+            let max = chrono::Duration::from_std(expected).unwrap_or(chrono::Duration::MAX);
+
+            if let Some(gap) = heartbeat.max_gap.filter(|gap| *gap > max) {
+                issues.push(ConformanceIssue::Gap {
+                    message: "Heartbeat",
+                    gap,
+                    expected,
+                });
+            }
+            if now - heartbeat.last > max {
+                issues.push(ConformanceIssue::Stale {
+                    message: "Heartbeat",
+                    since: now - heartbeat.last,
+                });
+            }
+        }
+
+        issues
+    }
+}
+
+#[derive(Debug, Clone, Builder)]
+/// `EfbEmulator` settings, the defaults stay on localhost
+pub struct EfbEmulatorConfig {
+    /// `App` of the broadcast
+    pub app: String,
+
+    /// Where GDL90 is received, port 0 picks a free one.
+    /// The actual port is what gets announced.
+    pub gdl90_addr: SocketAddr,
+
+    /// Where the `ForeFlightBroadcast` is sent, e.g. `255.255.255.255:63093` on a real network
+    pub announce_addr: SocketAddr,
+
+    pub announce_interval: Duration,
+}
+
+impl Default for EfbEmulatorConfig {
+    fn default() -> Self {
+        Self {
+            app: "EFB Emulator".into(),
+            gdl90_addr: SocketAddr::from((Ipv4Addr::LOCALHOST, GDL90_PORT)),
+            announce_addr: SocketAddr::from((Ipv4Addr::LOCALHOST, FOREFLIGHT_BROADCAST_PORT)),
+            announce_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// The EFB half of the protocol, to test GDL90 sources without a tablet.
+///
+/// Announces itself like ForeFlight does, receives GDL90 on the announced port
+/// and records which message types arrive and how often.
+///
+/// ```ignore
+/// let emulator = EfbEmulator::bind(EfbEmulatorConfig::default()).await?;
+/// let observations = emulator.observations();
+/// let _task = emulator.spawn()?;
+///
+/// tokio::time::sleep(Duration::from_secs(10)).await;
+/// assert!(observations.check(&ConformanceRules::default(), Utc::now()).is_empty());
+/// ```
+#[derive(Debug)]
+pub struct EfbEmulator {
+    announcer: UdpSocket,
+    receiver: UdpReceiver,
+    config: EfbEmulatorConfig,
+    observations: Observations,
+}
+
+impl EfbEmulator {
+    /// # Errors
+    ///
+    /// If a socket can't be bound.
+    pub async fn bind(config: EfbEmulatorConfig) -> GDL90Result<Self> {
+        let receiver_config = UdpReceiverConfig::default().with_bind_addr(config.gdl90_addr);
+        let receiver = UdpReceiver::bind(receiver_config).await?;
+
+        let ip = match config.gdl90_addr.ip() {
+            IpAddr::V4(_) => IpAddr::from(Ipv4Addr::UNSPECIFIED),
+            ip @ IpAddr::V6(_) => ip,
+        };
+        let announcer = UdpSocket::bind((ip, 0)).await?;
+        announcer.set_broadcast(true)?;
+
+        Ok(Self {
+            announcer,
+            receiver,
+            config,
+            observations: Observations::default(),
+        })
+    }
+
+    /// Where GDL90 is received
+    ///
+    /// # Errors
+    ///
+    /// If the socket has no local address.
+    pub fn gdl90_addr(&self) -> GDL90Result<SocketAddr> {
+        self.receiver.local_addr()
+    }
+
+    #[must_use]
+    pub fn observations(&self) -> Observations {
+        self.observations.clone()
+    }
+
+    /// Packets and decode errors per source
+    #[must_use]
+    pub fn statistics(&self) -> Statistics {
+        self.receiver.statistics()
+    }
+
+    /// The broadcast announcing the actual GDL90 port
+    ///
+    /// # Errors
+    ///
+    /// If the socket has no local address.
+    pub fn broadcast(&self) -> GDL90Result<ForeFlightBroadcast> {
+        Ok(ForeFlightBroadcast {
+            app: self.config.app.clone(),
+            gdl90: Port {
+                port: self.gdl90_addr()?.port(),
+            },
+        })
+    }
+
+    /// Send the broadcast once
+    ///
+    /// # Errors
+    ///
+    /// If the broadcast can't be serialized or sent.
+    pub async fn announce(&self) -> GDL90Result<()> {
+        let json = self
+            .broadcast()?
+            .to_json()
+            .map_err(|err| GDL90Error::InvalidBroadcast(err.to_string()))?;
+        self.announcer
+            .send_to(json.as_bytes(), self.config.announce_addr)
+            .await?;
+        Ok(())
+    }
+
+    /// Announce and receive on the tokio runtime until the task is aborted
+    ///
+    /// # Errors
+    ///
+    /// If the broadcast can't be serialized.
+    pub fn spawn(self) -> GDL90Result<JoinHandle<()>> {
+        let json = self
+            .broadcast()?
+            .to_json()
+            .map_err(|err| GDL90Error::InvalidBroadcast(err.to_string()))?;
+        let Self {
+            announcer,
+            mut receiver,
+            config,
+            observations,
+        } = self;
+
+        Ok(tokio::spawn(async move {
+            let mut announce = tokio::time::interval(config.announce_interval);
+            loop {
+                tokio::select! {
+                    _ = announce.tick() => {
+                        // not being heard is exactly what this is supposed to find out
+                        let _ = announcer.send_to(json.as_bytes(), config.announce_addr).await;
+                    }
+                    received = receiver.recv() => {
+                        for received in received.unwrap_or_default() {
+                            observations.observe(&received.message, received.received_at);
+                        }
+                    }
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conformance() {
+        let observations = Observations::default();
+        let rules = ConformanceRules::default();
+        let t0 = Utc::now();
+        let heartbeat = Message::from(Heartbeat::default());
+
+        assert_eq!(
+            observations.check(&rules, t0),
+            vec![
+                ConformanceIssue::Missing("Heartbeat"),
+                ConformanceIssue::Missing("ForeFlightID")
+            ]
+        );
+
+        observations.observe(&ForeFlightID::default().into(), t0);
+        for s in 0..5 {
+            observations.observe(&heartbeat, t0 + chrono::Duration::seconds(s));
+        }
+        let now = t0 + chrono::Duration::seconds(4);
+        assert!(observations.check(&rules, now).is_empty());
+
+        let heartbeats = observations.get("Heartbeat").unwrap();
+        assert_eq!(heartbeats.count, 5);
+        assert_eq!(heartbeats.rate(), Some(1.));
+        assert_eq!(observations.get("ForeFlightID").unwrap().rate(), None);
+
+        let late = t0 + chrono::Duration::seconds(7);
+        observations.observe(&heartbeat, late);
+        assert_eq!(
+            observations.check(&rules, late + chrono::Duration::seconds(2)),
+            vec![
+                ConformanceIssue::Gap {
+                    message: "Heartbeat",
+                    gap: chrono::Duration::seconds(3),
+                    expected: rules.heartbeat_max_gap,
+                },
+                ConformanceIssue::Stale {
+                    message: "Heartbeat",
+                    since: chrono::Duration::seconds(2),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn discovered_and_served() {
+        let discovery_config =
+            DiscoveryConfig::default().with_bind_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)));
+        let mut discovery = Discovery::bind(discovery_config).await.unwrap();
+
+        let config = EfbEmulatorConfig::default()
+            .with_gdl90_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .with_announce_addr(discovery.local_addr().unwrap());
+        let emulator = EfbEmulator::bind(config).await.unwrap();
+        let gdl90_addr = emulator.gdl90_addr().unwrap();
+        let observations = emulator.observations();
+        let _task = emulator.spawn().unwrap();
+
+        let efb = discovery.recv().await.unwrap();
+        assert_eq!(efb.app, "EFB Emulator");
+        assert_eq!(efb.target(), Target::Unicast(gdl90_addr));
+
+        let sender = UdpSender::bind(UdpSenderConfig::loopback()).await.unwrap();
+        sender.targets().add(efb.target());
+
+        let messages: [Message; 2] = [Heartbeat::default().into(), ForeFlightID::default().into()];
+        sender.send_messages(messages).await.unwrap();
+
+        for _ in 0..100 {
+            if observations.get("ForeFlightID").is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(
+            observations
+                .check(&ConformanceRules::default(), Utc::now())
+                .is_empty()
+        );
+    }
+}
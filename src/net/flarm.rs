@@ -0,0 +1,264 @@
+use std::{collections::HashMap, time::Duration};
+
+use chrono::{DateTime, Utc};
+use tokio::{task::JoinHandle, time::Instant};
+
+use crate::prelude::*;
+
+/// Port most FLARM Wi-Fi bridges serve NMEA on, over TCP and UDP
+pub const FLARM_PORT: u16 = 2000;
+
+/// Where a `FlarmOutput` writes its sentences
+#[derive(Debug)]
+pub enum FlarmTransport {
+    /// Datagrams of at most the sender's `max_packet_size`, split between sentences
+    Udp(UdpSender),
+    /// All clients of a `TcpServer`
+    Tcp(TcpOutput),
+}
+
+impl FlarmTransport {
+    /// Returns the number of targets or clients the sentences were sent to
+    pub async fn send(&self, sentences: &[String]) -> usize {
+        match self {
+            Self::Udp(sender) => {
+                let mut packets: Vec<String> = vec![];
+                for sentence in sentences {
+                    match packets.last_mut() {
+                        Some(packet)
+                            if packet.len() + sentence.len() <= sender.config().max_packet_size =>
+                        {
+                            packet.push_str(sentence);
+                        }
+                        _ => packets.push(sentence.clone()),
+                    }
+                }
+
+                let mut sent = 0;
+                for packet in packets {
+                    sent = sender.send(packet).await;
+                }
+                sent
+            }
+            Self::Tcp(output) => output.send(sentences.concat()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Builder)]
+/// `FlarmOutput` settings
+pub struct FlarmOutputConfig {
+    /// FLARM devices send once a second
+    pub interval: Duration,
+
+    /// Ownship and traffic not updated for this long are dropped
+    pub traffic_timeout: Duration,
+}
+
+impl Default for FlarmOutputConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            traffic_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Converts ownship and traffic reports to FLARM NMEA, for gliding instruments that don't read GDL90.
+///
+/// Every `interval` it sends `$GPRMC`, `$GPGGA`, `$PFLAU` and a `$PFLAA` per target.
+/// Without a current ownship report only `$PFLAU` is sent, with no GPS fix.
+///
+/// ```ignore
+/// let server = TcpServer::bind(TcpServerConfig::default().with_bind_addr(([0, 0, 0, 0], FLARM_PORT).into())).await?;
+/// let output = FlarmOutput::new(FlarmTransport::Tcp(server.output()), FlarmOutputConfig::default());
+/// let (_from_clients, _server) = server.spawn();
+/// let _task = output.spawn(receiver.spawn());
+/// ```
+#[derive(Debug)]
+pub struct FlarmOutput {
+    transport: FlarmTransport,
+    config: FlarmOutputConfig,
+    ownship: Option<(Instant, TrafficReport)>,
+    geometric_altitude: Option<Length>,
+    traffic: HashMap<TargetIdentity, (Instant, TrafficReport)>,
+}
+
+impl FlarmOutput {
+    #[must_use]
+    pub fn new(transport: FlarmTransport, config: FlarmOutputConfig) -> Self {
+        Self {
+            transport,
+            config,
+            ownship: None,
+            geometric_altitude: None,
+            traffic: HashMap::new(),
+        }
+    }
+
+    /// Keep ownship, ownship geometric altitude and traffic reports, other messages are ignored
+    pub fn update(&mut self, message: &Message, now: Instant) {
+        match message {
+            Message::Ownship(OwnshipMessage(report)) => self.ownship = Some((now, report.clone())),
+            Message::OwnshipGeometricAltitude(altitude) => {
+                self.geometric_altitude = Some(altitude.ownship_geo_altitude);
+            }
+            Message::Traffic(TrafficMessage(report)) => {
+                self.traffic
+                    .insert(report.target_identity, (now, report.clone()));
+            }
+            _ => {}
+        }
+    }
+
+    /// Drop expired ownship and traffic and build the sentences for `time`
+    pub fn sentences(&mut self, now: Instant, time: DateTime<Utc>) -> Vec<String> {
+        let timeout = self.config.traffic_timeout;
+        self.traffic
+            .retain(|_, (updated, _)| now.duration_since(*updated) <= timeout);
+        if self
+            .ownship
+            .as_ref()
+            .is_some_and(|(updated, _)| now.duration_since(*updated) > timeout)
+        {
+            self.ownship = None;
+            self.geometric_altitude = None;
+        }
+
+        let Some((_, ownship)) = &self.ownship else {
+            return vec![Pflau::from_targets(None, &[]).to_sentence()];
+        };
+
+        let mut targets = self
+            .traffic
+            .values()
+            // the ownship address may also show up as traffic
+            .filter(|(_, report)| report.target_identity != ownship.target_identity)
+            .filter_map(|(_, report)| Pflaa::from_reports(ownship, report))
+            .collect::<Vec<_>>();
+        targets.sort_by(|a, b| a.distance().value.total_cmp(&b.distance().value));
+
+        let mut sentences = vec![
+            gprmc(ownship, time),
+            gpgga(ownship, self.geometric_altitude, time),
+            Pflau::from_targets(Some(ownship), &targets).to_sentence(),
+        ];
+        sentences.extend(targets.iter().map(Pflaa::to_sentence));
+        sentences
+    }
+
+    /// Update from `messages` and send every `interval` on the tokio runtime, until `messages` is closed
+    #[must_use]
+    pub fn spawn(mut self, mut messages: MessageStream) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.config.interval);
+            loop {
+                tokio::select! {
+                    received = messages.recv() => match received {
+                        Some(received) => self.update(&received.message, Instant::now()),
+                        None => return,
+                    },
+                    _ = interval.tick() => {
+                        let sentences = self.sentences(Instant::now(), Utc::now());
+                        self.transport.send(&sentences).await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use tokio::{net::UdpSocket, sync::mpsc};
+
+    use super::*;
+
+    fn report(address: u32, latitude: f64) -> TrafficReport {
+        TrafficReport::default()
+            .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, address))
+            .with_latitude(latitude.degrees())
+            .with_longitude((-122.0).degrees())
+            .with_altitude(3000.0.feet())
+    }
+
+    async fn udp_output(receiver: &UdpSocket, config: FlarmOutputConfig) -> FlarmOutput {
+        let sender = UdpSender::bind(UdpSenderConfig::loopback()).await.unwrap();
+        sender
+            .targets()
+            .add(Target::Unicast(receiver.local_addr().unwrap()));
+        FlarmOutput::new(FlarmTransport::Udp(sender), config)
+    }
+
+    #[tokio::test]
+    async fn sentences() {
+        let receiver = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let mut output = udp_output(&receiver, FlarmOutputConfig::default()).await;
+        let (t0, time) = (Instant::now(), Utc::now());
+
+        output.update(&report(2, 45.02).traffic().into(), t0);
+        assert_eq!(
+            output.sentences(t0, time),
+            [nmea_sentence("PFLAU,0,1,0,1,0,,0,,,")]
+        );
+
+        output.update(&report(1, 45.0).traffic().into(), t0);
+        output.update(
+            &report(1, 45.0).ownship().into(),
+            t0 + Duration::from_secs(5),
+        );
+        output.update(
+            &report(3, 45.01).traffic().into(),
+            t0 + Duration::from_secs(5),
+        );
+        let sentences = output.sentences(t0 + Duration::from_secs(5), time);
+        assert_eq!(sentences.len(), 5);
+        assert!(sentences[0].starts_with("$GPRMC,"));
+        assert!(sentences[1].starts_with("$GPGGA,"));
+        assert!(sentences[2].starts_with("$PFLAU,2,1,1,1,0,0,0,0,1112,000003*"));
+        assert!(sentences[3].starts_with("$PFLAA,0,1112,0,0,1,000003,"));
+        assert!(sentences[4].starts_with("$PFLAA,0,2224,0,0,1,000002,"));
+
+        // target 2 expired
+        let sentences = output.sentences(t0 + Duration::from_secs(11), time);
+        assert_eq!(sentences.len(), 4);
+
+        assert_eq!(output.transport.send(&sentences).await, 1);
+        let mut buf = [0; 1500];
+        let len = receiver.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], sentences.concat().as_bytes());
+
+        // ownship expired, back to no fix
+        assert_eq!(
+            output.sentences(t0 + Duration::from_secs(16), time),
+            [nmea_sentence("PFLAU,0,1,0,1,0,,0,,,")]
+        );
+    }
+
+    #[tokio::test]
+    async fn spawn() {
+        let receiver = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let config = FlarmOutputConfig::default().with_interval(Duration::from_millis(20));
+        let output = udp_output(&receiver, config).await;
+
+        let (tx, rx) = mpsc::channel(16);
+        let _task = output.spawn(rx);
+        tx.send(ReceivedMessage {
+            message: report(1, 45.0).ownship().into(),
+            source: Source::Serial("test".into()),
+            received_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+
+        let mut buf = [0; 1500];
+        loop {
+            let len = receiver.recv(&mut buf).await.unwrap();
+            if buf[..len].starts_with(b"$GPRMC,") {
+                break;
+            }
+        }
+    }
+}
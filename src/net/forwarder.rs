@@ -0,0 +1,512 @@
+use std::{
+    borrow::Cow,
+    net::{Ipv4Addr, SocketAddr},
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use tokio::{net::UdpSocket, task::JoinHandle};
+
+use crate::prelude::*;
+
+/// What a `Filter` does with a message
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Drop,
+    /// Forward this instead, it is re-encoded
+    Replace(Message),
+}
+
+/// A step of a `Destination`'s filter chain.
+///
+/// Closures `FnMut(&Message) -> Verdict` are filters too.
+pub trait Filter: Send {
+    /// Called with the message as left by the previous filters
+    fn filter(&mut self, message: &Message) -> Verdict;
+
+    /// Called for frames that didn't decode, `true` forwards them as they are
+    fn filter_undecoded(&mut self, frame: &[u8]) -> bool {
+        let _ = frame;
+        true
+    }
+}
+
+impl<F: FnMut(&Message) -> Verdict + Send> Filter for F {
+    fn filter(&mut self, message: &Message) -> Verdict {
+        self(message)
+    }
+}
+
+/// Drops all messages with one of the message IDs, including frames that don't decode
+#[derive(Debug, Clone)]
+pub struct DropIds(pub Vec<u8>);
+
+impl Filter for DropIds {
+    fn filter(&mut self, message: &Message) -> Verdict {
+        if self.0.contains(&message.message_id()) {
+            Verdict::Drop
+        } else {
+            Verdict::Pass
+        }
+    }
+
+    fn filter_undecoded(&mut self, frame: &[u8]) -> bool {
+        frame_message_id(frame).is_none_or(|id| !self.0.contains(&id))
+    }
+}
+
+/// Drops traffic farther than `max_distance` from ownship.
+///
+/// The ownship position is taken from the ownship reports passing this filter, so put it before
+/// filters that drop them. Traffic passes as long as the ownship position is unknown or older
+/// than the timeout, 5 s by default. Reports without a position, see
+/// `TrafficReport::has_position()`, neither move ownship nor get dropped.
+#[derive(Debug, Clone)]
+pub struct TrafficRange {
+    max_distance: Length,
+    timeout: Duration,
+    ownship: Option<(Instant, Angle, Angle)>,
+}
+
+impl TrafficRange {
+    #[must_use]
+    pub fn new(max_distance: Length) -> Self {
+        Self {
+            max_distance,
+            timeout: Duration::from_secs(5),
+            ownship: None,
+        }
+    }
+
+    /// How long an ownship position is used after its last update
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Last known ownship latitude and longitude, `None` once it timed out
+    #[must_use]
+    pub fn ownship(&self) -> Option<(Angle, Angle)> {
+        self.ownship
+            .filter(|(updated, ..)| updated.elapsed() <= self.timeout)
+            .map(|(_, lat, lon)| (lat, lon))
+    }
+
+    fn set_ownship(&mut self, latitude: Angle, longitude: Angle) {
+        self.ownship = Some((Instant::now(), latitude, longitude));
+    }
+
+    fn in_range(&self, latitude: Angle, longitude: Angle) -> bool {
+        let Some((lat, lon)) = self.ownship() else {
+            return true;
+        };
+        distance(lat, lon, latitude, longitude) <= self.max_distance
+    }
+}
+
+fn position(report: &TrafficReport) -> Option<(Angle, Angle)> {
+    report
+        .has_position()
+        .then_some((report.latitude, report.longitude))
+}
+
+impl Filter for TrafficRange {
+    fn filter(&mut self, message: &Message) -> Verdict {
+        let traffic = match message {
+            Message::Ownship(OwnshipMessage(report)) => {
+                if let Some((lat, lon)) = position(report) {
+                    self.set_ownship(lat, lon);
+                }
+                return Verdict::Pass;
+            }
+            Message::Custom(CustomMessage::PreciseOwnship(ownship)) => {
+                self.set_ownship(ownship.latitude, ownship.longitude);
+                return Verdict::Pass;
+            }
+            Message::Traffic(TrafficMessage(report)) => position(report),
+            Message::Custom(CustomMessage::PreciseTraffic(traffic)) => {
+                Some((traffic.latitude, traffic.longitude))
+            }
+            _ => return Verdict::Pass,
+        };
+
+        match traffic {
+            Some((lat, lon)) if !self.in_range(lat, lon) => Verdict::Drop,
+            _ => Verdict::Pass,
+        }
+    }
+}
+
+/// A frame of a received packet and its decode result
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Including the flag bytes
+    pub bytes: Vec<u8>,
+    pub message: GDL90Result<Message>,
+}
+
+impl Frame {
+    /// Split a packet into its frames
+    #[must_use]
+    pub fn split(packet: impl AsRef<[u8]>) -> Vec<Self> {
+        FrameDecoder::default()
+            .push_frames(packet)
+            .into_iter()
+            .filter_map(|bytes| {
+                let message = Message::from_gdl90_bytes(&bytes).into_iter().next()?;
+                Some(Self { bytes, message })
+            })
+            .collect()
+    }
+}
+
+/// A `Forwarder` target with its own filter chain
+pub struct Destination {
+    pub target: Target,
+    filters: Vec<Box<dyn Filter>>,
+}
+
+impl std::fmt::Debug for Destination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Destination")
+            .field("target", &self.target)
+            .field("filters", &self.filters.len())
+            .finish()
+    }
+}
+
+impl Destination {
+    /// Forwards everything until filters are added
+    #[must_use]
+    pub fn new(target: Target) -> Self {
+        Self {
+            target,
+            filters: vec![],
+        }
+    }
+
+    /// Append a filter to the chain, filters run in the order they were added
+    #[must_use]
+    pub fn with_filter(mut self, filter: impl Filter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Run the frames through the filter chain and build the packet for this destination.
+    ///
+    /// Frames are copied byte for byte unless a filter replaced their message.
+    /// A replacement that fails to encode is dropped.
+    pub fn apply(&mut self, frames: &[Frame]) -> Vec<u8> {
+        let mut packet = vec![];
+
+        for frame in frames {
+            let Ok(message) = &frame.message else {
+                if self
+                    .filters
+                    .iter_mut()
+                    .all(|f| f.filter_undecoded(&frame.bytes))
+                {
+                    packet.extend(&frame.bytes);
+                }
+                continue;
+            };
+
+            let Some(message) = self.filter(message) else {
+                continue;
+            };
+            match message {
+                Cow::Borrowed(_) => packet.extend(&frame.bytes),
+                Cow::Owned(message) => {
+                    if let Ok(bytes) = message.into_gdl90_bytes() {
+                        packet.extend(bytes);
+                    }
+                }
+            }
+        }
+
+        packet
+    }
+
+    fn filter<'a>(&mut self, message: &'a Message) -> Option<Cow<'a, Message>> {
+        let mut message = Cow::Borrowed(message);
+        for filter in &mut self.filters {
+            match filter.filter(&message) {
+                Verdict::Pass => {}
+                Verdict::Drop => return None,
+                Verdict::Replace(replacement) => message = Cow::Owned(replacement),
+            }
+        }
+        Some(message)
+    }
+}
+
+#[derive(Debug, Clone, Builder)]
+/// `Forwarder` settings, `ForwarderConfig::default()` receives on `0.0.0.0:4000`
+pub struct ForwarderConfig {
+    pub bind_addr: SocketAddr,
+
+    /// Receive broadcast packets
+    pub broadcast: bool,
+
+    /// Largest packet that can be received
+    pub buffer_size: usize,
+
+    pub sender: UdpSenderConfig,
+}
+
+impl Default for ForwarderConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from((Ipv4Addr::UNSPECIFIED, GDL90_PORT)),
+            broadcast: true,
+            buffer_size: 2048,
+            sender: UdpSenderConfig::default(),
+        }
+    }
+}
+
+/// Relays one GDL90 feed to several destinations, each with its own filter chain.
+///
+/// Every received packet becomes at most one packet per destination, packets left empty by
+/// the filters are not sent.
+///
+/// ```ignore
+/// let forwarder = Forwarder::bind(ForwarderConfig::default())
+///     .await?
+///     .with_destination(
+///         Destination::new(Target::Unicast("192.168.1.20:4000".parse()?))
+///             .with_filter(DropIds(vec![Message::ID_UPLINK_DATA, Message::ID_CUSTOM]))
+///             .with_filter(TrafficRange::new(20.0.nautical_miles())),
+///     );
+/// let _task = forwarder.spawn();
+/// ```
+#[derive(Debug)]
+pub struct Forwarder {
+    socket: UdpSocket,
+    buf: Vec<u8>,
+    sender: UdpSender,
+    destinations: Vec<Destination>,
+    statistics: Statistics,
+}
+
+impl Forwarder {
+    /// # Errors
+    ///
+    /// If the receive or send sockets can't be set up.
+    pub async fn bind(config: ForwarderConfig) -> GDL90Result<Self> {
+        let socket = UdpSocket::bind(config.bind_addr).await?;
+        socket.set_broadcast(config.broadcast)?;
+        let sender = UdpSender::bind(config.sender).await?;
+
+        Ok(Self {
+            socket,
+            buf: vec![0; config.buffer_size],
+            sender,
+            destinations: vec![],
+            statistics: Statistics::default(),
+        })
+    }
+
+    #[must_use]
+    pub fn with_destination(mut self, destination: Destination) -> Self {
+        self.sender.targets().add(destination.target);
+        self.destinations.push(destination);
+        self
+    }
+
+    /// # Errors
+    ///
+    /// If the receive socket has no local address.
+    pub fn local_addr(&self) -> GDL90Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Received packets and decode errors per source
+    #[must_use]
+    pub fn statistics(&self) -> Statistics {
+        self.statistics.clone()
+    }
+
+    /// Sent packets per destination
+    #[must_use]
+    pub fn targets(&self) -> Targets {
+        self.sender.targets()
+    }
+
+    /// Wait for the next packet and forward it, returns the number of destinations it was sent to
+    ///
+    /// # Errors
+    ///
+    /// If receiving from the socket failed.
+    pub async fn forward(&mut self) -> GDL90Result<usize> {
+        let (len, addr) = self.socket.recv_from(&mut self.buf).await?;
+        let frames = Frame::split(&self.buf[..len]);
+
+        let results = frames.iter().map(|frame| frame.message.clone()).collect();
+        self.statistics
+            .record(&Source::Udp(addr), len, Utc::now(), results);
+
+        let mut sent = 0;
+        for destination in &mut self.destinations {
+            let packet = destination.apply(&frames);
+            if !packet.is_empty() && self.sender.send_to(&destination.target, packet).await {
+                sent += 1;
+            }
+        }
+        Ok(sent)
+    }
+
+    /// Run the forward loop on the tokio runtime until the task is aborted.
+    ///
+    /// Socket errors are skipped, the loop keeps running.
+    #[must_use]
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let _ = self.forward().await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(latitude: f64, longitude: f64) -> TrafficReport {
+        TrafficReport::default()
+            .with_latitude(latitude.degrees())
+            .with_longitude(longitude.degrees())
+            .with_callsign("N825V".into())
+    }
+
+    fn packet(messages: &[Message]) -> Vec<u8> {
+        batch_packets(messages.iter().cloned(), usize::MAX)
+            .unwrap()
+            .concat()
+    }
+
+    fn target() -> Target {
+        Target::Unicast(SocketAddr::from((Ipv4Addr::LOCALHOST, 4000)))
+    }
+
+    #[test]
+    fn filter_chains() {
+        let mut packet = packet(&[
+            Heartbeat::default().with_utc_ok().into(),
+            report(45.1, -122.0).ownship().into(),
+            UplinkData::default().with_uplink_payload([1, 2, 3]).into(),
+            // 6 nm
+            report(45.0, -122.0).traffic().into(),
+            // 24 nm
+            report(45.5, -122.0).traffic().into(),
+            Ping::new(1, Utc::now()).into(),
+        ]);
+        // unknown custom sub-ID
+        packet.extend([0x7E, 0xC9, 0x7F, 0x00, 0x00, 0x7E]);
+        let frames = Frame::split(&packet);
+        assert_eq!(frames.len(), 7);
+
+        let mut everything = Destination::new(target());
+        assert_eq!(everything.apply(&frames), packet);
+
+        let mut filtered = Destination::new(target())
+            .with_filter(DropIds(vec![Message::ID_UPLINK_DATA, Message::ID_CUSTOM]))
+            .with_filter(TrafficRange::new(20.0.nautical_miles()));
+        let messages = Message::from_gdl90_bytes(filtered.apply(&frames))
+            .into_iter()
+            .map(Result::unwrap)
+            .map(|m| m.type_name())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["Heartbeat", "Ownship", "Traffic"]);
+    }
+
+    #[test]
+    fn traffic_range_timeout() {
+        let ownship = Message::from(report(45.1, -122.0).ownship());
+        // 24 nm
+        let far = Message::from(report(45.5, -122.0).traffic());
+
+        let mut range = TrafficRange::new(20.0.nautical_miles());
+        assert_eq!(range.filter(&far), Verdict::Pass);
+        assert_eq!(range.filter(&ownship), Verdict::Pass);
+        assert!(range.ownship().is_some());
+        assert_eq!(range.filter(&far), Verdict::Drop);
+
+        let mut range = TrafficRange::new(20.0.nautical_miles()).with_timeout(Duration::ZERO);
+        range.filter(&ownship);
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(range.ownship().is_none());
+        assert_eq!(range.filter(&far), Verdict::Pass);
+    }
+
+    #[test]
+    fn reencode_changed_only() {
+        let packet = packet(&[
+            Heartbeat::default().into(),
+            report(45.0, -122.0).traffic().into(),
+        ]);
+        let frames = Frame::split(&packet);
+
+        let mut anonymize =
+            Destination::new(target()).with_filter(|message: &Message| match message {
+                Message::Traffic(TrafficMessage(report)) => {
+                    Verdict::Replace(report.clone().with_callsign(String::new()).traffic().into())
+                }
+                _ => Verdict::Pass,
+            });
+        let out = anonymize.apply(&frames);
+
+        assert_eq!(out[..frames[0].bytes.len()], frames[0].bytes);
+        let messages = Message::from_gdl90_bytes(&out);
+        assert_eq!(
+            messages[1].as_ref().unwrap(),
+            &Message::from(report(45.0, -122.0).with_callsign(String::new()).traffic())
+        );
+        assert_ne!(out, packet);
+    }
+
+    #[tokio::test]
+    async fn forward_packets() {
+        let a = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let b = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+
+        let config = ForwarderConfig {
+            bind_addr: SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+            broadcast: false,
+            sender: UdpSenderConfig::loopback(),
+            ..Default::default()
+        };
+        let mut forwarder = Forwarder::bind(config)
+            .await
+            .unwrap()
+            .with_destination(Destination::new(Target::Unicast(a.local_addr().unwrap())))
+            .with_destination(
+                Destination::new(Target::Unicast(b.local_addr().unwrap()))
+                    .with_filter(DropIds(vec![Message::ID_UPLINK_DATA])),
+            );
+        let addr = forwarder.local_addr().unwrap();
+
+        let input = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let uplink = packet(&[UplinkData::default().into()]);
+        input.send_to(&uplink, addr).await.unwrap();
+        assert_eq!(forwarder.forward().await.unwrap(), 1);
+
+        let heartbeat = packet(&[Heartbeat::default().into()]);
+        input.send_to(&heartbeat, addr).await.unwrap();
+        assert_eq!(forwarder.forward().await.unwrap(), 2);
+
+        let mut buf = [0; 1024];
+        let len = a.recv(&mut buf).await.unwrap();
+        assert_eq!(buf[..len], uplink);
+        let len = a.recv(&mut buf).await.unwrap();
+        assert_eq!(buf[..len], heartbeat);
+        let len = b.recv(&mut buf).await.unwrap();
+        assert_eq!(buf[..len], heartbeat);
+
+        let source = Source::Udp(input.local_addr().unwrap());
+        assert_eq!(forwarder.statistics().get(&source).unwrap().packets, 2);
+    }
+}
@@ -0,0 +1,480 @@
+use std::{
+    collections::{BTreeMap, HashMap, btree_map::Entry},
+    fmt::Display,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+use crate::prelude::*;
+
+/// How long a scrape may take to send its request
+const SCRAPE_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Builder)]
+/// `MetricsServer` settings, `MetricsConfig::default()` listens on `127.0.0.1:9190`
+pub struct MetricsConfig {
+    pub bind_addr: SocketAddr,
+
+    /// Traffic not seen for this long no longer counts as a target
+    pub traffic_timeout: Duration,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from((Ipv4Addr::LOCALHOST, 9190)),
+            traffic_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Observed {
+    statistics: Vec<Statistics>,
+    heartbeats: HashMap<Source, (DateTime<Utc>, Heartbeat)>,
+    traffic: HashMap<TargetIdentity, DateTime<Utc>>,
+}
+
+/// Collects what `MetricsServer` exports: the `Statistics` of the transports
+/// and what is seen in their `MessageStream`s.
+///
+/// Cloning is cheap, all clones feed the same metrics.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    traffic_timeout: Duration,
+    observed: Arc<Mutex<Observed>>,
+}
+
+impl Metrics {
+    #[must_use]
+    pub fn new(traffic_timeout: Duration) -> Self {
+        Self {
+            traffic_timeout,
+            observed: Arc::default(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Observed> {
+        self.observed.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Export the counters of a transport
+    pub fn add_statistics(&self, statistics: Statistics) {
+        self.lock().statistics.push(statistics);
+    }
+
+    /// Track Heartbeats and traffic targets
+    pub fn observe(&self, received: &ReceivedMessage) {
+        let mut observed = self.lock();
+        match &received.message {
+            Message::Heartbeat(heartbeat) => {
+                observed
+                    .heartbeats
+                    .insert(received.source.clone(), (received.received_at, *heartbeat));
+            }
+            Message::Traffic(TrafficMessage(report)) => {
+                observed
+                    .traffic
+                    .insert(report.target_identity, received.received_at);
+            }
+            _ => {}
+        }
+    }
+
+    /// `observe()` everything in `stream` on the tokio runtime and pass it on
+    #[must_use]
+    pub fn tap(&self, mut stream: MessageStream) -> (MessageStream, JoinHandle<()>) {
+        let metrics = self.clone();
+        let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_CAPACITY);
+
+        let task = tokio::spawn(async move {
+            while let Some(received) = stream.recv().await {
+                metrics.observe(&received);
+                if tx.send(received).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        (rx, task)
+    }
+
+    /// Prometheus text exposition format
+    #[must_use]
+    pub fn render(&self, now: DateTime<Utc>) -> String {
+        let mut observed = self.lock();
+        let timeout =
+            chrono::Duration::from_std(self.traffic_timeout).unwrap_or(chrono::Duration::MAX);
+        observed.traffic.retain(|_, seen| now - *seen <= timeout);
+
+        let mut sources = BTreeMap::<Source, SourceStats>::new();
+        for (source, stats) in observed.statistics.iter().flat_map(Statistics::snapshot) {
+            match sources.entry(source) {
+                Entry::Vacant(entry) => {
+                    entry.insert(stats);
+                }
+                Entry::Occupied(mut entry) => entry.get_mut().merge(&stats),
+            }
+        }
+        let socket_errors = observed
+            .statistics
+            .iter()
+            .map(Statistics::socket_errors)
+            .sum::<u64>();
+        let heartbeats = observed.heartbeats.iter().collect::<BTreeMap<_, _>>();
+
+        let mut out = String::new();
+        let per_source = |value: fn(&SourceStats) -> u64| {
+            sources.iter().map(move |(source, stats)| {
+                (labels(&[("source", &source.to_string())]), value(stats))
+            })
+        };
+
+        metric(
+            &mut out,
+            "gdl90_packets_total",
+            "counter",
+            "Packets received, frames for stream transports",
+            per_source(|s| s.packets),
+        );
+        metric(
+            &mut out,
+            "gdl90_bytes_total",
+            "counter",
+            "Bytes received",
+            per_source(|s| s.bytes),
+        );
+        metric(
+            &mut out,
+            "gdl90_messages_total",
+            "counter",
+            "Decoded messages per type",
+            sources.iter().flat_map(|(source, stats)| {
+                stats.types.iter().map(move |(type_name, count)| {
+                    (
+                        labels(&[("source", &source.to_string()), ("type", type_name)]),
+                        *count,
+                    )
+                })
+            }),
+        );
+        metric(
+            &mut out,
+            "gdl90_decode_errors_total",
+            "counter",
+            "Messages that failed to decode",
+            per_source(|s| s.errors),
+        );
+        metric(
+            &mut out,
+            "gdl90_crc_mismatches_total",
+            "counter",
+            "Messages with a wrong CRC",
+            per_source(|s| s.crc_mismatches),
+        );
+        metric(
+            &mut out,
+            "gdl90_invalid_escapes_total",
+            "counter",
+            "Messages with an invalid escape sequence",
+            per_source(|s| s.invalid_escapes),
+        );
+        metric(
+            &mut out,
+            "gdl90_unknown_message_ids_total",
+            "counter",
+            "Messages with an unknown message ID",
+            per_source(|s| s.unknown_ids),
+        );
+        metric(
+            &mut out,
+            "gdl90_socket_errors_total",
+            "counter",
+            "Failed receives from the sockets",
+            [(String::new(), socket_errors)],
+        );
+        metric(
+            &mut out,
+            "gdl90_traffic_targets",
+            "gauge",
+            "Traffic targets seen recently",
+            [(String::new(), observed.traffic.len())],
+        );
+        metric(
+            &mut out,
+            "gdl90_heartbeat_age_seconds",
+            "gauge",
+            "Seconds since the last Heartbeat",
+            heartbeats.iter().map(|(source, (at, _))| {
+                (
+                    labels(&[("source", &source.to_string())]),
+                    (now - *at).as_seconds_f64(),
+                )
+            }),
+        );
+        metric(
+            &mut out,
+            "gdl90_heartbeat_uplink_messages",
+            "gauge",
+            "Uplink messages the device received in the previous second",
+            heartbeats.iter().map(|(source, (_, heartbeat))| {
+                (
+                    labels(&[("source", &source.to_string())]),
+                    heartbeat.uplink_count(),
+                )
+            }),
+        );
+        metric(
+            &mut out,
+            "gdl90_heartbeat_basic_long_messages",
+            "gauge",
+            "Basic and Long reports the device received in the previous second",
+            heartbeats.iter().map(|(source, (_, heartbeat))| {
+                (
+                    labels(&[("source", &source.to_string())]),
+                    heartbeat.basic_long_count(),
+                )
+            }),
+        );
+
+        out
+    }
+}
+
+/// `{name="value",...}` with the values escaped
+fn labels(labels: &[(&str, &str)]) -> String {
+    let labels = labels
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', r"\\")
+                .replace('"', r#"\""#)
+                .replace('\n', r"\n");
+            format!("{name}=\"{value}\"")
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{{{labels}}}")
+}
+
+fn metric<V: Display>(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    samples: impl IntoIterator<Item = (String, V)>,
+) {
+    out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+    for (labels, value) in samples {
+        out.push_str(&format!("{name}{labels} {value}\n"));
+    }
+}
+
+/// Serves `Metrics` at `/metrics` for Prometheus to scrape.
+///
+/// ```ignore
+/// let server = MetricsServer::bind(MetricsConfig::default()).await?;
+/// let metrics = server.metrics();
+/// let _server = server.spawn();
+///
+/// let receiver = UdpReceiver::bind(UdpReceiverConfig::default()).await?;
+/// metrics.add_statistics(receiver.statistics());
+/// let (stream, _receiver) = receiver.spawn();
+/// let (stream, _tap) = metrics.tap(stream);
+/// ```
+#[derive(Debug)]
+pub struct MetricsServer {
+    listener: TcpListener,
+    metrics: Metrics,
+}
+
+impl MetricsServer {
+    /// # Errors
+    ///
+    /// If the listener can't be bound.
+    pub async fn bind(config: MetricsConfig) -> GDL90Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(config.bind_addr).await?,
+            metrics: Metrics::new(config.traffic_timeout),
+        })
+    }
+
+    /// # Errors
+    ///
+    /// If the listener has no local address.
+    pub fn local_addr(&self) -> GDL90Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    #[must_use]
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.clone()
+    }
+
+    /// Answer scrapes on the tokio runtime until the task is aborted
+    #[must_use]
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = self.listener.accept().await else {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                };
+                tokio::spawn(scrape(stream, self.metrics.clone()));
+            }
+        })
+    }
+}
+
+/// Just enough HTTP/1.1 for a scraper, one request per connection
+async fn scrape(mut stream: TcpStream, metrics: Metrics) {
+    // a client that never finishes its request would hold the connection forever
+    let Ok(Some(request)) =
+        tokio::time::timeout(SCRAPE_READ_TIMEOUT, read_request(&mut stream)).await
+    else {
+        return;
+    };
+
+    let request = String::from_utf8_lossy(&request);
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+    let (status, body) = match path.split('?').next() {
+        Some("/metrics") => ("200 OK", metrics.render(Utc::now())),
+        _ => ("404 Not Found", String::new()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Read up to the end of the request headers, `None` if the connection closed before
+async fn read_request(stream: &mut TcpStream) -> Option<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let Ok(len @ 1..) = stream.read(&mut buf).await else {
+            return None;
+        };
+        request.extend(&buf[..len]);
+        if request.len() > 8192 {
+            return None;
+        }
+    }
+    Some(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: i64) -> DateTime<Utc> {
+        DateTime::UNIX_EPOCH + chrono::Duration::seconds(s)
+    }
+
+    fn source() -> Source {
+        Source::Udp(SocketAddr::from((Ipv4Addr::LOCALHOST, 4000)))
+    }
+
+    fn received(message: impl Into<Message>, s: i64) -> ReceivedMessage {
+        ReceivedMessage {
+            message: message.into(),
+            source: source(),
+            received_at: at(s),
+        }
+    }
+
+    #[test]
+    fn render() {
+        let statistics = Statistics::default();
+        let mut heartbeat = Heartbeat::default().with_utc_ok();
+        heartbeat.message_counts = 0x1805;
+        let bad_escape = [0x7E, 0x00, 0x7D, 0x00, 0x00, 0x7E];
+        let unknown = [0x7E, 0x33, 0x01, 0x02, 0x32, 0x07, 0x7E];
+        let mut packet = heartbeat.into_gdl90_bytes().unwrap();
+        packet.extend(bad_escape);
+        packet.extend(unknown);
+        statistics.record(
+            &source(),
+            packet.len(),
+            at(0),
+            Message::from_gdl90_bytes(&packet),
+        );
+
+        // the same source seen by a second transport
+        let other = Statistics::default();
+        other.record(
+            &source(),
+            4,
+            at(1),
+            vec![Err(GDL90Error::CrcMismatch {
+                expected: 0,
+                got: 1,
+            })],
+        );
+
+        let metrics = Metrics::new(Duration::from_secs(30));
+        metrics.add_statistics(statistics);
+        metrics.add_statistics(other);
+        metrics.observe(&received(heartbeat, 0));
+        for address in [1, 2] {
+            let report = TrafficReport::default()
+                .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, address));
+            metrics.observe(&received(report.traffic(), address.into()));
+        }
+
+        let text = metrics.render(at(32));
+        let source = r#"source="udp://127.0.0.1:4000""#;
+        for line in [
+            "# TYPE gdl90_messages_total counter".to_string(),
+            format!(r#"gdl90_messages_total{{{source},type="Heartbeat"}} 1"#),
+            format!("gdl90_packets_total{{{source}}} 2"),
+            format!("gdl90_decode_errors_total{{{source}}} 3"),
+            format!("gdl90_crc_mismatches_total{{{source}}} 1"),
+            format!("gdl90_invalid_escapes_total{{{source}}} 1"),
+            format!("gdl90_unknown_message_ids_total{{{source}}} 1"),
+            format!("gdl90_heartbeat_age_seconds{{{source}}} 32"),
+            format!("gdl90_heartbeat_uplink_messages{{{source}}} 3"),
+            format!("gdl90_heartbeat_basic_long_messages{{{source}}} 5"),
+            "gdl90_socket_errors_total 0".to_string(),
+            // the first target expired
+            "gdl90_traffic_targets 1".to_string(),
+        ] {
+            assert!(text.lines().any(|l| l == line), "{line} missing in\n{text}");
+        }
+    }
+
+    #[tokio::test]
+    async fn scrape_endpoint() {
+        let config =
+            MetricsConfig::default().with_bind_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)));
+        let server = MetricsServer::bind(config).await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let _task = server.spawn();
+
+        let get = |path: &'static str| async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let request = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("# TYPE gdl90_traffic_targets gauge"));
+        assert!(get("/").await.starts_with("HTTP/1.1 404"));
+    }
+}
@@ -0,0 +1,273 @@
+//! Async transports, enabled with the `net` feature.
+//!
+//! Every receiving transport delivers `ReceivedMessage`s through a `MessageStream`
+//! and keeps per-source `Statistics`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use chrono::{DateTime, Utc};
+use tokio::sync::mpsc;
+
+use crate::prelude::*;
+
+pub mod cot_output;
+pub mod device;
+pub mod discovery;
+pub mod efb_emulator;
+pub mod flarm;
+pub mod forwarder;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod mux;
+pub mod profile;
+#[cfg(feature = "serial")]
+pub mod serial;
+pub mod server;
+pub mod session;
+pub mod tcp;
+pub mod udp_receiver;
+pub mod udp_sender;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
+#[cfg(feature = "metrics")]
+pub use self::metrics::*;
+#[cfg(feature = "serial")]
+pub use self::serial::*;
+#[cfg(feature = "websocket")]
+pub use self::websocket::*;
+pub use self::{
+    cot_output::*, device::*, discovery::*, efb_emulator::*, flarm::*, forwarder::*, mux::*,
+    profile::*, server::*, session::*, tcp::*, udp_receiver::*, udp_sender::*,
+};
+
+/// Default GDL90 UDP port
+pub const GDL90_PORT: u16 = 4000;
+
+/// Channel capacity used by the transports unless configured otherwise
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Decoded messages of a transport, in the order they were received
+pub type MessageStream = mpsc::Receiver<ReceivedMessage>;
+
+/// Where a packet came from
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Source {
+    Udp(SocketAddr),
+    /// Device path
+    Serial(String),
+    /// Peer of the connection
+    Tcp(SocketAddr),
+}
+
+impl Source {
+    /// Network address of the sender
+    #[must_use]
+    pub fn addr(&self) -> Option<SocketAddr> {
+        match self {
+            Self::Udp(addr) | Self::Tcp(addr) => Some(*addr),
+            Self::Serial(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Udp(addr) => write!(f, "udp://{addr}"),
+            Self::Serial(path) => write!(f, "serial://{path}"),
+            Self::Tcp(addr) => write!(f, "tcp://{addr}"),
+        }
+    }
+}
+
+/// A decoded message, tagged with its source and receive time
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceivedMessage {
+    pub message: Message,
+    pub source: Source,
+    /// When the packet containing the message was received
+    pub received_at: DateTime<Utc>,
+}
+
+/// Counters for a single source
+#[derive(Debug, Clone)]
+pub struct SourceStats {
+    /// Datagrams (or frames for stream transports) received
+    pub packets: u64,
+    pub bytes: u64,
+    /// Successfully decoded messages
+    pub messages: u64,
+    /// Messages that failed to decode
+    pub errors: u64,
+    /// Decoded messages per `Message::type_name()`
+    pub types: BTreeMap<&'static str, u64>,
+    /// Errors that are `GDL90Error::CrcMismatch`
+    pub crc_mismatches: u64,
+    /// Errors that are `GDL90Error::InvalidEscapeSequence`
+    pub invalid_escapes: u64,
+    /// Errors that are `GDL90Error::UnknownMessageId`
+    pub unknown_ids: u64,
+    pub last_error: Option<GDL90Error>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl SourceStats {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            packets: 0,
+            bytes: 0,
+            messages: 0,
+            errors: 0,
+            types: BTreeMap::new(),
+            crc_mismatches: 0,
+            invalid_escapes: 0,
+            unknown_ids: 0,
+            last_error: None,
+            first_seen: now,
+            last_seen: now,
+        }
+    }
+
+    /// Add the counters of `other`, e.g. the same source seen by another transport
+    pub(crate) fn merge(&mut self, other: &Self) {
+        self.packets += other.packets;
+        self.bytes += other.bytes;
+        self.messages += other.messages;
+        self.errors += other.errors;
+        for (type_name, count) in &other.types {
+            *self.types.entry(type_name).or_default() += count;
+        }
+        self.crc_mismatches += other.crc_mismatches;
+        self.invalid_escapes += other.invalid_escapes;
+        self.unknown_ids += other.unknown_ids;
+        if other.last_seen > self.last_seen && other.last_error.is_some() {
+            self.last_error.clone_from(&other.last_error);
+        }
+        self.first_seen = self.first_seen.min(other.first_seen);
+        self.last_seen = self.last_seen.max(other.last_seen);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    sources: HashMap<Source, SourceStats>,
+    socket_errors: u64,
+    last_socket_error: Option<GDL90Error>,
+}
+
+/// Per-source statistics, shared between a transport and its users.
+///
+/// Errors of the socket itself have no source and are counted apart, see `socket_errors()`.
+/// Cloning is cheap, all clones see the same counters.
+#[derive(Debug, Clone, Default)]
+pub struct Statistics(Arc<Mutex<Counters>>);
+
+impl Statistics {
+    fn lock(&self) -> MutexGuard<'_, Counters> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Count a failed receive
+    pub(crate) fn record_socket_error(&self, err: GDL90Error) {
+        let mut counters = self.lock();
+        counters.socket_errors += 1;
+        counters.last_socket_error = Some(err);
+    }
+
+    /// Failed receives from the socket
+    #[must_use]
+    pub fn socket_errors(&self) -> u64 {
+        self.lock().socket_errors
+    }
+
+    #[must_use]
+    pub fn last_socket_error(&self) -> Option<GDL90Error> {
+        self.lock().last_socket_error.clone()
+    }
+
+    /// Count one packet and its decode results, returns the decoded messages
+    pub(crate) fn record(
+        &self,
+        source: &Source,
+        len: usize,
+        received_at: DateTime<Utc>,
+        results: Vec<GDL90Result<Message>>,
+    ) -> Vec<Message> {
+        let mut counters = self.lock();
+        let stats = counters
+            .sources
+            .entry(source.clone())
+            .or_insert_with(|| SourceStats::new(received_at));
+
+        stats.packets += 1;
+        stats.bytes += len as u64;
+        stats.last_seen = received_at;
+
+        let mut messages = Vec::with_capacity(results.len());
+        for result in results {
+            match result {
+                Ok(message) => {
+                    stats.messages += 1;
+                    *stats.types.entry(message.type_name()).or_default() += 1;
+                    messages.push(message);
+                }
+                Err(err) => {
+                    stats.errors += 1;
+                    match err {
+                        GDL90Error::CrcMismatch { .. } => stats.crc_mismatches += 1,
+                        GDL90Error::InvalidEscapeSequence(_) => stats.invalid_escapes += 1,
+                        GDL90Error::UnknownMessageId(_) => stats.unknown_ids += 1,
+                        _ => {}
+                    }
+                    stats.last_error = Some(err);
+                }
+            }
+        }
+        messages
+    }
+
+    #[must_use]
+    pub fn get(&self, source: &Source) -> Option<SourceStats> {
+        self.lock().sources.get(source).cloned()
+    }
+
+    /// Copy of the counters of all sources seen so far
+    #[must_use]
+    pub fn snapshot(&self) -> HashMap<Source, SourceStats> {
+        self.lock().sources.clone()
+    }
+
+    pub fn reset(&self) {
+        *self.lock() = Counters::default();
+    }
+}
+
+/// Decode the frames completed by `bytes`, counting each frame as a packet
+pub(crate) fn decode_frames(
+    decoder: &mut FrameDecoder,
+    statistics: &Statistics,
+    source: &Source,
+    bytes: &[u8],
+) -> Vec<ReceivedMessage> {
+    let received_at = Utc::now();
+
+    decoder
+        .push_frames(bytes)
+        .into_iter()
+        .flat_map(|frame| {
+            let results = Message::from_gdl90_bytes(&frame);
+            statistics.record(source, frame.len(), received_at, results)
+        })
+        .map(|message| ReceivedMessage {
+            message,
+            source: source.clone(),
+            received_at,
+        })
+        .collect()
+}
@@ -0,0 +1,377 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Builder)]
+/// `Multiplexer` rules
+pub struct MuxConfig {
+    /// Only source for Heartbeat, ownship and AHRS.
+    /// `None` lets the first source sending them claim it.
+    pub authoritative: Option<Source>,
+
+    /// Another source takes over ownship when the authoritative one is silent this long.
+    /// Ignored if `authoritative` is set.
+    pub failover_timeout: Duration,
+
+    /// A report from another source with a lower NACp is dropped unless the last one is older than this
+    pub traffic_hold: Duration,
+
+    /// Traffic not updated by any source for this long is forgotten by `spawn()`
+    pub traffic_timeout: Duration,
+
+    /// Identical uplink payloads within this window are passed only once
+    pub uplink_window: Duration,
+}
+
+impl Default for MuxConfig {
+    fn default() -> Self {
+        Self {
+            authoritative: None,
+            failover_timeout: Duration::from_secs(3),
+            traffic_hold: Duration::from_secs(2),
+            traffic_timeout: Duration::from_secs(60),
+            uplink_window: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TrafficEntry {
+    source: Source,
+    nacp: NACp,
+    received_at: DateTime<Utc>,
+}
+
+/// Merges the messages of several receivers into one feed.
+///
+/// - Heartbeat, ownship, ownship geometric altitude and AHRS only come from one authoritative source
+/// - Traffic is merged by `TargetIdentity`, preferring the newest report with the highest NACp
+/// - Identical Uplink Data received by several receivers is passed only once
+/// - Everything else is passed as is
+///
+/// ```ignore
+/// let mux = Multiplexer::new(MuxConfig::default());
+/// let (mut merged, _task) = mux.spawn([udp_stream, serial_stream]);
+/// ```
+#[derive(Debug)]
+pub struct Multiplexer {
+    config: MuxConfig,
+    ownship_source: Option<(Source, DateTime<Utc>)>,
+    traffic: HashMap<TargetIdentity, TrafficEntry>,
+    uplinks: VecDeque<UplinkEntry>,
+}
+
+/// An uplink passed within the window, the hash only speeds up the comparison
+#[derive(Debug, Clone)]
+struct UplinkEntry {
+    received_at: DateTime<Utc>,
+    hash: u64,
+    payload: Vec<u8>,
+}
+
+impl Multiplexer {
+    #[must_use]
+    pub fn new(config: MuxConfig) -> Self {
+        let ownship_source = config
+            .authoritative
+            .clone()
+            .map(|source| (source, DateTime::<Utc>::MIN_UTC));
+
+        Self {
+            config,
+            ownship_source,
+            traffic: HashMap::new(),
+            uplinks: VecDeque::new(),
+        }
+    }
+
+    /// Current source of Heartbeat, ownship and AHRS
+    #[must_use]
+    pub fn ownship_source(&self) -> Option<&Source> {
+        self.ownship_source.as_ref().map(|(source, _)| source)
+    }
+
+    /// `Some` if the message belongs in the merged feed
+    pub fn process(&mut self, received: ReceivedMessage) -> Option<ReceivedMessage> {
+        let pass = match &received.message {
+            Message::Heartbeat(_)
+            | Message::Ownship(_)
+            | Message::OwnshipGeometricAltitude(_)
+            | Message::ForeFlight(ForeFlightMessage::AHRS(_)) => self.ownship(&received),
+            Message::Traffic(TrafficMessage(report)) => self.traffic(&received, report),
+            Message::UplinkData(uplink) => self.uplink(&received, uplink),
+            _ => true,
+        };
+
+        pass.then_some(received)
+    }
+
+    fn ownship(&mut self, received: &ReceivedMessage) -> bool {
+        let timeout = chrono::Duration::from_std(self.config.failover_timeout)
+            .unwrap_or(chrono::Duration::MAX);
+        let fixed = self.config.authoritative.is_some();
+
+        match &mut self.ownship_source {
+            Some((source, last)) if *source == received.source => {
+                *last = received.received_at;
+                true
+            }
+            Some((_, last)) if fixed || received.received_at - *last <= timeout => false,
+            _ => {
+                self.ownship_source = Some((received.source.clone(), received.received_at));
+                true
+            }
+        }
+    }
+
+    fn traffic(&mut self, received: &ReceivedMessage, report: &TrafficReport) -> bool {
+        let hold =
+            chrono::Duration::from_std(self.config.traffic_hold).unwrap_or(chrono::Duration::MAX);
+        let entry = TrafficEntry {
+            source: received.source.clone(),
+            nacp: report.nacp,
+            received_at: received.received_at,
+        };
+
+        let pass = match self.traffic.get(&report.target_identity) {
+            None => true,
+            Some(last) => {
+                last.source == entry.source
+                    || entry.received_at - last.received_at > hold
+                    || u8::from(entry.nacp) >= u8::from(last.nacp)
+            }
+        };
+
+        if pass {
+            self.traffic.insert(report.target_identity, entry);
+        }
+        pass
+    }
+
+    fn uplink(&mut self, received: &ReceivedMessage, uplink: &UplinkData) -> bool {
+        let window =
+            chrono::Duration::from_std(self.config.uplink_window).unwrap_or(chrono::Duration::MAX);
+        while let Some(entry) = self.uplinks.front()
+            && received.received_at - entry.received_at > window
+        {
+            self.uplinks.pop_front();
+        }
+
+        // time of reception differs between receivers, only the payload identifies the uplink
+        let hash = payload_hash(&uplink.uplink_payload);
+        if self
+            .uplinks
+            .iter()
+            .any(|entry| entry.hash == hash && entry.payload == uplink.uplink_payload)
+        {
+            return false;
+        }
+        self.uplinks.push_back(UplinkEntry {
+            received_at: received.received_at,
+            hash,
+            payload: uplink.uplink_payload.to_vec(),
+        });
+        true
+    }
+
+    /// Drop traffic not seen for longer than `timeout`
+    pub fn expire_traffic(&mut self, timeout: Duration, now: DateTime<Utc>) {
+        let timeout = chrono::Duration::from_std(timeout).unwrap_or(chrono::Duration::MAX);
+        self.traffic
+            .retain(|_, entry| now - entry.received_at <= timeout);
+    }
+
+    /// Merge `inputs` on the tokio runtime.
+    ///
+    /// The task ends when all inputs are closed or the merged stream is dropped.
+    #[must_use]
+    pub fn spawn(
+        mut self,
+        inputs: impl IntoIterator<Item = MessageStream>,
+    ) -> (MessageStream, JoinHandle<()>) {
+        let (merged_tx, mut merged_rx) = mpsc::channel(DEFAULT_CHANNEL_CAPACITY);
+        for mut input in inputs {
+            let merged_tx = merged_tx.clone();
+            tokio::spawn(async move {
+                while let Some(received) = input.recv().await {
+                    if merged_tx.send(received).await.is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(merged_tx);
+
+        let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_CAPACITY);
+        let task = tokio::spawn(async move {
+            while let Some(received) = merged_rx.recv().await {
+                self.expire_traffic(self.config.traffic_timeout, received.received_at);
+
+                if let Some(received) = self.process(received)
+                    && tx.send(received).await.is_err()
+                {
+                    return;
+                }
+            }
+        });
+
+        (rx, task)
+    }
+}
+
+fn payload_hash(payload: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    payload.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use super::*;
+
+    fn source(port: u16) -> Source {
+        Source::Udp(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+    }
+
+    fn received(message: impl Into<Message>, port: u16, ms: i64) -> ReceivedMessage {
+        ReceivedMessage {
+            message: message.into(),
+            source: source(port),
+            received_at: DateTime::UNIX_EPOCH + chrono::Duration::milliseconds(ms),
+        }
+    }
+
+    fn traffic(nacp: NACp) -> TrafficMessage {
+        TrafficReport::default()
+            .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, 0xABCDEF))
+            .with_nacp(nacp)
+            .traffic()
+    }
+
+    #[test]
+    fn ownship_failover() {
+        let mut mux = Multiplexer::new(MuxConfig::default());
+        let hb = Heartbeat::default;
+
+        assert!(mux.process(received(hb(), 1, 0)).is_some());
+        assert!(mux.process(received(hb(), 2, 100)).is_none());
+        assert!(
+            mux.process(received(ForeFlightAHRS::default(), 2, 200))
+                .is_none()
+        );
+        assert!(
+            mux.process(received(ForeFlightAHRS::default(), 1, 300))
+                .is_some()
+        );
+        assert_eq!(mux.ownship_source(), Some(&source(1)));
+
+        // source 1 silent for more than 3 s
+        assert!(mux.process(received(hb(), 2, 3500)).is_some());
+        assert_eq!(mux.ownship_source(), Some(&source(2)));
+        assert!(mux.process(received(hb(), 1, 3600)).is_none());
+
+        let mut mux = Multiplexer::new(MuxConfig {
+            authoritative: Some(source(1)),
+            ..Default::default()
+        });
+        assert!(mux.process(received(hb(), 2, 0)).is_none());
+        assert!(mux.process(received(hb(), 2, 60_000)).is_none());
+        assert!(mux.process(received(hb(), 1, 60_000)).is_some());
+    }
+
+    #[test]
+    fn traffic_merge() {
+        let mut mux = Multiplexer::new(MuxConfig::default());
+
+        assert!(
+            mux.process(received(traffic(NACp::NACp9_HFOM_30M_VFOM_45M), 1, 0))
+                .is_some()
+        );
+        // worse accuracy from another receiver
+        assert!(
+            mux.process(received(traffic(NACp::NACp8_0_05NM), 2, 100))
+                .is_none()
+        );
+        // same receiver always updates
+        assert!(
+            mux.process(received(traffic(NACp::NACp8_0_05NM), 1, 200))
+                .is_some()
+        );
+        // now the other one is at least as good
+        assert!(
+            mux.process(received(traffic(NACp::NACp8_0_05NM), 2, 300))
+                .is_some()
+        );
+        assert!(
+            mux.process(received(traffic(NACp::NACp7_0_1NM), 1, 400))
+                .is_none()
+        );
+        // last report too old to hold on to
+        assert!(
+            mux.process(received(traffic(NACp::NACp7_0_1NM), 1, 2500))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn uplink_dedupe() {
+        let mut mux = Multiplexer::new(MuxConfig::default());
+        let uplink = |tor_ms, first| {
+            UplinkData {
+                time_of_reception: Some(Duration::from_millis(tor_ms)),
+                ..Default::default()
+            }
+            .with_uplink_payload([first, 2, 3])
+        };
+
+        assert!(mux.process(received(uplink(1, 1), 1, 0)).is_some());
+        assert!(mux.process(received(uplink(2, 1), 2, 10)).is_none());
+        assert!(mux.process(received(uplink(2, 9), 2, 20)).is_some());
+        assert!(mux.process(received(uplink(3, 1), 1, 10_100)).is_some());
+
+        // a hash collision alone doesn't make it a duplicate
+        let mut mux = Multiplexer::new(MuxConfig::default());
+        let first = uplink(1, 1);
+        mux.uplinks.push_back(UplinkEntry {
+            received_at: DateTime::UNIX_EPOCH,
+            hash: payload_hash(&first.uplink_payload),
+            payload: uplink(1, 9).uplink_payload.to_vec(),
+        });
+        assert!(mux.process(received(first, 1, 10)).is_some());
+    }
+
+    #[tokio::test]
+    async fn merge_streams() {
+        let (a_tx, a) = mpsc::channel(8);
+        let (b_tx, b) = mpsc::channel(8);
+        let (mut merged, task) = Multiplexer::new(MuxConfig::default()).spawn([a, b]);
+
+        a_tx.send(received(Heartbeat::default(), 1, 0))
+            .await
+            .unwrap();
+        let first = merged.recv().await.unwrap();
+        assert_eq!(first.source, source(1));
+
+        b_tx.send(received(Heartbeat::default(), 2, 10))
+            .await
+            .unwrap();
+        b_tx.send(received(Initialization::default(), 2, 20))
+            .await
+            .unwrap();
+        let next = merged.recv().await.unwrap();
+        assert_eq!(next.message, Message::from(Initialization::default()));
+
+        drop((a_tx, b_tx));
+        assert!(merged.recv().await.is_none());
+        task.await.unwrap();
+    }
+}
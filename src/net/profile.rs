@@ -0,0 +1,185 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use crate::prelude::*;
+
+/// Output settings known to work with a particular EFB.
+///
+/// A profile sets the `OutputConfig` (which messages, how often, how they are grouped into packets),
+/// the Heartbeat flags and ForeFlight ID to start with, see `OutputServer::with_profile()`.
+///
+/// ```ignore
+/// let profile: EfbProfile = "SkyDemon".parse()?;
+/// let server = OutputServer::with_profile(sender, profile);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EfbProfile {
+    /// What the ICD asks for, no vendor extensions
+    Generic,
+    /// ID message every second so the device is named in the app, AHRS at 5 Hz, as in the
+    /// ForeFlight GDL 90 Extended Specification
+    ForeFlight,
+    /// Standard messages only
+    GarminPilot,
+    /// Standard messages only
+    SkyDemon,
+}
+
+impl EfbProfile {
+    pub const ALL: [Self; 4] = [
+        Self::Generic,
+        Self::ForeFlight,
+        Self::GarminPilot,
+        Self::SkyDemon,
+    ];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Generic => "Generic",
+            Self::ForeFlight => "ForeFlight",
+            Self::GarminPilot => "Garmin Pilot",
+            Self::SkyDemon => "SkyDemon",
+        }
+    }
+
+    /// Schedules and packet grouping.
+    ///
+    /// Only ForeFlight reads the ID and AHRS extensions, the others get the standard messages
+    /// on the default schedules. Every profile sends ownship and geometric altitude right after
+    /// the Heartbeat in one batched packet, so a receiver that pairs them by packet gets both.
+    #[must_use]
+    pub fn output_config(self) -> OutputConfig {
+        match self {
+            Self::ForeFlight => OutputConfig::default(),
+            Self::Generic | Self::GarminPilot | Self::SkyDemon => OutputConfig {
+                id: Schedule::Off,
+                ahrs: Schedule::Off,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// ID to start with, ForeFlight lists the device by the ID it receives
+    #[must_use]
+    pub fn id(self) -> Option<ForeFlightID> {
+        match self {
+            Self::ForeFlight => Some(
+                ForeFlightID::default()
+                    .with_device_name("GDL90".to_string())
+                    .with_device_long_name("GDL90 output".to_string()),
+            ),
+            Self::Generic | Self::GarminPilot | Self::SkyDemon => None,
+        }
+    }
+
+    /// Heartbeat flags to start with.
+    ///
+    /// Only `uat_initialized`, whether there is a valid position or UTC time isn't up to the
+    /// profile. `gps_pos_valid` follows `OutputServer::set_ownship()`, `utc_ok` is set by the
+    /// application once its time source is synchronized.
+    #[must_use]
+    pub fn heartbeat(self) -> Heartbeat {
+        Heartbeat::default().with_uat_initialized()
+    }
+
+    /// What an `EfbEmulator` should see from an output using this profile
+    #[must_use]
+    pub fn conformance_rules(self) -> ConformanceRules {
+        let mut required = vec!["Heartbeat", "Ownship"];
+        match self {
+            Self::ForeFlight => required.push("ForeFlightID"),
+            Self::GarminPilot | Self::SkyDemon => required.push("OwnshipGeometricAltitude"),
+            Self::Generic => {}
+        }
+
+        ConformanceRules {
+            required,
+            heartbeat_max_gap: Duration::from_millis(1200),
+        }
+    }
+}
+
+impl fmt::Display for EfbProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for EfbProfile {
+    type Err = GDL90Error;
+
+    /// Case, spaces, dashes and underscores are ignored, e.g. `garmin-pilot`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalize = |s: &str| {
+            s.chars()
+                .filter(|c| !matches!(c, ' ' | '-' | '_'))
+                .collect::<String>()
+                .to_lowercase()
+        };
+        let name = normalize(s);
+
+        Self::ALL
+            .into_iter()
+            .find(|profile| normalize(profile.name()) == name)
+            .ok_or_else(|| GDL90Error::UnknownProfile(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        for profile in EfbProfile::ALL {
+            assert_eq!(profile.to_string().parse::<EfbProfile>().unwrap(), profile);
+        }
+        assert_eq!(
+            "garmin-pilot".parse::<EfbProfile>().unwrap(),
+            EfbProfile::GarminPilot
+        );
+        assert_eq!(
+            " SKYDEMON".parse::<EfbProfile>().unwrap(),
+            EfbProfile::SkyDemon
+        );
+        assert!(matches!(
+            "EFB 3000".parse::<EfbProfile>(),
+            Err(GDL90Error::UnknownProfile(_))
+        ));
+    }
+
+    #[test]
+    fn settings() {
+        let foreflight = EfbProfile::ForeFlight.output_config();
+        assert_eq!(foreflight.id, Schedule::Every(Duration::from_secs(1)));
+        assert_eq!(foreflight.ahrs, Schedule::Every(Duration::from_millis(200)));
+        assert_eq!(
+            EfbProfile::ForeFlight.id().unwrap().device_name,
+            "GDL90".to_string()
+        );
+        assert_eq!(EfbProfile::SkyDemon.id(), None);
+
+        let skydemon = EfbProfile::SkyDemon.output_config();
+        assert_eq!(skydemon.id, Schedule::Off);
+        assert_eq!(skydemon.ahrs, Schedule::Off);
+        for profile in EfbProfile::ALL {
+            let config = profile.output_config();
+            assert_eq!(config.ownship, Schedule::WithHeartbeat);
+            assert_eq!(config.geometric_altitude, Schedule::WithHeartbeat);
+            assert_eq!(config.grouping, Grouping::Batched);
+        }
+
+        for profile in EfbProfile::ALL {
+            let heartbeat = profile.heartbeat();
+            assert!(heartbeat.uat_initialized);
+            assert!(!heartbeat.gps_pos_valid && !heartbeat.utc_ok);
+        }
+
+        assert!(
+            EfbProfile::ForeFlight
+                .conformance_rules()
+                .required
+                .contains(&"ForeFlightID")
+        );
+    }
+}
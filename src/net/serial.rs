@@ -0,0 +1,229 @@
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, ReadHalf, WriteHalf},
+    sync::mpsc,
+    task::JoinHandle,
+};
+use tokio_serial::{DataBits, Parity, SerialPortBuilderExt, SerialStream, StopBits};
+
+use super::decode_frames;
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Builder)]
+/// `SerialTransport` settings, `SerialConfig::new(path)` is 38400 8N1
+pub struct SerialConfig {
+    /// e.g. `/dev/ttyUSB0`
+    pub path: String,
+
+    /// GDL 90 devices use 38400 or 115200
+    #[builder(skip(ctor), default = 38400)]
+    pub baud_rate: u32,
+
+    #[builder(skip(ctor), default = Parity::None)]
+    pub parity: Parity,
+
+    #[builder(skip(ctor), default = DataBits::Eight)]
+    pub data_bits: DataBits,
+
+    #[builder(skip(ctor), default = StopBits::One)]
+    pub stop_bits: StopBits,
+
+    #[builder(skip(ctor), default = DEFAULT_CHANNEL_CAPACITY)]
+    pub channel_capacity: usize,
+}
+
+/// GDL90 over RS-232 or USB serial, e.g. a Garmin GDL 39.
+///
+/// ```ignore
+/// let serial = SerialTransport::open(SerialConfig::new("/dev/ttyUSB0".into()).with_baud_rate(115_200))?;
+/// let (mut stream, mut writer, _task) = serial.spawn();
+///
+/// writer.send(Initialization::default().with_cdti_ok()).await?;
+/// while let Some(received) = stream.recv().await { /* ... */ }
+/// ```
+#[derive(Debug)]
+pub struct SerialTransport {
+    reader: SerialReader,
+    writer: SerialWriter,
+    channel_capacity: usize,
+}
+
+impl SerialTransport {
+    /// # Errors
+    ///
+    /// If the port can't be opened or configured.
+    pub fn open(config: SerialConfig) -> GDL90Result<Self> {
+        let port = tokio_serial::new(&config.path, config.baud_rate)
+            .parity(config.parity)
+            .data_bits(config.data_bits)
+            .stop_bits(config.stop_bits)
+            .open_native_async()
+            .map_err(std::io::Error::from)?;
+        let (reader, writer) = tokio::io::split(port);
+
+        Ok(Self {
+            reader: SerialReader {
+                reader,
+                source: Source::Serial(config.path),
+                decoder: FrameDecoder::default(),
+                statistics: Statistics::default(),
+            },
+            writer: SerialWriter(writer),
+            channel_capacity: config.channel_capacity,
+        })
+    }
+
+    /// Frame counts and decode errors, one frame is one packet
+    #[must_use]
+    pub fn statistics(&self) -> Statistics {
+        self.reader.statistics.clone()
+    }
+
+    /// Wait for more bytes and return the messages of the frames they complete.
+    ///
+    /// # Errors
+    ///
+    /// If reading failed or the port was closed.
+    pub async fn recv(&mut self) -> GDL90Result<Vec<ReceivedMessage>> {
+        self.reader.recv().await
+    }
+
+    /// Write a message to the device, e.g. `Initialization`
+    ///
+    /// # Errors
+    ///
+    /// If the message can't be encoded or written.
+    pub async fn send(&mut self, message: impl Into<Message>) -> GDL90Result<()> {
+        self.writer.send(message).await
+    }
+
+    /// Run the read loop on the tokio runtime.
+    ///
+    /// Messages are written with the returned `SerialWriter`.
+    /// The task ends when the stream is dropped or the port fails, e.g. when the device is unplugged.
+    #[must_use]
+    pub fn spawn(self) -> (MessageStream, SerialWriter, JoinHandle<()>) {
+        let Self {
+            mut reader,
+            writer,
+            channel_capacity,
+        } = self;
+        let (tx, rx) = mpsc::channel(channel_capacity);
+
+        let task = tokio::spawn(async move {
+            loop {
+                let received = tokio::select! {
+                    () = tx.closed() => return,
+                    received = reader.recv() => received,
+                };
+
+                let Ok(messages) = received else {
+                    return;
+                };
+
+                for message in messages {
+                    if tx.send(message).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        (rx, writer, task)
+    }
+}
+
+#[derive(Debug)]
+struct SerialReader {
+    reader: ReadHalf<SerialStream>,
+    source: Source,
+    decoder: FrameDecoder,
+    statistics: Statistics,
+}
+
+impl SerialReader {
+    async fn recv(&mut self) -> GDL90Result<Vec<ReceivedMessage>> {
+        let mut buf = [0; 1024];
+        let len = self.reader.read(&mut buf).await?;
+        if len == 0 {
+            bail!(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+
+        Ok(decode_frames(
+            &mut self.decoder,
+            &self.statistics,
+            &self.source,
+            &buf[..len],
+        ))
+    }
+}
+
+/// Write half of a `SerialTransport`
+#[derive(Debug)]
+pub struct SerialWriter(WriteHalf<SerialStream>);
+
+impl SerialWriter {
+    /// # Errors
+    ///
+    /// If the message can't be encoded or written.
+    pub async fn send(&mut self, message: impl Into<Message>) -> GDL90Result<()> {
+        let bytes = message.into().into_gdl90_bytes()?;
+        self.0.write_all(&bytes).await?;
+        self.0.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::os::fd::OwnedFd;
+
+    use tokio::fs::File;
+
+    use super::*;
+
+    /// Master side, slave side (keep it open, or the pty is hung up) and slave path
+    fn pty() -> (File, OwnedFd, String) {
+        let pty = nix::pty::openpty(None, None).unwrap();
+        let path = nix::unistd::ttyname(&pty.slave).unwrap();
+        let master = File::from_std(std::fs::File::from(pty.master));
+        (master, pty.slave, path.to_string_lossy().into_owned())
+    }
+
+    #[tokio::test]
+    async fn read_and_write() {
+        let (mut master, _slave, path) = pty();
+        let serial = SerialTransport::open(SerialConfig::new(path.clone())).unwrap();
+        let statistics = serial.statistics();
+        let (mut stream, mut writer, _task) = serial.spawn();
+
+        let heartbeat = Heartbeat::default().with_utc_ok();
+        let frame = heartbeat.into_gdl90_bytes().unwrap();
+        let (first, second) = frame.split_at(4);
+        master.write_all(first).await.unwrap();
+        master.flush().await.unwrap();
+        master.write_all(second).await.unwrap();
+        master.flush().await.unwrap();
+
+        let received = stream.recv().await.unwrap();
+        assert_eq!(received.message, Message::Heartbeat(heartbeat));
+        assert_eq!(received.source, Source::Serial(path.clone()));
+        assert_eq!(statistics.get(&Source::Serial(path)).unwrap().messages, 1);
+
+        let init = Initialization::default().with_cdti_ok();
+        writer.send(init).await.unwrap();
+
+        let mut decoder = FrameDecoder::default();
+        let mut buf = [0; 64];
+        let messages = loop {
+            let len = master.read(&mut buf).await.unwrap();
+            let messages = decoder.push(&buf[..len]);
+            if !messages.is_empty() {
+                break messages;
+            }
+        };
+        assert_eq!(
+            messages[0].as_ref().unwrap(),
+            &Message::Initialization(init)
+        );
+    }
+}
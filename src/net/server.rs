@@ -0,0 +1,645 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Timelike, Utc};
+use tokio::{task::JoinHandle, time::Instant};
+
+use crate::prelude::*;
+
+/// When a message type is sent by the `OutputServer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    /// Never
+    Off,
+    /// Right after the Heartbeat, at the start of each UTC second
+    WithHeartbeat,
+    /// At a fixed interval, independent of the UTC second
+    Every(Duration),
+    /// Whenever the value is updated
+    OnUpdate,
+}
+
+/// How messages that are due at the same time are put into packets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    /// As few packets as possible, up to the sender's `max_packet_size`
+    Batched,
+    /// Every message in a packet of its own
+    Separate,
+}
+
+#[derive(Debug, Clone, Builder)]
+/// `OutputServer` schedules, the defaults follow the ICD and the ForeFlight spec
+pub struct OutputConfig {
+    /// Heartbeat at the start of each UTC second
+    pub heartbeat: bool,
+
+    pub ownship: Schedule,
+
+    pub geometric_altitude: Schedule,
+
+    pub id: Schedule,
+
+    /// ForeFlight wants about 5 Hz
+    pub ahrs: Schedule,
+
+    /// `OnUpdate` sends reports as they come in, `Every` resends all current targets
+    pub traffic: Schedule,
+
+    /// Traffic not updated for this long is dropped
+    pub traffic_timeout: Duration,
+
+    pub grouping: Grouping,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat: true,
+            ownship: Schedule::WithHeartbeat,
+            geometric_altitude: Schedule::WithHeartbeat,
+            id: Schedule::Every(Duration::from_secs(1)),
+            ahrs: Schedule::Every(Duration::from_millis(200)),
+            traffic: Schedule::OnUpdate,
+            traffic_timeout: Duration::from_secs(10),
+            grouping: Grouping::Batched,
+        }
+    }
+}
+
+/// What the `OutputServer` sends, `None` values are skipped
+#[derive(Debug, Clone, Default)]
+pub struct OutputState {
+    /// Status flags, the timestamp is set when it's sent
+    pub heartbeat: Heartbeat,
+    pub id: Option<ForeFlightID>,
+    pub ownship: Option<TrafficReport>,
+    pub geometric_altitude: Option<OwnshipGeometricAltitude>,
+    pub ahrs: Option<ForeFlightAHRS>,
+    pub traffic: HashMap<TargetIdentity, (Instant, TrafficReport)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Heartbeat,
+    Ownship,
+    GeometricAltitude,
+    Id,
+    Ahrs,
+    Traffic,
+}
+
+impl Output {
+    const SCHEDULED: [Self; 5] = [
+        Self::Ownship,
+        Self::GeometricAltitude,
+        Self::Id,
+        Self::Ahrs,
+        Self::Traffic,
+    ];
+
+    fn schedule(self, config: &OutputConfig) -> Schedule {
+        match self {
+            Self::Heartbeat => Schedule::Off,
+            Self::Ownship => config.ownship,
+            Self::GeometricAltitude => config.geometric_altitude,
+            Self::Id => config.id,
+            Self::Ahrs => config.ahrs,
+            Self::Traffic => config.traffic,
+        }
+    }
+}
+
+/// Decides which outputs are due, separate from the clock so it can be tested
+#[derive(Debug)]
+struct Scheduler {
+    heartbeat: bool,
+    with_heartbeat: Vec<Output>,
+    periodic: Vec<(Output, Duration, Instant)>,
+    last_second: Option<i64>,
+}
+
+impl Scheduler {
+    fn new(config: &OutputConfig, now: Instant) -> Self {
+        let mut with_heartbeat = vec![];
+        let mut periodic = vec![];
+
+        for output in Output::SCHEDULED {
+            match output.schedule(config) {
+                Schedule::WithHeartbeat => with_heartbeat.push(output),
+                Schedule::Every(period) if !period.is_zero() => {
+                    periodic.push((output, period, now))
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            heartbeat: config.heartbeat,
+            with_heartbeat,
+            periodic,
+            last_second: None,
+        }
+    }
+
+    fn due(&mut self, now: Instant, utc: DateTime<Utc>) -> Vec<Output> {
+        let mut due = vec![];
+
+        if self.last_second != Some(utc.timestamp()) {
+            self.last_second = Some(utc.timestamp());
+            if self.heartbeat {
+                due.push(Output::Heartbeat);
+            }
+            due.extend(&self.with_heartbeat);
+        }
+
+        for (output, period, next) in &mut self.periodic {
+            if *next <= now {
+                due.push(*output);
+                *next += *period;
+                // fell behind, don't burst to catch up
+                if *next <= now {
+                    *next = now + *period;
+                }
+            }
+        }
+
+        due
+    }
+
+    fn next_wakeup(&self, now: Instant, utc: DateTime<Utc>) -> Instant {
+        let to_next_second = Duration::from_secs(1).saturating_sub(Duration::from_nanos(
+            u64::from(utc.nanosecond() % 1_000_000_000),
+        ));
+
+        self.periodic
+            .iter()
+            .map(|(_, _, next)| *next)
+            .fold(now + to_next_second, Instant::min)
+    }
+}
+
+/// Sends ownship, AHRS, traffic, etc. on their own schedules through a `UdpSender`.
+///
+/// The Heartbeat goes out at the start of each UTC second, followed by everything scheduled `WithHeartbeat`.
+/// Values are encoded before they are stored, so one that can't be encoded is rejected by its setter
+/// instead of stopping the schedule. Cloning is cheap, all clones share the same state.
+///
+/// ```ignore
+/// let sender = UdpSender::bind(UdpSenderConfig::default()).await?;
+/// sender.targets().add(Target::Broadcast("192.168.1.255:4000".parse()?));
+///
+/// let server = OutputServer::new(sender, OutputConfig::default());
+/// let _task = server.spawn();
+///
+/// server.set_ownship(Some(ownship)).await?;
+/// server.update_traffic(traffic).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct OutputServer {
+    sender: Arc<UdpSender>,
+    config: OutputConfig,
+    state: Arc<Mutex<OutputState>>,
+    encode_errors: Arc<AtomicU64>,
+}
+
+impl OutputServer {
+    #[must_use]
+    pub fn new(sender: UdpSender, config: OutputConfig) -> Self {
+        Self {
+            sender: Arc::new(sender),
+            config,
+            state: Arc::default(),
+            encode_errors: Arc::default(),
+        }
+    }
+
+    /// Schedules, Heartbeat flags and ID of an EFB profile
+    #[must_use]
+    pub fn with_profile(sender: UdpSender, profile: EfbProfile) -> Self {
+        let server = Self::new(sender, profile.output_config());
+        {
+            let mut state = server.lock();
+            state.heartbeat = profile.heartbeat();
+            state.id = profile.id();
+        }
+        server
+    }
+
+    fn lock(&self) -> MutexGuard<'_, OutputState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[must_use]
+    pub fn targets(&self) -> Targets {
+        self.sender.targets()
+    }
+
+    /// Copy of the current state
+    #[must_use]
+    pub fn state(&self) -> OutputState {
+        self.lock().clone()
+    }
+
+    /// Messages that were skipped because they couldn't be encoded
+    #[must_use]
+    pub fn encode_errors(&self) -> u64 {
+        self.encode_errors.load(Ordering::Relaxed)
+    }
+
+    #[must_use]
+    pub fn heartbeat(&self) -> Heartbeat {
+        self.lock().heartbeat
+    }
+
+    pub fn set_heartbeat(&self, heartbeat: Heartbeat) {
+        self.lock().heartbeat = heartbeat;
+    }
+
+    /// Change some Heartbeat flags without touching the others
+    pub fn update_heartbeat(&self, update: impl FnOnce(&mut Heartbeat)) {
+        update(&mut self.lock().heartbeat);
+    }
+
+    /// Setters below send the value right away if it's scheduled `OnUpdate`
+    ///
+    /// # Errors
+    ///
+    /// If the message can't be encoded, the previous value is kept in that case.
+    pub async fn set_id(&self, id: Option<ForeFlightID>) -> GDL90Result<()> {
+        check_encode(id.clone())?;
+        self.lock().id = id;
+        self.updated(Output::Id).await;
+        Ok(())
+    }
+
+    /// Also sets the Heartbeat `gps_pos_valid` flag, to whether the report has a position
+    ///
+    /// # Errors
+    ///
+    /// If the message can't be encoded, the previous value is kept in that case.
+    pub async fn set_ownship(&self, ownship: Option<TrafficReport>) -> GDL90Result<()> {
+        check_encode(ownship.clone().map(TrafficReport::ownship))?;
+        {
+            let mut state = self.lock();
+            state.heartbeat.gps_pos_valid =
+                ownship.as_ref().is_some_and(TrafficReport::has_position);
+            state.ownship = ownship;
+        }
+        self.updated(Output::Ownship).await;
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// If the message can't be encoded, the previous value is kept in that case.
+    pub async fn set_geometric_altitude(
+        &self,
+        geometric_altitude: Option<OwnshipGeometricAltitude>,
+    ) -> GDL90Result<()> {
+        check_encode(geometric_altitude)?;
+        self.lock().geometric_altitude = geometric_altitude;
+        self.updated(Output::GeometricAltitude).await;
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// If the message can't be encoded, the previous value is kept in that case.
+    pub async fn set_ahrs(&self, ahrs: Option<ForeFlightAHRS>) -> GDL90Result<()> {
+        check_encode(ahrs)?;
+        self.lock().ahrs = ahrs;
+        self.updated(Output::Ahrs).await;
+        Ok(())
+    }
+
+    async fn updated(&self, output: Output) {
+        if output.schedule(&self.config) == Schedule::OnUpdate {
+            let messages = self.messages(&[output], Instant::now(), Utc::now());
+            self.send(messages).await;
+        }
+    }
+
+    /// Store the report, and send it right away if traffic is scheduled `OnUpdate`
+    ///
+    /// # Errors
+    ///
+    /// If the report can't be encoded, it isn't stored in that case.
+    pub async fn update_traffic(&self, report: TrafficReport) -> GDL90Result<()> {
+        let bytes = report.clone().traffic().into_gdl90_bytes()?;
+        self.lock()
+            .traffic
+            .insert(report.target_identity, (Instant::now(), report));
+
+        if self.config.traffic == Schedule::OnUpdate {
+            self.sender.send(bytes).await;
+        }
+        Ok(())
+    }
+
+    pub fn remove_traffic(&self, target_identity: &TargetIdentity) -> Option<TrafficReport> {
+        self.lock()
+            .traffic
+            .remove(target_identity)
+            .map(|(_, report)| report)
+    }
+
+    /// Drop expired traffic and build the messages for `outputs`, in that order
+    fn messages(&self, outputs: &[Output], now: Instant, utc: DateTime<Utc>) -> Vec<Message> {
+        let mut state = self.lock();
+        let timeout = self.config.traffic_timeout;
+        state
+            .traffic
+            .retain(|_, (updated, _)| now.duration_since(*updated) <= timeout);
+
+        let mut messages = vec![];
+        for output in outputs {
+            match output {
+                Output::Heartbeat => messages.push(
+                    state
+                        .heartbeat
+                        .with_timestamp(utc.num_seconds_from_midnight())
+                        .into(),
+                ),
+                Output::Ownship => {
+                    messages.extend(state.ownship.clone().map(|report| report.ownship().into()));
+                }
+                Output::GeometricAltitude => {
+                    messages.extend(state.geometric_altitude.map(Message::from));
+                }
+                Output::Id => messages.extend(state.id.clone().map(Message::from)),
+                Output::Ahrs => messages.extend(state.ahrs.map(Message::from)),
+                Output::Traffic => messages.extend(
+                    state
+                        .traffic
+                        .values()
+                        .map(|(_, report)| report.clone().traffic().into()),
+                ),
+            }
+        }
+        messages
+    }
+
+    /// Encode the messages, skipping and counting those that fail
+    fn encode(&self, messages: Vec<Message>) -> Vec<Vec<u8>> {
+        messages
+            .into_iter()
+            .filter_map(|message| {
+                let bytes = message.into_gdl90_bytes().ok();
+                if bytes.is_none() {
+                    self.encode_errors.fetch_add(1, Ordering::Relaxed);
+                }
+                bytes
+            })
+            .collect()
+    }
+
+    async fn send(&self, messages: Vec<Message>) {
+        let frames = self.encode(messages);
+        match self.config.grouping {
+            Grouping::Batched => {
+                let max_packet_size = self.sender.config().max_packet_size;
+                let mut packet = vec![];
+                for frame in frames {
+                    if !packet.is_empty() && packet.len() + frame.len() > max_packet_size {
+                        self.sender.send(std::mem::take(&mut packet)).await;
+                    }
+                    packet.extend(frame);
+                }
+                if !packet.is_empty() {
+                    self.sender.send(packet).await;
+                }
+            }
+            Grouping::Separate => {
+                for frame in frames {
+                    self.sender.send(frame).await;
+                }
+            }
+        }
+    }
+
+    /// Send on schedule until the task is aborted.
+    ///
+    /// Messages that can't be encoded are skipped, see `encode_errors()`.
+    pub async fn run(&self) {
+        let mut scheduler = Scheduler::new(&self.config, Instant::now());
+
+        loop {
+            let (now, utc) = (Instant::now(), Utc::now());
+            let due = scheduler.due(now, utc);
+            if !due.is_empty() {
+                let messages = self.messages(&due, now, utc);
+                self.send(messages).await;
+            }
+
+            tokio::time::sleep_until(scheduler.next_wakeup(Instant::now(), Utc::now())).await;
+        }
+    }
+
+    /// `run()` on the tokio runtime
+    #[must_use]
+    pub fn spawn(&self) -> JoinHandle<()> {
+        let server = self.clone();
+        tokio::spawn(async move { server.run().await })
+    }
+}
+
+/// Encode once to reject a value before it's stored
+fn check_encode(message: Option<impl Into<Message>>) -> GDL90Result<()> {
+    if let Some(message) = message {
+        message.into_gdl90_bytes()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn utc(s: u32, ms: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, s).unwrap()
+            + chrono::Duration::milliseconds(ms.into())
+    }
+
+    #[test]
+    fn schedule() {
+        let t0 = Instant::now();
+        let ms = |ms| t0 + Duration::from_millis(ms);
+        let mut scheduler = Scheduler::new(&OutputConfig::default(), t0);
+
+        assert_eq!(
+            scheduler.due(t0, utc(0, 500)),
+            vec![
+                Output::Heartbeat,
+                Output::Ownship,
+                Output::GeometricAltitude,
+                Output::Id,
+                Output::Ahrs
+            ]
+        );
+        assert_eq!(scheduler.next_wakeup(t0, utc(0, 500)), ms(200));
+
+        assert_eq!(scheduler.due(ms(100), utc(0, 600)), vec![]);
+        assert_eq!(scheduler.due(ms(200), utc(0, 700)), vec![Output::Ahrs]);
+        assert_eq!(scheduler.next_wakeup(ms(350), utc(0, 850)), ms(400));
+        assert_eq!(scheduler.due(ms(400), utc(0, 900)), vec![Output::Ahrs]);
+        assert_eq!(scheduler.next_wakeup(ms(450), utc(0, 950)), ms(500));
+
+        assert_eq!(
+            scheduler.due(ms(600), utc(1, 100)),
+            vec![
+                Output::Heartbeat,
+                Output::Ownship,
+                Output::GeometricAltitude,
+                Output::Ahrs
+            ]
+        );
+        assert_eq!(
+            scheduler.due(ms(1000), utc(1, 500)),
+            vec![Output::Id, Output::Ahrs]
+        );
+
+        // 2 s late, once per output
+        assert_eq!(
+            scheduler.due(ms(3000), utc(3, 0)),
+            vec![
+                Output::Heartbeat,
+                Output::Ownship,
+                Output::GeometricAltitude,
+                Output::Id,
+                Output::Ahrs
+            ]
+        );
+        assert_eq!(scheduler.next_wakeup(ms(3000), utc(3, 0)), ms(3200));
+    }
+
+    #[tokio::test]
+    async fn messages() {
+        let config = UdpSenderConfig::loopback();
+        let sender = UdpSender::bind(config.clone()).await.unwrap();
+        let server = OutputServer::new(sender, OutputConfig::default());
+        let now = Instant::now();
+        let all = [
+            Output::Heartbeat,
+            Output::Ownship,
+            Output::GeometricAltitude,
+            Output::Id,
+            Output::Ahrs,
+            Output::Traffic,
+        ];
+
+        server.set_heartbeat(Heartbeat::default().with_utc_ok());
+        let messages = server.messages(&all, now, utc(1, 0));
+        assert_eq!(
+            messages,
+            vec![Message::from(
+                Heartbeat::default()
+                    .with_utc_ok()
+                    .with_timestamp(12 * 3600 + 1)
+            )]
+        );
+
+        let ownship = TrafficReport::default().with_callsign("OWN".to_string());
+        let traffic = TrafficReport::default()
+            .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, 1));
+        server.set_ownship(Some(ownship.clone())).await.unwrap();
+        assert!(!server.heartbeat().gps_pos_valid);
+        server.update_traffic(traffic.clone()).await.unwrap();
+
+        let messages = server.messages(&[Output::Ownship, Output::Traffic], now, utc(1, 0));
+        assert_eq!(
+            messages,
+            vec![ownship.ownship().into(), traffic.traffic().into()]
+        );
+
+        let later = Instant::now() + Duration::from_secs(11);
+        assert!(
+            server
+                .messages(&[Output::Traffic], later, utc(12, 0))
+                .is_empty()
+        );
+        assert!(server.state().traffic.is_empty());
+
+        let positioned = ownship.clone().with_latitude(47.0.degrees());
+        server.set_ownship(Some(positioned)).await.unwrap();
+        assert!(server.heartbeat().gps_pos_valid);
+        server.set_ownship(None).await.unwrap();
+        assert!(!server.heartbeat().gps_pos_valid);
+
+        let sender = UdpSender::bind(config).await.unwrap();
+        let server = OutputServer::with_profile(sender, EfbProfile::ForeFlight);
+        assert_eq!(
+            server.messages(&[Output::Id], now, utc(1, 0)),
+            vec![Message::from(EfbProfile::ForeFlight.id().unwrap())]
+        );
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Unencodable;
+
+    impl Extension for Unencodable {
+        const KEY: ExtensionKey = ExtensionKey::with_sub_id(Message::ID_CUSTOM, 0x7F);
+
+        fn decode(_payload: &[u8]) -> GDL90Result<Self> {
+            Ok(Self)
+        }
+
+        fn encode(&self) -> GDL90Result<Vec<u8>> {
+            Err(GDL90Error::Extension("unencodable".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn encode_errors() {
+        let sender = UdpSender::bind(UdpSenderConfig::loopback()).await.unwrap();
+        let server = OutputServer::new(sender, OutputConfig::default());
+        let heartbeat = Message::from(Heartbeat::default());
+
+        let frames = server.encode(vec![
+            heartbeat.clone(),
+            Unencodable.into(),
+            heartbeat.clone(),
+        ]);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], heartbeat.into_gdl90_bytes().unwrap());
+        assert_eq!(server.encode_errors(), 1);
+    }
+
+    #[tokio::test]
+    async fn grouping() {
+        let receiver = tokio::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap();
+        let sender = UdpSender::bind(UdpSenderConfig::loopback()).await.unwrap();
+        sender
+            .targets()
+            .add(Target::Unicast(receiver.local_addr().unwrap()));
+
+        let heartbeat = EfbProfile::SkyDemon.heartbeat();
+        let config = OutputConfig::default().with_grouping(Grouping::Separate);
+        let server = OutputServer::new(sender, config);
+        server.send(vec![heartbeat.into(), heartbeat.into()]).await;
+
+        let mut buf = [0; 64];
+        for _ in 0..2 {
+            let len = receiver.recv(&mut buf).await.unwrap();
+            assert_eq!(
+                Message::from_gdl90_bytes(&buf[..len])
+                    .into_iter()
+                    .map(Result::unwrap)
+                    .collect::<Vec<_>>(),
+                vec![Message::from(heartbeat)]
+            );
+        }
+    }
+}
@@ -0,0 +1,652 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, Timelike, Utc};
+use tokio::{
+    net::UdpSocket,
+    sync::Semaphore,
+    time::{Instant, sleep_until},
+};
+
+use crate::{capture::read_record, prelude::*};
+
+const MAGIC: &[u8; 8] = b"GDL90SES";
+const VERSION: u8 = 1;
+
+const SOURCE_UDP: u8 = 0;
+const SOURCE_SERIAL: u8 = 1;
+const SOURCE_TCP: u8 = 2;
+
+/// A raw packet of a recorded session, exactly as it was received
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedPacket {
+    pub received_at: DateTime<Utc>,
+    pub source: Source,
+    pub payload: Vec<u8>,
+}
+
+impl RecordedPacket {
+    #[must_use]
+    pub fn messages(&self) -> Vec<GDL90Result<Message>> {
+        Message::from_gdl90_bytes(&self.payload)
+    }
+}
+
+/// Writes a session recording.
+///
+/// The format is the `GDL90SES` magic and a version byte, followed by one record per packet,
+/// all integers big endian:
+///
+/// | field       | size                                                  |
+/// |-------------|-------------------------------------------------------|
+/// | received_at | `i64`, nanoseconds since the Unix epoch               |
+/// | source kind | `u8`, 0 UDP, 1 serial, 2 TCP                          |
+/// | source      | `u16` length and UTF-8 socket address or device path  |
+/// | payload     | `u32` length and the raw packet bytes                 |
+#[derive(Debug)]
+pub struct SessionWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> SessionWriter<W> {
+    /// Write the file header
+    ///
+    /// # Errors
+    ///
+    /// If writing fails.
+    pub fn new(mut writer: W) -> GDL90Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(Self { writer })
+    }
+
+    /// # Errors
+    ///
+    /// If the timestamp is out of range, the payload too long, or writing fails.
+    pub fn write(&mut self, packet: &RecordedPacket) -> GDL90Result<()> {
+        let Some(nanos) = packet.received_at.timestamp_nanos_opt() else {
+            bail!(invalid(format!(
+                "timestamp {} can't be recorded",
+                packet.received_at
+            )));
+        };
+        let (kind, source) = match &packet.source {
+            Source::Udp(addr) => (SOURCE_UDP, addr.to_string()),
+            Source::Serial(path) => (SOURCE_SERIAL, path.clone()),
+            Source::Tcp(addr) => (SOURCE_TCP, addr.to_string()),
+        };
+        let (Ok(source_len), Ok(payload_len)) = (
+            u16::try_from(source.len()),
+            u32::try_from(packet.payload.len()),
+        ) else {
+            bail!(invalid("source or payload too long".to_string()));
+        };
+
+        let mut record = Vec::with_capacity(19 + source.len() + packet.payload.len());
+        record.extend(nanos.to_be_bytes());
+        record.push(kind);
+        record.extend(source_len.to_be_bytes());
+        record.extend(source.as_bytes());
+        record.extend(payload_len.to_be_bytes());
+        record.extend(&packet.payload);
+        self.writer.write_all(&record)?;
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// If flushing fails.
+    pub fn flush(&mut self) -> GDL90Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    #[must_use]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads the packets of a recording made by `SessionWriter`, see there for the format
+#[derive(Debug)]
+pub struct SessionReader<R: Read> {
+    reader: R,
+    done: bool,
+}
+
+impl<R: Read> SessionReader<R> {
+    /// Check the file header
+    ///
+    /// # Errors
+    ///
+    /// If the file is not a session recording, or a newer version of it.
+    pub fn new(mut reader: R) -> GDL90Result<Self> {
+        let mut header = [0; 9];
+        reader.read_exact(&mut header)?;
+        if &header[..8] != MAGIC {
+            bail!(invalid("not a GDL90 session recording".to_string()));
+        }
+        if header[8] > VERSION {
+            bail!(invalid(format!("unsupported version {}", header[8])));
+        }
+        Ok(Self {
+            reader,
+            done: false,
+        })
+    }
+
+    fn read_packet(&mut self) -> GDL90Result<Option<RecordedPacket>> {
+        let mut head = [0; 11];
+        match self.reader.read_exact(&mut head[..1]) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        self.reader.read_exact(&mut head[1..])?;
+
+        let mut nanos = [0; 8];
+        nanos.copy_from_slice(&head[..8]);
+        let received_at = DateTime::from_timestamp_nanos(i64::from_be_bytes(nanos));
+
+        let source = self.read_vec(usize::from(u16::from_be_bytes([head[9], head[10]])))?;
+        let source =
+            String::from_utf8(source).map_err(|_| invalid("source is not UTF-8".to_string()))?;
+        let source = match head[8] {
+            SOURCE_UDP => Source::Udp(parse_addr(&source)?),
+            SOURCE_SERIAL => Source::Serial(source),
+            SOURCE_TCP => Source::Tcp(parse_addr(&source)?),
+            kind => return Err(invalid(format!("unknown source kind {kind}"))),
+        };
+
+        let mut len = [0; 4];
+        self.reader.read_exact(&mut len)?;
+        let payload = self.read_vec(u32::from_be_bytes(len) as usize)?;
+
+        Ok(Some(RecordedPacket {
+            received_at,
+            source,
+            payload,
+        }))
+    }
+
+    fn read_vec(&mut self, len: usize) -> GDL90Result<Vec<u8>> {
+        read_record(&mut self.reader, len, invalid)
+    }
+}
+
+impl<R: Read> Iterator for SessionReader<R> {
+    type Item = GDL90Result<RecordedPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_packet().transpose();
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
+
+fn invalid(reason: String) -> GDL90Error {
+    GDL90Error::InvalidSession(reason)
+}
+
+fn parse_addr(addr: &str) -> GDL90Result<std::net::SocketAddr> {
+    addr.parse()
+        .map_err(|_| invalid(format!("invalid source address {addr:?}")))
+}
+
+/// Records every datagram arriving on a UDP socket, undecoded.
+///
+/// ```ignore
+/// let mut recorder = SessionRecorder::bind(UdpReceiverConfig::default()).await?;
+/// let mut writer = SessionWriter::new(File::create("flight.gdl90ses")?)?;
+/// // runs until an error, or until the future is dropped, e.g. by a timeout
+/// let _ = tokio::time::timeout(Duration::from_secs(3600), recorder.record(&mut writer)).await;
+/// writer.flush()?;
+/// ```
+#[derive(Debug)]
+pub struct SessionRecorder {
+    socket: UdpSocket,
+    buf: Vec<u8>,
+}
+
+impl SessionRecorder {
+    /// `channel_capacity` of the config is not used
+    ///
+    /// # Errors
+    ///
+    /// If the socket can't be bound.
+    pub async fn bind(config: UdpReceiverConfig) -> GDL90Result<Self> {
+        let socket = UdpSocket::bind(config.bind_addr).await?;
+        socket.set_broadcast(config.broadcast)?;
+
+        Ok(Self {
+            socket,
+            buf: vec![0; config.buffer_size],
+        })
+    }
+
+    /// # Errors
+    ///
+    /// If the socket has no local address.
+    pub fn local_addr(&self) -> GDL90Result<std::net::SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Wait for the next datagram
+    ///
+    /// # Errors
+    ///
+    /// If receiving from the socket failed.
+    pub async fn recv(&mut self) -> GDL90Result<RecordedPacket> {
+        let (len, addr) = self.socket.recv_from(&mut self.buf).await?;
+        Ok(RecordedPacket {
+            received_at: Utc::now(),
+            source: Source::Udp(addr),
+            payload: self.buf[..len].to_vec(),
+        })
+    }
+
+    /// Write every received datagram to `writer`.
+    ///
+    /// Only returns on errors. Dropping the future in between packets is safe, no packet
+    /// is half written.
+    ///
+    /// # Errors
+    ///
+    /// If receiving or writing failed.
+    pub async fn record<W: Write>(&mut self, writer: &mut SessionWriter<W>) -> GDL90Result<()> {
+        loop {
+            let packet = self.recv().await?;
+            writer.write(&packet)?;
+        }
+    }
+}
+
+/// How `SessionPlayer::play()` paces the packets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaybackMode {
+    /// With the recorded gaps between packets
+    RealTime,
+    /// Gaps divided by the factor, e.g. `4.0` plays four times as fast, see `PlaybackMode::accelerated()`
+    Accelerated(f64),
+    /// One packet per `Stepper::step()`
+    Stepping,
+}
+
+impl PlaybackMode {
+    /// # Errors
+    ///
+    /// If `factor` isn't finite and positive.
+    pub fn accelerated(factor: f64) -> GDL90Result<Self> {
+        if factor.is_finite() && factor > 0.0 {
+            Ok(Self::Accelerated(factor))
+        } else {
+            Err(GDL90Error::InvalidPlaybackSpeed(factor))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Builder)]
+/// `SessionPlayer` settings
+pub struct PlayerConfig {
+    pub mode: PlaybackMode,
+
+    /// Set the timestamp of replayed `Heartbeat`s to the current UTC second,
+    /// so EFBs don't reject the data as stale
+    pub rewrite_heartbeat_timestamps: bool,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            mode: PlaybackMode::RealTime,
+            rewrite_heartbeat_timestamps: true,
+        }
+    }
+}
+
+/// Releases packets of a `SessionPlayer` in `PlaybackMode::Stepping`.
+///
+/// Cloning is cheap, all clones step the same player.
+#[derive(Debug, Clone)]
+pub struct Stepper(Arc<Semaphore>);
+
+impl Stepper {
+    /// Let the player send the next packet, steps taken while it is busy are queued
+    pub fn step(&self) {
+        self.0.add_permits(1);
+    }
+}
+
+/// Replays a recorded session through a `UdpSender`.
+///
+/// ```ignore
+/// let packets = SessionReader::new(File::open("flight.gdl90ses")?)?.collect::<GDL90Result<_>>()?;
+/// let mut player = SessionPlayer::new(packets, PlayerConfig::default());
+///
+/// let sender = UdpSender::bind(UdpSenderConfig::default()).await?;
+/// sender.targets().add(Target::Broadcast("192.168.1.255:4000".parse()?));
+/// player.play(&sender).await;
+/// ```
+#[derive(Debug)]
+pub struct SessionPlayer {
+    packets: Vec<RecordedPacket>,
+    position: usize,
+    config: PlayerConfig,
+    stepper: Stepper,
+}
+
+impl SessionPlayer {
+    #[must_use]
+    pub fn new(packets: Vec<RecordedPacket>, config: PlayerConfig) -> Self {
+        Self {
+            packets,
+            position: 0,
+            config,
+            stepper: Stepper(Arc::new(Semaphore::new(0))),
+        }
+    }
+
+    /// Handle for `PlaybackMode::Stepping`
+    #[must_use]
+    pub fn stepper(&self) -> Stepper {
+        self.stepper.clone()
+    }
+
+    /// Index of the next packet to send
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.packets.len()
+    }
+
+    /// Continue at packet `position`, e.g. `0` to start over
+    pub fn seek(&mut self, position: usize) {
+        self.position = position.min(self.packets.len());
+    }
+
+    /// Send the next packet right away, regardless of the mode.
+    ///
+    /// Returns `false` if the session is finished. Send failures are in the sender's `TargetStats`.
+    pub async fn send_next(&mut self, sender: &UdpSender) -> bool {
+        let Some(packet) = self.packets.get(self.position) else {
+            return false;
+        };
+        let payload = self.prepare(&packet.payload, Utc::now());
+        self.position += 1;
+
+        sender.send(payload).await;
+        true
+    }
+
+    /// Play from the current position to the end, paced according to the mode.
+    ///
+    /// In real-time and accelerated mode the first packet is sent right away.
+    /// Dropping the future pauses playback, `play()` again continues with the next packet.
+    ///
+    /// Returns the number of packets sent.
+    pub async fn play(&mut self, sender: &UdpSender) -> usize {
+        let speed = match self.config.mode {
+            PlaybackMode::RealTime => 1.0,
+            PlaybackMode::Accelerated(factor) => factor,
+            PlaybackMode::Stepping => return self.play_stepping(sender).await,
+        };
+
+        let Some(first) = self.packets.get(self.position) else {
+            return 0;
+        };
+        let (start, recorded_start) = (Instant::now(), first.received_at);
+
+        let mut sent = 0;
+        while let Some(packet) = self.packets.get(self.position) {
+            let gap = (packet.received_at - recorded_start)
+                .to_std()
+                .unwrap_or_default();
+            // factors `PlaybackMode::accelerated()` rejects play without pauses
+            let due = Duration::try_from_secs_f64(gap.as_secs_f64() / speed)
+                .ok()
+                .and_then(|delay| start.checked_add(delay));
+            if let Some(due) = due {
+                sleep_until(due).await;
+            }
+
+            self.send_next(sender).await;
+            sent += 1;
+        }
+        sent
+    }
+
+    async fn play_stepping(&mut self, sender: &UdpSender) -> usize {
+        let mut sent = 0;
+        while !self.is_finished() {
+            let Ok(permit) = self.stepper.0.acquire().await else {
+                break;
+            };
+            permit.forget();
+
+            self.send_next(sender).await;
+            sent += 1;
+        }
+        sent
+    }
+
+    /// The packet as it is sent, with rewritten `Heartbeat` timestamps if configured.
+    ///
+    /// Frames other than `Heartbeat`s are copied byte for byte.
+    fn prepare(&self, payload: &[u8], now: DateTime<Utc>) -> Vec<u8> {
+        if !self.config.rewrite_heartbeat_timestamps {
+            return payload.to_vec();
+        }
+
+        let mut packet = Vec::with_capacity(payload.len());
+        for frame in Frame::split(payload) {
+            let heartbeat = match frame.message {
+                Ok(Message::Heartbeat(heartbeat)) => heartbeat,
+                _ => {
+                    packet.extend(frame.bytes);
+                    continue;
+                }
+            };
+            match heartbeat
+                .with_timestamp(now.num_seconds_from_midnight())
+                .into_gdl90_bytes()
+            {
+                Ok(bytes) => packet.extend(bytes),
+                Err(_) => packet.extend(frame.bytes),
+            }
+        }
+        packet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use super::*;
+
+    fn at(millis: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(1_760_000_000_000 + millis).unwrap()
+    }
+
+    fn heartbeat(timestamp: u32) -> Message {
+        Heartbeat::default()
+            .with_utc_ok()
+            .with_timestamp(timestamp)
+            .into()
+    }
+
+    fn recording() -> Vec<RecordedPacket> {
+        let ownship = TrafficReport::default()
+            .with_latitude(45.0.degrees())
+            .ownship();
+        let source = Source::Udp(SocketAddr::from((Ipv4Addr::new(192, 168, 10, 1), 4000)));
+
+        (0..3)
+            .map(|i| RecordedPacket {
+                received_at: at(i * 1000),
+                source: source.clone(),
+                payload: batch_packets([heartbeat(0), ownship.clone().into()], usize::MAX)
+                    .unwrap()
+                    .concat(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let mut packets = recording();
+        packets[1].source = Source::Serial("/dev/ttyUSB0".to_string());
+        packets[2].source = Source::Tcp("[::1]:2000".parse().unwrap());
+
+        let mut writer = SessionWriter::new(Vec::new()).unwrap();
+        for packet in &packets {
+            writer.write(packet).unwrap();
+        }
+        let file = writer.into_inner();
+
+        let read = SessionReader::new(file.as_slice())
+            .unwrap()
+            .collect::<GDL90Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read, packets);
+
+        let mut reader = SessionReader::new(&file[..file.len() - 1]).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+
+        assert!(matches!(
+            SessionReader::new(&b"GDL90SES\x02"[..]),
+            Err(GDL90Error::InvalidSession(_))
+        ));
+    }
+
+    #[test]
+    fn heartbeat_rewrite() {
+        let packet = &recording()[0];
+        let now = at(0).with_hour(13).unwrap().with_minute(5).unwrap();
+
+        let player = SessionPlayer::new(vec![], PlayerConfig::default());
+        let messages = Message::from_gdl90_bytes(player.prepare(&packet.payload, now));
+        let recorded = packet.messages();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].as_ref().unwrap(),
+            &heartbeat(now.num_seconds_from_midnight())
+        );
+        assert_eq!(messages[1].as_ref().unwrap(), recorded[1].as_ref().unwrap());
+
+        let player = SessionPlayer::new(
+            vec![],
+            PlayerConfig::default().with_rewrite_heartbeat_timestamps(false),
+        );
+        assert_eq!(player.prepare(&packet.payload, now), packet.payload);
+    }
+
+    async fn sender_to(target: SocketAddr) -> UdpSender {
+        let sender = UdpSender::bind(UdpSenderConfig::loopback()).await.unwrap();
+        sender.targets().add(Target::Unicast(target));
+        sender
+    }
+
+    #[tokio::test]
+    async fn record_and_play() {
+        let config = UdpReceiverConfig::default()
+            .with_bind_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .with_broadcast(false);
+        let mut recorder = SessionRecorder::bind(config).await.unwrap();
+        let sender = sender_to(recorder.local_addr().unwrap()).await;
+
+        let packets = recording();
+        for packet in &packets {
+            sender.send(&packet.payload).await;
+        }
+        let mut writer = SessionWriter::new(Vec::new()).unwrap();
+        for _ in &packets {
+            let packet = recorder.recv().await.unwrap();
+            assert_eq!(packet.source, Source::Udp(sender.local_addr().unwrap()));
+            writer.write(&packet).unwrap();
+        }
+        let recorded = SessionReader::new(writer.into_inner().as_slice())
+            .unwrap()
+            .collect::<GDL90Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(recorded[2].payload, packets[2].payload);
+
+        // 2 s of recording at 100x
+        let receiver = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let sender = sender_to(receiver.local_addr().unwrap()).await;
+        let mut player = SessionPlayer::new(
+            packets.clone(),
+            PlayerConfig::default().with_mode(PlaybackMode::accelerated(100.0).unwrap()),
+        );
+        let start = Instant::now();
+        assert_eq!(player.play(&sender).await, 3);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert!(player.is_finished());
+
+        for factor in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                PlaybackMode::accelerated(factor),
+                Err(GDL90Error::InvalidPlaybackSpeed(_))
+            ));
+        }
+        player.seek(0);
+        player.config.mode = PlaybackMode::Accelerated(0.0);
+        assert_eq!(player.play(&sender).await, 3);
+
+        let mut buf = [0; 256];
+        let len = receiver.recv(&mut buf).await.unwrap();
+        let Ok(Message::Heartbeat(replayed)) = &Message::from_gdl90_bytes(&buf[..len])[0] else {
+            panic!("expected a Heartbeat");
+        };
+        let now = Utc::now().num_seconds_from_midnight();
+        assert!(replayed.timestamp().abs_diff(now) <= 1);
+    }
+
+    #[tokio::test]
+    async fn stepping() {
+        let receiver = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let sender = sender_to(receiver.local_addr().unwrap()).await;
+        let mut player = SessionPlayer::new(
+            recording(),
+            PlayerConfig::default().with_mode(PlaybackMode::Stepping),
+        );
+
+        let stepper = player.stepper();
+        stepper.step();
+        stepper.step();
+        let paused = tokio::time::timeout(Duration::from_millis(50), player.play(&sender)).await;
+        assert!(paused.is_err());
+        assert_eq!(player.position(), 2);
+
+        stepper.step();
+        assert_eq!(player.play(&sender).await, 1);
+        assert!(player.is_finished());
+        assert!(!player.send_next(&sender).await);
+
+        player.seek(0);
+        assert!(player.send_next(&sender).await);
+        assert_eq!(player.position(), 1);
+    }
+}
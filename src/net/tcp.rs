@@ -0,0 +1,384 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
+
+use super::decode_frames;
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Builder)]
+/// `TcpServer` settings, `TcpServerConfig::default()` listens on `0.0.0.0:4000`
+pub struct TcpServerConfig {
+    pub bind_addr: SocketAddr,
+
+    pub channel_capacity: usize,
+
+    /// Packets queued per client, a client that falls further behind skips packets
+    pub client_queue: usize,
+}
+
+impl Default for TcpServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from((Ipv4Addr::UNSPECIFIED, GDL90_PORT)),
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            client_queue: 256,
+        }
+    }
+}
+
+/// Serves one GDL90 output to any number of TCP clients, for networks where UDP is blocked.
+///
+/// Whatever the clients send, e.g. `Initialization`, ends up in the `MessageStream`.
+///
+/// ```ignore
+/// let server = TcpServer::bind(TcpServerConfig::default()).await?;
+/// let output = server.output();
+/// let (_from_clients, _task) = server.spawn();
+///
+/// output.send_messages([heartbeat, ownship])?;
+/// ```
+#[derive(Debug)]
+pub struct TcpServer {
+    listener: TcpListener,
+    output: TcpOutput,
+    statistics: Statistics,
+    channel_capacity: usize,
+}
+
+/// Sends packets to all clients of a `TcpServer`.
+///
+/// Cloning is cheap, all clones send to the same clients.
+#[derive(Debug, Clone)]
+pub struct TcpOutput {
+    packets: broadcast::Sender<Arc<[u8]>>,
+    clients: Arc<AtomicUsize>,
+}
+
+impl TcpOutput {
+    /// Connected clients
+    #[must_use]
+    pub fn clients(&self) -> usize {
+        self.clients.load(Ordering::Relaxed)
+    }
+
+    /// Queue an already encoded packet for all clients, returns the number of clients
+    pub fn send(&self, packet: impl AsRef<[u8]>) -> usize {
+        self.packets.send(packet.as_ref().into()).unwrap_or(0)
+    }
+
+    /// Encode the messages and queue them for all clients as one packet
+    ///
+    /// # Errors
+    ///
+    /// If a message can't be encoded, nothing is sent in that case.
+    pub fn send_messages<M: Into<Message>>(
+        &self,
+        messages: impl IntoIterator<Item = M>,
+    ) -> GDL90Result<usize> {
+        let packet = batch_packets(messages, usize::MAX)?.concat();
+        Ok(self.send(packet))
+    }
+}
+
+impl TcpServer {
+    /// # Errors
+    ///
+    /// If the listener can't be bound.
+    pub async fn bind(config: TcpServerConfig) -> GDL90Result<Self> {
+        let listener = TcpListener::bind(config.bind_addr).await?;
+        let (packets, _) = broadcast::channel(config.client_queue);
+
+        Ok(Self {
+            listener,
+            output: TcpOutput {
+                packets,
+                clients: Arc::default(),
+            },
+            statistics: Statistics::default(),
+            channel_capacity: config.channel_capacity,
+        })
+    }
+
+    /// # Errors
+    ///
+    /// If the listener has no local address.
+    pub fn local_addr(&self) -> GDL90Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    #[must_use]
+    pub fn output(&self) -> TcpOutput {
+        self.output.clone()
+    }
+
+    /// Frame counts and decode errors of what clients send, one frame is one packet
+    #[must_use]
+    pub fn statistics(&self) -> Statistics {
+        self.statistics.clone()
+    }
+
+    /// Accept clients on the tokio runtime until the task is aborted
+    #[must_use]
+    pub fn spawn(self) -> (MessageStream, JoinHandle<()>) {
+        let (tx, rx) = mpsc::channel(self.channel_capacity);
+
+        let task = tokio::spawn(async move {
+            loop {
+                let Ok((stream, peer)) = self.listener.accept().await else {
+                    // e.g. out of file descriptors, give the clients a moment to go away
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                };
+                let client = Client {
+                    stream,
+                    source: Source::Tcp(peer),
+                    output: self.output.clone(),
+                    statistics: self.statistics.clone(),
+                    tx: tx.clone(),
+                };
+                tokio::spawn(client.run());
+            }
+        });
+
+        (rx, task)
+    }
+}
+
+/// Connection of a `TcpServer` client
+struct Client {
+    stream: TcpStream,
+    source: Source,
+    output: TcpOutput,
+    statistics: Statistics,
+    tx: mpsc::Sender<ReceivedMessage>,
+}
+
+impl Client {
+    async fn run(self) {
+        let Self {
+            stream,
+            source,
+            output,
+            statistics,
+            tx,
+        } = self;
+
+        let mut packets = output.packets.subscribe();
+        output.clients.fetch_add(1, Ordering::Relaxed);
+
+        let (mut reader, mut writer) = stream.into_split();
+        let mut decoder = FrameDecoder::default();
+        let mut buf = [0; 1024];
+
+        loop {
+            tokio::select! {
+                packet = packets.recv() => match packet {
+                    Ok(packet) => {
+                        if writer.write_all(&packet).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                len = reader.read(&mut buf) => {
+                    let Ok(len @ 1..) = len else {
+                        break;
+                    };
+                    for message in decode_frames(&mut decoder, &statistics, &source, &buf[..len]) {
+                        // a full or dropped stream must not stall the output
+                        let _ = tx.try_send(message);
+                    }
+                }
+            }
+        }
+
+        output.clients.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, Builder)]
+/// `TcpClient` settings
+pub struct TcpClientConfig {
+    /// `host:port` of the server
+    pub addr: String,
+
+    /// First reconnect delay, doubled after every failed attempt
+    #[builder(skip(ctor), default = Duration::from_millis(500))]
+    pub initial_backoff: Duration,
+
+    #[builder(skip(ctor), default = Duration::from_secs(30))]
+    pub max_backoff: Duration,
+
+    #[builder(skip(ctor), default = DEFAULT_CHANNEL_CAPACITY)]
+    pub channel_capacity: usize,
+}
+
+/// Receives GDL90 from a TCP server and reconnects with exponential backoff when the connection drops.
+///
+/// ```ignore
+/// let client = TcpClient::new(TcpClientConfig::new("10.8.0.1:4000".into()));
+/// let (mut stream, _task) = client.spawn();
+/// ```
+#[derive(Debug)]
+pub struct TcpClient {
+    config: TcpClientConfig,
+    statistics: Statistics,
+}
+
+impl TcpClient {
+    #[must_use]
+    pub fn new(config: TcpClientConfig) -> Self {
+        Self {
+            config,
+            statistics: Statistics::default(),
+        }
+    }
+
+    /// Frame counts and decode errors per server address, one frame is one packet
+    #[must_use]
+    pub fn statistics(&self) -> Statistics {
+        self.statistics.clone()
+    }
+
+    /// Connect and receive on the tokio runtime.
+    ///
+    /// The task ends once the returned stream is dropped.
+    #[must_use]
+    pub fn spawn(self) -> (MessageStream, JoinHandle<()>) {
+        let (tx, rx) = mpsc::channel(self.config.channel_capacity);
+
+        let task = tokio::spawn(async move {
+            let mut backoff = self.config.initial_backoff;
+            loop {
+                if let Ok(stream) = TcpStream::connect(&self.config.addr).await {
+                    backoff = self.config.initial_backoff;
+                    if self.receive(stream, &tx).await.is_err() {
+                        return;
+                    }
+                }
+
+                tokio::select! {
+                    () = tx.closed() => return,
+                    () = tokio::time::sleep(backoff) => {}
+                }
+                backoff = (backoff * 2).min(self.config.max_backoff);
+            }
+        });
+
+        (rx, task)
+    }
+
+    /// Until the connection drops, `Err` if the stream was dropped
+    async fn receive(
+        &self,
+        stream: TcpStream,
+        tx: &mpsc::Sender<ReceivedMessage>,
+    ) -> Result<(), ()> {
+        let Ok(peer) = stream.peer_addr() else {
+            return Ok(());
+        };
+        let source = Source::Tcp(peer);
+        let mut stream = stream;
+        let mut decoder = FrameDecoder::default();
+        let mut buf = [0; 1024];
+
+        loop {
+            let len = tokio::select! {
+                () = tx.closed() => return Err(()),
+                len = stream.read(&mut buf) => len,
+            };
+            let Ok(len @ 1..) = len else {
+                return Ok(());
+            };
+
+            for message in decode_frames(&mut decoder, &self.statistics, &source, &buf[..len]) {
+                tx.send(message).await.map_err(|_| ())?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heartbeat() -> Heartbeat {
+        Heartbeat::default().with_utc_ok()
+    }
+
+    async fn wait_for_clients(output: &TcpOutput, clients: usize) {
+        while output.clients() < clients {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn server_to_clients() {
+        let config =
+            TcpServerConfig::default().with_bind_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)));
+        let server = TcpServer::bind(config).await.unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let output = server.output();
+        let (mut from_clients, _task) = server.spawn();
+
+        let (mut a, _a) = TcpClient::new(TcpClientConfig::new(addr.clone())).spawn();
+        let (mut b, _b) = TcpClient::new(TcpClientConfig::new(addr.clone())).spawn();
+        wait_for_clients(&output, 2).await;
+
+        assert_eq!(output.send_messages([heartbeat()]).unwrap(), 2);
+        assert_eq!(a.recv().await.unwrap().message, Message::from(heartbeat()));
+        assert_eq!(b.recv().await.unwrap().message, Message::from(heartbeat()));
+
+        // a frame split across segments
+        let mut raw = TcpStream::connect(&addr).await.unwrap();
+        let init = Initialization::default().with_cdti_ok();
+        let frame = init.into_gdl90_bytes().unwrap();
+        raw.write_all(&frame[..3]).await.unwrap();
+        raw.flush().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        raw.write_all(&frame[3..]).await.unwrap();
+
+        let received = from_clients.recv().await.unwrap();
+        assert_eq!(received.message, Message::from(init));
+        assert_eq!(received.source, Source::Tcp(raw.local_addr().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn client_reconnects() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let config =
+            TcpClientConfig::new(addr.to_string()).with_initial_backoff(Duration::from_millis(10));
+        let client = TcpClient::new(config);
+        let statistics = client.statistics();
+        let (mut stream, _task) = client.spawn();
+
+        let (first, _) = listener.accept().await.unwrap();
+        drop(first);
+
+        let (mut second, _) = listener.accept().await.unwrap();
+        second
+            .write_all(&heartbeat().into_gdl90_bytes().unwrap())
+            .await
+            .unwrap();
+
+        let received = stream.recv().await.unwrap();
+        assert_eq!(received.message, Message::from(heartbeat()));
+        assert_eq!(received.source, Source::Tcp(addr));
+        assert_eq!(statistics.get(&Source::Tcp(addr)).unwrap().messages, 1);
+    }
+}
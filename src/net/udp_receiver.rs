@@ -0,0 +1,218 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use chrono::Utc;
+use tokio::{net::UdpSocket, sync::mpsc, task::JoinHandle};
+
+use crate::prelude::*;
+
+/// Wait after the first socket error in a row, doubled for each further one up to 1 s
+const SOCKET_ERROR_BACKOFF: Duration = Duration::from_millis(10);
+const SOCKET_ERROR_BACKOFF_MAX: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Builder)]
+/// `UdpReceiver` settings, `UdpReceiverConfig::default()` listens on `0.0.0.0:4000`
+pub struct UdpReceiverConfig {
+    pub bind_addr: SocketAddr,
+
+    /// Allow broadcast datagrams
+    pub broadcast: bool,
+
+    /// Largest datagram accepted, longer ones are truncated
+    pub buffer_size: usize,
+
+    pub channel_capacity: usize,
+
+    /// Socket errors in a row after which the `spawn()` task ends
+    pub max_socket_errors: u32,
+}
+
+impl Default for UdpReceiverConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from((Ipv4Addr::UNSPECIFIED, GDL90_PORT)),
+            broadcast: true,
+            buffer_size: u16::MAX as usize,
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            max_socket_errors: 10,
+        }
+    }
+}
+
+impl UdpReceiverConfig {
+    #[must_use]
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.bind_addr.set_port(port);
+        self
+    }
+}
+
+/// Receives GDL90 datagrams and decodes every message in them.
+///
+/// Use `recv()` to pull packets yourself, or `spawn()` to get a `MessageStream`.
+///
+/// ```ignore
+/// let receiver = UdpReceiver::bind(UdpReceiverConfig::default()).await?;
+/// let stats = receiver.statistics();
+/// let (mut stream, _task) = receiver.spawn();
+///
+/// while let Some(received) = stream.recv().await {
+///     println!("{} from {}", received.message.message_id(), received.source);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct UdpReceiver {
+    socket: UdpSocket,
+    config: UdpReceiverConfig,
+    statistics: Statistics,
+    buf: Vec<u8>,
+}
+
+impl UdpReceiver {
+    /// # Errors
+    ///
+    /// If the socket can't be bound.
+    pub async fn bind(config: UdpReceiverConfig) -> GDL90Result<Self> {
+        let socket = UdpSocket::bind(config.bind_addr).await?;
+        socket.set_broadcast(config.broadcast)?;
+
+        Ok(Self {
+            socket,
+            buf: vec![0; config.buffer_size],
+            config,
+            statistics: Statistics::default(),
+        })
+    }
+
+    /// # Errors
+    ///
+    /// If the socket has no local address.
+    pub fn local_addr(&self) -> GDL90Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    #[must_use]
+    pub fn statistics(&self) -> Statistics {
+        self.statistics.clone()
+    }
+
+    /// Wait for the next datagram and return the messages that decoded.
+    ///
+    /// Messages that fail to decode are only counted in the statistics.
+    ///
+    /// # Errors
+    ///
+    /// If receiving from the socket failed, counted in `Statistics::socket_errors()`.
+    pub async fn recv(&mut self) -> GDL90Result<Vec<ReceivedMessage>> {
+        let (len, addr) = match self.socket.recv_from(&mut self.buf).await {
+            Ok(received) => received,
+            Err(err) => {
+                let err = GDL90Error::from(err);
+                self.statistics.record_socket_error(err.clone());
+                bail!(err);
+            }
+        };
+        let received_at = Utc::now();
+        let source = Source::Udp(addr);
+
+        let results = Message::from_gdl90_bytes(&self.buf[..len]);
+        let messages = self
+            .statistics
+            .record(&source, len, received_at, results)
+            .into_iter()
+            .map(|message| ReceivedMessage {
+                message,
+                source: source.clone(),
+                received_at,
+            })
+            .collect();
+
+        Ok(messages)
+    }
+
+    /// Run the receive loop on the tokio runtime.
+    ///
+    /// The task ends once the returned stream is dropped.
+    /// After a socket error it waits before receiving again, longer with every error in a row,
+    /// and ends the stream after `max_socket_errors` of them.
+    #[must_use]
+    pub fn spawn(mut self) -> (MessageStream, JoinHandle<()>) {
+        let (tx, rx) = mpsc::channel(self.config.channel_capacity);
+
+        let task = tokio::spawn(async move {
+            let mut socket_errors = 0;
+            loop {
+                let received = tokio::select! {
+                    () = tx.closed() => return,
+                    received = self.recv() => received,
+                };
+
+                let Ok(messages) = received else {
+                    socket_errors += 1;
+                    if socket_errors >= self.config.max_socket_errors {
+                        return;
+                    }
+                    let backoff = SOCKET_ERROR_BACKOFF
+                        .saturating_mul(2_u32.saturating_pow(socket_errors - 1))
+                        .min(SOCKET_ERROR_BACKOFF_MAX);
+                    tokio::select! {
+                        () = tx.closed() => return,
+                        () = tokio::time::sleep(backoff) => continue,
+                    }
+                };
+                socket_errors = 0;
+
+                for message in messages {
+                    if tx.send(message).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        (rx, task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn receive_and_count() {
+        let config = UdpReceiverConfig::default()
+            .with_bind_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .with_broadcast(false);
+        let receiver = UdpReceiver::bind(config).await.unwrap();
+        let addr = receiver.local_addr().unwrap();
+        let statistics = receiver.statistics();
+        let (mut stream, _task) = receiver.spawn();
+
+        let sender = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let source = Source::Udp(sender.local_addr().unwrap());
+
+        let heartbeat = Heartbeat::default().with_utc_ok();
+        let mut packet = heartbeat.into_gdl90_bytes().unwrap();
+        packet.extend([0x7E, 0x00, 0x01, 0x02, 0x7E]);
+        sender.send_to(&packet, addr).await.unwrap();
+
+        let received = stream.recv().await.unwrap();
+        assert_eq!(received.message, Message::Heartbeat(heartbeat));
+        assert_eq!(received.source, source);
+
+        let stats = statistics.get(&source).unwrap();
+        assert_eq!(stats.packets, 1);
+        assert_eq!(stats.bytes, packet.len() as u64);
+        assert_eq!(stats.messages, 1);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.crc_mismatches, 1);
+        assert_eq!(stats.types.get("Heartbeat"), Some(&1));
+        assert!(matches!(
+            stats.last_error,
+            Some(GDL90Error::CrcMismatch { .. })
+        ));
+        assert_eq!(statistics.socket_errors(), 0);
+    }
+}
@@ -0,0 +1,412 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use chrono::{DateTime, Utc};
+use socket2::SockRef;
+use tokio::net::UdpSocket;
+
+use crate::prelude::*;
+
+/// Where `UdpSender` sends packets to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Target {
+    /// Single host
+    Unicast(SocketAddr),
+    /// Subnet broadcast address, e.g. `192.168.1.255:4000`
+    Broadcast(SocketAddr),
+    /// IPv4 or IPv6 multicast group
+    Multicast(SocketAddr),
+}
+
+impl Target {
+    #[must_use]
+    pub fn addr(&self) -> SocketAddr {
+        match self {
+            Self::Unicast(addr) | Self::Broadcast(addr) | Self::Multicast(addr) => *addr,
+        }
+    }
+}
+
+/// Counters for a single target
+#[derive(Debug, Clone, Default)]
+pub struct TargetStats {
+    pub packets: u64,
+    pub bytes: u64,
+    /// Failed sends, including `unreachable`
+    pub errors: u64,
+    /// Sends that failed with network or host unreachable
+    pub unreachable: u64,
+    pub last_error: Option<GDL90Error>,
+    pub last_sent: Option<DateTime<Utc>>,
+}
+
+/// Target list of a `UdpSender`, shared so targets can be added and removed at runtime.
+///
+/// Cloning is cheap, all clones see the same targets.
+#[derive(Debug, Clone, Default)]
+pub struct Targets(Arc<Mutex<HashMap<Target, TargetStats>>>);
+
+impl Targets {
+    fn lock(&self) -> MutexGuard<'_, HashMap<Target, TargetStats>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns `false` if the target was already in the list, its statistics are kept
+    pub fn add(&self, target: Target) -> bool {
+        let mut targets = self.lock();
+        if targets.contains_key(&target) {
+            return false;
+        }
+        targets.insert(target, TargetStats::default());
+        true
+    }
+
+    /// Returns `false` if the target wasn't in the list
+    pub fn remove(&self, target: &Target) -> bool {
+        self.lock().remove(target).is_some()
+    }
+
+    #[must_use]
+    pub fn contains(&self, target: &Target) -> bool {
+        self.lock().contains_key(target)
+    }
+
+    #[must_use]
+    pub fn list(&self) -> Vec<Target> {
+        let mut targets = self.lock().keys().copied().collect::<Vec<_>>();
+        targets.sort();
+        targets
+    }
+
+    #[must_use]
+    pub fn stats(&self, target: &Target) -> Option<TargetStats> {
+        self.lock().get(target).cloned()
+    }
+
+    /// Copy of the counters of all targets
+    #[must_use]
+    pub fn snapshot(&self) -> HashMap<Target, TargetStats> {
+        self.lock().clone()
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn record(&self, target: &Target, result: std::io::Result<usize>) {
+        let mut targets = self.lock();
+        // removed while the packet was in flight
+        let Some(stats) = targets.get_mut(target) else {
+            return;
+        };
+
+        match result {
+            Ok(len) => {
+                stats.packets += 1;
+                stats.bytes += len as u64;
+                stats.last_sent = Some(Utc::now());
+            }
+            Err(err) => {
+                stats.errors += 1;
+                if matches!(
+                    err.kind(),
+                    ErrorKind::NetworkUnreachable | ErrorKind::HostUnreachable
+                ) {
+                    stats.unreachable += 1;
+                }
+                stats.last_error = Some(err.into());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Builder)]
+/// `UdpSender` settings
+pub struct UdpSenderConfig {
+    pub bind_v4: SocketAddr,
+
+    /// `None` to send to IPv4 targets only
+    pub bind_v6: Option<SocketAddr>,
+
+    /// Largest packet `send_messages()` builds, messages are never split
+    pub max_packet_size: usize,
+
+    /// IPv4 multicast TTL and IPv6 multicast hop limit
+    pub multicast_ttl: u32,
+
+    /// Receive our own multicast packets on this host
+    pub multicast_loop: bool,
+
+    /// Address of the interface IPv4 multicast is sent on, `None` for the OS default
+    pub multicast_if_v4: Option<Ipv4Addr>,
+
+    /// Index of the interface IPv6 multicast is sent on, `None` for the OS default
+    pub multicast_if_v6: Option<u32>,
+}
+
+impl Default for UdpSenderConfig {
+    fn default() -> Self {
+        Self {
+            bind_v4: SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+            bind_v6: Some(SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0))),
+            max_packet_size: 1400,
+            multicast_ttl: 1,
+            multicast_loop: true,
+            multicast_if_v4: None,
+            multicast_if_v6: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl UdpSenderConfig {
+    /// IPv4 only, bound to localhost on an ephemeral port
+    pub(crate) fn loopback() -> Self {
+        Self {
+            bind_v4: SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+            bind_v6: None,
+            ..Default::default()
+        }
+    }
+}
+
+/// Sends GDL90 packets to every target in its `Targets` list.
+///
+/// A failing target, e.g. with `ENETUNREACH` while an interface is down, is counted in its
+/// `TargetStats` and doesn't affect the others.
+///
+/// ```ignore
+/// let sender = UdpSender::bind(UdpSenderConfig::default()).await?;
+/// sender.targets().add(Target::Broadcast("192.168.1.255:4000".parse()?));
+/// sender.send_messages([heartbeat.into(), ownship.into()]).await?;
+/// ```
+#[derive(Debug)]
+pub struct UdpSender {
+    v4: UdpSocket,
+    v6: Option<UdpSocket>,
+    v6_error: Option<GDL90Error>,
+    config: UdpSenderConfig,
+    targets: Targets,
+}
+
+impl UdpSender {
+    /// # Errors
+    ///
+    /// If the IPv4 socket can't be set up.
+    /// A failing IPv6 socket only disables IPv6 targets, its error is kept in `v6_error()`.
+    pub async fn bind(config: UdpSenderConfig) -> GDL90Result<Self> {
+        let v4 = UdpSocket::bind(config.bind_v4).await?;
+        v4.set_broadcast(true)?;
+        v4.set_multicast_ttl_v4(config.multicast_ttl)?;
+        v4.set_multicast_loop_v4(config.multicast_loop)?;
+        if let Some(interface) = config.multicast_if_v4 {
+            SockRef::from(&v4).set_multicast_if_v4(&interface)?;
+        }
+
+        let (v6, v6_error) = match config.bind_v6 {
+            Some(addr) => match Self::bind_v6(addr, &config).await {
+                Ok(socket) => (Some(socket), None),
+                Err(err) => (None, Some(err.into())),
+            },
+            None => (None, None),
+        };
+
+        Ok(Self {
+            v4,
+            v6,
+            v6_error,
+            config,
+            targets: Targets::default(),
+        })
+    }
+
+    async fn bind_v6(addr: SocketAddr, config: &UdpSenderConfig) -> std::io::Result<UdpSocket> {
+        let socket = UdpSocket::bind(addr).await?;
+        socket.set_multicast_loop_v6(config.multicast_loop)?;
+
+        let sock_ref = SockRef::from(&socket);
+        sock_ref.set_multicast_hops_v6(config.multicast_ttl)?;
+        if let Some(interface) = config.multicast_if_v6 {
+            sock_ref.set_multicast_if_v6(interface)?;
+        }
+
+        Ok(socket)
+    }
+
+    /// Why the IPv6 socket couldn't be set up, `None` if it is or `bind_v6` is `None`
+    #[must_use]
+    pub fn v6_error(&self) -> Option<&GDL90Error> {
+        self.v6_error.as_ref()
+    }
+
+    /// Shared target list, see `Targets`
+    #[must_use]
+    pub fn targets(&self) -> Targets {
+        self.targets.clone()
+    }
+
+    #[must_use]
+    pub fn config(&self) -> &UdpSenderConfig {
+        &self.config
+    }
+
+    #[must_use]
+    pub fn with_targets(mut self, targets: Targets) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    /// # Errors
+    ///
+    /// If the IPv4 socket has no local address.
+    pub fn local_addr(&self) -> GDL90Result<SocketAddr> {
+        Ok(self.v4.local_addr()?)
+    }
+
+    /// Send an already encoded packet to all targets.
+    ///
+    /// Returns the number of targets the packet was sent to, failures are in `TargetStats`.
+    pub async fn send(&self, packet: impl AsRef<[u8]>) -> usize {
+        let packet = packet.as_ref();
+        let mut sent = 0;
+
+        for target in self.targets.list() {
+            sent += usize::from(self.send_to(&target, packet).await);
+        }
+
+        sent
+    }
+
+    /// Send an already encoded packet to a single target, e.g. with content specific to it.
+    ///
+    /// Counted in `TargetStats` if the target is in the `Targets` list.
+    pub async fn send_to(&self, target: &Target, packet: impl AsRef<[u8]>) -> bool {
+        let packet = packet.as_ref();
+        let result = match (target.addr(), &self.v6) {
+            (SocketAddr::V4(addr), _) => self.v4.send_to(packet, addr).await,
+            (SocketAddr::V6(addr), Some(v6)) => v6.send_to(packet, addr).await,
+            (SocketAddr::V6(_), None) => Err(ErrorKind::AddrNotAvailable.into()),
+        };
+
+        let sent = result.is_ok();
+        self.targets.record(target, result);
+        sent
+    }
+
+    /// Encode the messages and send them to all targets.
+    ///
+    /// Messages are concatenated into as few packets of at most `max_packet_size` bytes as possible.
+    ///
+    /// # Errors
+    ///
+    /// If a message can't be encoded, nothing is sent in that case.
+    pub async fn send_messages<M: Into<Message>>(
+        &self,
+        messages: impl IntoIterator<Item = M>,
+    ) -> GDL90Result<()> {
+        for packet in batch_packets(messages, self.config.max_packet_size)? {
+            self.send(packet).await;
+        }
+        Ok(())
+    }
+}
+
+/// Encode messages into packets of at most `max_packet_size` bytes.
+///
+/// A single message longer than `max_packet_size` gets a packet of its own.
+///
+/// # Errors
+///
+/// If a message can't be encoded.
+pub fn batch_packets<M: Into<Message>>(
+    messages: impl IntoIterator<Item = M>,
+    max_packet_size: usize,
+) -> GDL90Result<Vec<Vec<u8>>> {
+    let mut packets: Vec<Vec<u8>> = vec![];
+
+    for message in messages {
+        let bytes = message.into_gdl90_bytes()?;
+        match packets.last_mut() {
+            Some(packet) if packet.len() + bytes.len() <= max_packet_size => {
+                packet.extend(bytes);
+            }
+            _ => packets.push(bytes),
+        }
+    }
+
+    Ok(packets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batching() {
+        let messages = || [new_heartbeat(), new_heartbeat(), new_heartbeat()];
+        let len = new_heartbeat().into_gdl90_bytes().unwrap().len();
+
+        assert_eq!(batch_packets(messages(), 1500).unwrap().len(), 1);
+        assert_eq!(batch_packets(messages(), len * 2).unwrap().len(), 2);
+        assert_eq!(batch_packets(messages(), 1).unwrap().len(), 3);
+        assert!(batch_packets(Vec::<Message>::new(), 1).unwrap().is_empty());
+    }
+
+    fn new_heartbeat() -> Message {
+        Heartbeat::default().with_utc_ok().into()
+    }
+
+    #[tokio::test]
+    async fn send_to_targets() {
+        let receiver = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let good = Target::Unicast(receiver.local_addr().unwrap());
+        let no_v6 = Target::Unicast(SocketAddr::from((Ipv6Addr::LOCALHOST, 4000)));
+
+        let sender = UdpSender::bind(UdpSenderConfig::loopback()).await.unwrap();
+        assert!(sender.v6_error().is_none());
+        let targets = sender.targets();
+        assert!(targets.add(good));
+        assert!(!targets.add(good));
+        assert!(targets.add(no_v6));
+
+        sender.send_messages([new_heartbeat()]).await.unwrap();
+
+        let mut buf = [0; 64];
+        let len = receiver.recv(&mut buf).await.unwrap();
+        assert_eq!(
+            Message::from_gdl90_bytes(&buf[..len])[0].as_ref().unwrap(),
+            &new_heartbeat()
+        );
+
+        let stats = targets.stats(&good).unwrap();
+        assert_eq!(stats.packets, 1);
+        assert_eq!(stats.bytes, len as u64);
+        assert_eq!(stats.errors, 0);
+
+        let stats = targets.stats(&no_v6).unwrap();
+        assert_eq!(stats.packets, 0);
+        assert_eq!(stats.errors, 1);
+        assert!(matches!(stats.last_error, Some(GDL90Error::Io(_))));
+
+        assert!(targets.remove(&no_v6));
+        assert_eq!(sender.send(b"\x7E\x7E").await, 1);
+        assert_eq!(targets.list(), vec![good]);
+    }
+
+    #[tokio::test]
+    async fn v6_bind_error() {
+        let config = UdpSenderConfig::default()
+            .with_bind_v4(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            // documentation prefix, never assigned to a local interface
+            .with_bind_v6(SocketAddr::from(([0x2001, 0xDB8, 0, 0, 0, 0, 0, 1], 0)));
+        let sender = UdpSender::bind(config).await.unwrap();
+        assert!(matches!(sender.v6_error(), Some(GDL90Error::Io(_))));
+
+        let target = Target::Unicast(SocketAddr::from((Ipv6Addr::LOCALHOST, 4000)));
+        assert!(!sender.send_to(&target, b"\x7E\x7E").await);
+    }
+}
@@ -0,0 +1,405 @@
+use std::{
+    collections::HashSet,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::SecondsFormat;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::broadcast,
+    task::JoinHandle,
+};
+use tokio_tungstenite::tungstenite::{
+    self,
+    handshake::server::{ErrorResponse, Request, Response},
+};
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Builder)]
+/// `WebSocketBridge` settings, `WebSocketConfig::default()` listens on `127.0.0.1:8080`
+pub struct WebSocketConfig {
+    pub bind_addr: SocketAddr,
+
+    /// Messages queued per client, a client that falls further behind skips messages
+    pub client_queue: usize,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from((Ipv4Addr::LOCALHOST, 8080)),
+            client_queue: 1024,
+        }
+    }
+}
+
+/// Message types a client receives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subscription {
+    All,
+    /// `Message::type_name()`s
+    Types(HashSet<String>),
+}
+
+impl Subscription {
+    /// From the request path:
+    /// - `/traffic`: traffic reports, like the Stratux feed
+    /// - `/messages?types=Heartbeat,Traffic`: the listed types
+    /// - anything else: everything
+    #[must_use]
+    pub fn from_path(path: &str) -> Self {
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        if path.trim_end_matches('/') == "/traffic" {
+            return Self::types(["Traffic"]);
+        }
+
+        query
+            .split('&')
+            .find_map(|param| param.strip_prefix("types="))
+            .map_or(Self::All, |types| Self::types(types.split(',')))
+    }
+
+    /// `"*"` subscribes to everything
+    #[must_use]
+    pub fn types<S: AsRef<str>>(types: impl IntoIterator<Item = S>) -> Self {
+        let types = types
+            .into_iter()
+            .map(|t| t.as_ref().trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect::<HashSet<_>>();
+
+        if types.contains("*") {
+            Self::All
+        } else {
+            Self::Types(types)
+        }
+    }
+
+    #[must_use]
+    pub fn contains(&self, type_name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Types(types) => types.contains(type_name),
+        }
+    }
+}
+
+/// An encoded message, shared by all clients
+#[derive(Debug)]
+struct Event {
+    type_name: &'static str,
+    json: String,
+}
+
+/// Sends messages to all clients of a `WebSocketBridge`.
+///
+/// Cloning is cheap, all clones send to the same clients.
+#[derive(Debug, Clone)]
+pub struct WebSocketPublisher {
+    events: broadcast::Sender<Arc<Event>>,
+    clients: Arc<AtomicUsize>,
+}
+
+impl WebSocketPublisher {
+    /// Connected clients
+    #[must_use]
+    pub fn clients(&self) -> usize {
+        self.clients.load(Ordering::Relaxed)
+    }
+
+    /// Queue the message for all clients, see `message_json()`
+    pub fn publish(&self, received: &ReceivedMessage) {
+        let event = Event {
+            type_name: received.message.type_name(),
+            json: message_json(received).to_string(),
+        };
+        // no clients is fine
+        let _ = self.events.send(Arc::new(event));
+    }
+}
+
+/// Serves decoded messages as JSON over WebSocket, e.g. for browser dashboards.
+///
+/// Clients pick the message types in the URL, see `Subscription::from_path()`,
+/// and can change them by sending `{"subscribe": ["Heartbeat", "Traffic"]}`.
+///
+/// ```ignore
+/// let (stream, _receiver) = UdpReceiver::bind(UdpReceiverConfig::default()).await?.spawn();
+/// let bridge = WebSocketBridge::bind(WebSocketConfig::default()).await?;
+/// let _task = bridge.spawn(stream);
+/// // ws://127.0.0.1:8080/traffic
+/// ```
+#[derive(Debug)]
+pub struct WebSocketBridge {
+    listener: TcpListener,
+    publisher: WebSocketPublisher,
+}
+
+impl WebSocketBridge {
+    /// # Errors
+    ///
+    /// If the listener can't be bound.
+    pub async fn bind(config: WebSocketConfig) -> GDL90Result<Self> {
+        let listener = TcpListener::bind(config.bind_addr).await?;
+        let (events, _) = broadcast::channel(config.client_queue);
+
+        Ok(Self {
+            listener,
+            publisher: WebSocketPublisher {
+                events,
+                clients: Arc::default(),
+            },
+        })
+    }
+
+    /// # Errors
+    ///
+    /// If the listener has no local address.
+    pub fn local_addr(&self) -> GDL90Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    #[must_use]
+    pub fn publisher(&self) -> WebSocketPublisher {
+        self.publisher.clone()
+    }
+
+    /// Accept clients and publish `messages` on the tokio runtime.
+    ///
+    /// Keeps accepting clients after `messages` is closed, until the task is aborted.
+    #[must_use]
+    pub fn spawn(self, mut messages: MessageStream) -> JoinHandle<()> {
+        let publisher = self.publisher.clone();
+        tokio::spawn(async move {
+            while let Some(received) = messages.recv().await {
+                publisher.publish(&received);
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = self.listener.accept().await else {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                };
+                tokio::spawn(serve(stream, self.publisher.clone()));
+            }
+        })
+    }
+}
+
+/// Connection of a `WebSocketBridge` client
+async fn serve(stream: TcpStream, publisher: WebSocketPublisher) {
+    let mut subscription = Subscription::All;
+    let handshake = tokio_tungstenite::accept_hdr_async(
+        stream,
+        |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+            subscription = Subscription::from_path(&request.uri().to_string());
+            Ok(response)
+        },
+    )
+    .await;
+    let Ok(socket) = handshake else {
+        return;
+    };
+
+    let mut events = publisher.events.subscribe();
+    publisher.clients.fetch_add(1, Ordering::Relaxed);
+    let (mut writer, mut reader) = socket.split();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if !subscription.contains(event.type_name) {
+                        continue;
+                    }
+                    let message = tungstenite::Message::text(event.json.as_str());
+                    if writer.send(message).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = reader.next() => match message {
+                Some(Ok(tungstenite::Message::Text(text))) => {
+                    if let Some(types) = serde_json::from_str::<Value>(text.as_str())
+                        .ok()
+                        .and_then(|request| request.get("subscribe").cloned())
+                        .and_then(|types| serde_json::from_value::<Vec<String>>(types).ok())
+                    {
+                        subscription = Subscription::types(types);
+                    }
+                }
+                Some(Ok(tungstenite::Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    publisher.clients.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// JSON object with `type`, `message_id`, `sub_id`, `source`, `received_at` (RFC 3339) and `fields`.
+///
+/// `fields` is the message as the `serde` feature serializes it, without the enum variant around it,
+/// so field names carry their unit, e.g. `altitude_ft`, `latitude_deg`, `horizontal_velocity_kt`.
+/// Unavailable values are `null`, enums are their variant name.
+/// Messages without fields, including extensions, have an empty object.
+#[must_use]
+pub fn message_json(received: &ReceivedMessage) -> Value {
+    let message = &received.message;
+    json!({
+        "type": message.type_name(),
+        "message_id": message.message_id(),
+        "sub_id": message.sub_id(),
+        "source": received.source.to_string(),
+        "received_at": received.received_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+        "fields": fields(message),
+    })
+}
+
+fn fields(message: &Message) -> Value {
+    // externally tagged, ForeFlight and custom messages are an enum in an enum
+    let untag = |value: Value| match value {
+        Value::Object(map) if map.len() == 1 => map.into_iter().next().map(|(_, value)| value),
+        _ => None,
+    };
+
+    let value = serde_json::to_value(message).ok().and_then(untag);
+    let value = match message {
+        Message::ForeFlight(_) | Message::Custom(_) => value.and_then(untag),
+        _ => value,
+    };
+    value.filter(Value::is_object).unwrap_or_else(|| json!({}))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+    use tokio_tungstenite::connect_async;
+
+    use super::*;
+
+    fn traffic() -> ReceivedMessage {
+        let report = TrafficReport::default()
+            .with_target_identity(TargetIdentity::new(AddressType::AdsbIcao, 0xAB4549))
+            .with_latitude(44.90708.degrees())
+            .with_altitude(5000.0.feet())
+            .with_callsign("N825V".into());
+
+        ReceivedMessage {
+            message: report.traffic().into(),
+            source: Source::Udp(SocketAddr::from((Ipv4Addr::LOCALHOST, 4000))),
+            received_at: DateTime::<Utc>::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn json_fields() {
+        let json = message_json(&traffic());
+        assert_eq!(json["type"], "Traffic");
+        assert_eq!(json["message_id"], 20);
+        assert_eq!(json["source"], "udp://127.0.0.1:4000");
+        assert_eq!(json["received_at"], "1970-01-01T00:00:00.000Z");
+
+        let fields = &json["fields"];
+        assert_eq!(fields["target_identity"]["participant_address"], 0xAB4549);
+        assert_eq!(fields["target_identity"]["address_type"], "AdsbIcao");
+        assert_eq!(fields["altitude_ft"], 5000.0);
+        assert_eq!(fields["callsign"], "N825V");
+        assert!(fields["horizontal_velocity_kt"].is_null());
+        assert!((fields["latitude_deg"].as_f64().unwrap() - 44.90708).abs() < 1e-9);
+
+        // same schema as the serde impls
+        let Message::Traffic(TrafficMessage(report)) = traffic().message else {
+            unreachable!()
+        };
+        assert_eq!(*fields, serde_json::to_value(report).unwrap());
+
+        let uplink = ReceivedMessage {
+            message: UplinkData::default()
+                .with_time_of_reception(Duration::from_millis(1500))
+                .into(),
+            ..traffic()
+        };
+        let fields = &message_json(&uplink)["fields"];
+        assert_eq!(fields["time_of_reception_ns"], 1_500_000_000);
+        assert!(fields["uplink_payload"].is_string());
+
+        let ahrs = ReceivedMessage {
+            message: ForeFlightAHRS::default().into(),
+            ..traffic()
+        };
+        assert!(message_json(&ahrs)["fields"].get("heading_type").is_some());
+        let basic = ReceivedMessage {
+            message: Message::BasicReport,
+            ..traffic()
+        };
+        assert_eq!(message_json(&basic)["fields"], json!({}));
+    }
+
+    #[test]
+    fn subscriptions() {
+        assert_eq!(Subscription::from_path("/"), Subscription::All);
+        assert_eq!(
+            Subscription::from_path("/traffic"),
+            Subscription::types(["Traffic"])
+        );
+        let selected = Subscription::from_path("/messages?x=1&types=Heartbeat,Ownship");
+        assert!(selected.contains("Heartbeat") && selected.contains("Ownship"));
+        assert!(!selected.contains("Traffic"));
+        assert_eq!(Subscription::types(["Heartbeat", "*"]), Subscription::All);
+    }
+
+    #[tokio::test]
+    async fn bridge() {
+        let config =
+            WebSocketConfig::default().with_bind_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)));
+        let bridge = WebSocketBridge::bind(config).await.unwrap();
+        let addr = bridge.local_addr().unwrap();
+        let publisher = bridge.publisher();
+        let (tx, rx) = tokio::sync::mpsc::channel(8);
+        let _task = bridge.spawn(rx);
+
+        let (mut client, _) = connect_async(format!("ws://{addr}/traffic")).await.unwrap();
+        while publisher.clients() < 1 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let heartbeat = ReceivedMessage {
+            message: Heartbeat::default().into(),
+            ..traffic()
+        };
+        tx.send(heartbeat.clone()).await.unwrap();
+        tx.send(traffic()).await.unwrap();
+
+        let text = client.next().await.unwrap().unwrap().into_text().unwrap();
+        let json: Value = serde_json::from_str(text.as_str()).unwrap();
+        assert_eq!(json, message_json(&traffic()));
+
+        client
+            .send(tungstenite::Message::text(
+                r#"{"subscribe": ["Heartbeat"]}"#,
+            ))
+            .await
+            .unwrap();
+        // the subscription is applied before the next message arrives
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        tx.send(traffic()).await.unwrap();
+        tx.send(heartbeat.clone()).await.unwrap();
+
+        let text = client.next().await.unwrap().unwrap().into_text().unwrap();
+        let json: Value = serde_json::from_str(text.as_str()).unwrap();
+        assert_eq!(json["type"], "Heartbeat");
+    }
+}
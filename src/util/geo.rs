@@ -0,0 +1,121 @@
+use crate::prelude::*;
+
+/// Mean earth radius (IUGG)
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// Great circle distance between two positions
+#[must_use]
+pub fn distance(lat1: Angle, lon1: Angle, lat2: Angle, lon2: Angle) -> Length {
+    let (lat1, lat2) = (lat1.radians(), lat2.radians());
+    let dlat = lat2 - lat1;
+    let dlon = (lon2 - lon1).radians();
+
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    (2.0 * EARTH_RADIUS_M * a.sqrt().asin()).meters()
+}
+
+/// North and east offset of position 2 from position 1.
+///
+/// Flat earth approximation, within a few meters up to a few tens of kilometers.
+#[must_use]
+pub fn relative_position(lat1: Angle, lon1: Angle, lat2: Angle, lon2: Angle) -> (Length, Length) {
+    let dlat = (lat2 - lat1).radians();
+    let dlon = ((lon2 - lon1).degrees() + 540.0).rem_euclid(360.0) - 180.0;
+    let mean_lat = (lat1.radians() + lat2.radians()) / 2.0;
+
+    (
+        (dlat * EARTH_RADIUS_M).meters(),
+        (dlon.to_radians() * mean_lat.cos() * EARTH_RADIUS_M).meters(),
+    )
+}
+
+/// Position `north` and `east` of position 1, the inverse of `relative_position()`
+#[must_use]
+pub fn offset_position(lat1: Angle, lon1: Angle, north: Length, east: Length) -> (Angle, Angle) {
+    let lat2 = lat1.radians() + north.meters() / EARTH_RADIUS_M;
+    let mean_lat = (lat1.radians() + lat2) / 2.0;
+    let lon2 = lon1.degrees() + (east.meters() / (EARTH_RADIUS_M * mean_lat.cos())).to_degrees();
+
+    (
+        lat2.to_degrees().degrees(),
+        ((lon2 + 540.0).rem_euclid(360.0) - 180.0).degrees(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let zero = 0.0.degrees();
+        // one minute of latitude is a nautical mile, give or take (epsilons are in meters)
+        let nm = distance(zero, zero, (1.0 / 60.0).degrees(), zero);
+        assert_eq_f!(nm, 1.0.nautical_miles(), 2.0);
+
+        // KPDX - KSEA
+        let d = distance(
+            45.5887.degrees(),
+            (-122.5975).degrees(),
+            47.4502.degrees(),
+            (-122.3088).degrees(),
+        );
+        assert_eq_f!(d, 208.16.kilometers(), 10.0);
+
+        let d = distance(zero, 179.9.degrees(), zero, (-179.9).degrees());
+        assert_eq_f!(d, 22.239.kilometers(), 1.0);
+    }
+
+    #[test]
+    fn relative_positions() {
+        let (north, east) = relative_position(
+            45.0.degrees(),
+            (-122.0).degrees(),
+            45.01.degrees(),
+            (-122.01).degrees(),
+        );
+        assert_eq_f!(north, 1112.0.meters(), 1.0);
+        assert_eq_f!(east, (-786.2).meters(), 1.0);
+
+        let d = distance(
+            45.0.degrees(),
+            (-122.0).degrees(),
+            45.01.degrees(),
+            (-122.01).degrees(),
+        );
+        assert_eq_f!(north.hypot(east), d, 1.0);
+
+        let (north, east) = relative_position(
+            0.0.degrees(),
+            179.9.degrees(),
+            0.0.degrees(),
+            (-179.9).degrees(),
+        );
+        assert_eq_f!(north, 0.0.meters(), 1e-6);
+        assert_eq_f!(east, 22.239.kilometers(), 1.0);
+    }
+
+    #[test]
+    fn offset_positions() {
+        let (lat, lon) = offset_position(
+            45.0.degrees(),
+            (-122.0).degrees(),
+            1112.0.meters(),
+            (-786.2).meters(),
+        );
+        assert_eq_f!(lat, 45.01.degrees(), 1e-6);
+        assert_eq_f!(lon, (-122.01).degrees(), 1e-6);
+
+        let (north, east) = relative_position(45.0.degrees(), (-122.0).degrees(), lat, lon);
+        assert_eq_f!(north, 1112.0.meters(), 1e-6);
+        assert_eq_f!(east, (-786.2).meters(), 1e-6);
+
+        let (_, lon) = offset_position(
+            0.0.degrees(),
+            179.9.degrees(),
+            0.0.meters(),
+            22.239.kilometers(),
+        );
+        assert_eq_f!(lon, (-179.9).degrees(), 1e-4);
+    }
+}
@@ -1,6 +1,9 @@
+pub mod geo;
+#[cfg(feature = "serde")]
+pub mod serde_fields;
 pub mod uom_utils;
 
-pub use self::uom_utils::*;
+pub use self::{geo::*, uom_utils::*};
 
 macro_rules! impl_clamp_into {
     [$(($from:ty, $to:ty)),*] => {
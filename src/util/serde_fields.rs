@@ -0,0 +1,133 @@
+//! `#[serde(with = "..")]` adapters for the message fields, enabled with the `serde` feature.
+//!
+//! `uom` quantities are written as plain numbers in the unit the field is renamed to
+//! (`altitude_ft`, `latitude_deg`, ...), independent of how `uom` stores them.
+//! The units are the ones the GDL90 encoding uses, so a decoded message survives
+//! serialization and re-encodes to the same bytes.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use crate::prelude::*;
+
+macro_rules! unit_fields {
+    ($($unit:ident, $option:ident: $uom_type:ty;)*) => {$(
+        #[doc = concat!("`", stringify!($uom_type), "` as `f64` ", stringify!($unit))]
+        pub mod $unit {
+            use super::*;
+
+            /// # Errors
+            ///
+            /// If the serializer fails.
+            pub fn serialize<S: Serializer>(value: &$uom_type, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_f64(value.$unit())
+            }
+
+            /// # Errors
+            ///
+            /// If the value is not a number.
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<$uom_type, D::Error> {
+                f64::deserialize(deserializer).map(IntoUom::$unit)
+            }
+        }
+
+        #[doc = concat!("`Option<", stringify!($uom_type), ">` as `f64` ", stringify!($unit), " or `null`")]
+        pub mod $option {
+            use super::*;
+
+            /// # Errors
+            ///
+            /// If the serializer fails.
+            pub fn serialize<S: Serializer>(value: &Option<$uom_type>, serializer: S) -> Result<S::Ok, S::Error> {
+                value.map(FromUom::$unit).serialize(serializer)
+            }
+
+            /// # Errors
+            ///
+            /// If the value is neither a number nor `null`.
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<$uom_type>, D::Error> {
+                Option::<f64>::deserialize(deserializer).map(|value| value.map(IntoUom::$unit))
+            }
+        }
+    )*};
+}
+
+unit_fields! {
+    degrees, option_degrees: Angle;
+    feet, option_feet: Length;
+    meters, option_meters: Length;
+    knots, option_knots: Velocity;
+    feet_per_minute, option_feet_per_minute: Velocity;
+}
+
+/// `Option<Duration>` as integer nanoseconds or `null`
+pub mod option_nanoseconds {
+    use super::*;
+
+    /// # Errors
+    ///
+    /// If the serializer fails.
+    pub fn serialize<S: Serializer>(
+        value: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value
+            .map(|duration| u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX))
+            .serialize(serializer)
+    }
+
+    /// # Errors
+    ///
+    /// If the value is neither an unsigned integer nor `null`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<u64>::deserialize(deserializer).map(|value| value.map(Duration::from_nanos))
+    }
+}
+
+/// Byte arrays as an uppercase hex string, e.g. `"7E00FF"`
+pub mod hex {
+    use super::*;
+
+    /// # Errors
+    ///
+    /// If the serializer fails.
+    pub fn serialize<S: Serializer>(
+        value: impl AsRef<[u8]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode(value))
+    }
+
+    /// # Errors
+    ///
+    /// If the value is not a hex string of exactly `N` bytes.
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        let bytes = decode(&String::deserialize(deserializer)?).map_err(D::Error::custom)?;
+        let len = bytes.len();
+        bytes
+            .try_into()
+            .map_err(|_| D::Error::custom(format!("expected {N} bytes, got {len}")))
+    }
+
+    pub(crate) fn encode(bytes: impl AsRef<[u8]>) -> String {
+        bytes.as_ref().iter().map(|b| format!("{b:02X}")).collect()
+    }
+
+    pub(crate) fn decode(hex: &str) -> Result<Vec<u8>, String> {
+        if hex.len() % 2 != 0 || !hex.is_ascii() {
+            return Err(format!("invalid hex string: {hex:?}"));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                u8::from_str_radix(&hex[i..i + 2], 16)
+                    .map_err(|_| format!("invalid hex string: {hex:?}"))
+            })
+            .collect()
+    }
+}